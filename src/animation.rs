@@ -0,0 +1,311 @@
+use crate::component::{AnimationPlayer, Transform};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Interpolation {
+    Step,
+    Linear,
+    // Catmull-Rom through the neighbouring keyframes
+    Cubic,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Easing {
+    Linear,
+    QuadIn,
+    QuadOut,
+    QuadInOut,
+    CubicIn,
+    CubicOut,
+    CubicInOut,
+    SineInOut,
+}
+
+impl Easing {
+    pub fn apply(&self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+
+        match self {
+            Easing::Linear => t,
+            Easing::QuadIn => t * t,
+            Easing::QuadOut => t * (2.0 - t),
+            Easing::QuadInOut => match t < 0.5 {
+                true => 2.0 * t * t,
+                false => 1.0 - (-2.0 * t + 2.0).powi(2) * 0.5,
+            },
+            Easing::CubicIn => t * t * t,
+            Easing::CubicOut => 1.0 - (1.0 - t).powi(3),
+            Easing::CubicInOut => match t < 0.5 {
+                true => 4.0 * t * t * t,
+                false => 1.0 - (-2.0 * t + 2.0).powi(3) * 0.5,
+            },
+            Easing::SineInOut => -((std::f32::consts::PI * t).cos() - 1.0) * 0.5,
+        }
+    }
+}
+
+// Values that can be stored in a track
+pub trait Animatable:
+    Copy
+    + std::ops::Add<Output = Self>
+    + std::ops::Sub<Output = Self>
+    + std::ops::Mul<f32, Output = Self>
+{
+    fn lerp(&self, other: &Self, t: f32) -> Self {
+        *self + (*other - *self) * t
+    }
+
+    // Brings `self` next to `reference` before both are combined
+    fn align(&self, _reference: &Self) -> Self {
+        *self
+    }
+
+    fn normalized(&self) -> Self {
+        *self
+    }
+}
+
+impl Animatable for f32 {}
+
+impl Animatable for nalgebra_glm::Vec2 {}
+
+impl Animatable for nalgebra_glm::Vec3 {}
+
+impl Animatable for nalgebra_glm::Vec4 {}
+
+impl Animatable for nalgebra_glm::Quat {
+    fn lerp(&self, other: &Self, t: f32) -> Self {
+        nalgebra::UnitQuaternion::new_normalize(*self)
+            .slerp(&nalgebra::UnitQuaternion::new_normalize(*other), t)
+            .into_inner()
+    }
+
+    // `q` and `-q` are the same rotation, take the one on the short path
+    fn align(&self, reference: &Self) -> Self {
+        match self.dot(reference) < 0.0 {
+            true => -*self,
+            false => *self,
+        }
+    }
+
+    fn normalized(&self) -> Self {
+        self.normalize()
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Keyframe<T: Animatable> {
+    pub time: f32,
+    pub value: T,
+}
+
+#[derive(Debug, Clone)]
+pub struct Track<T: Animatable> {
+    // Sorted by time
+    keyframes: Vec<Keyframe<T>>,
+    pub interpolation: Interpolation,
+    // Applied between every pair of keyframes
+    pub easing: Easing,
+}
+
+impl<T: Animatable> Track<T> {
+    pub fn new(interpolation: Interpolation) -> Self {
+        Self {
+            keyframes: vec![],
+            interpolation,
+            easing: Easing::Linear,
+        }
+    }
+
+    pub fn with_easing(mut self, easing: Easing) -> Self {
+        self.easing = easing;
+        self
+    }
+
+    pub fn with_keyframe(mut self, time: f32, value: T) -> Self {
+        self.insert(time, value);
+        self
+    }
+
+    // A keyframe at the same time is replaced
+    pub fn insert(&mut self, time: f32, value: T) {
+        match self
+            .keyframes
+            .binary_search_by(|keyframe| keyframe.time.total_cmp(&time))
+        {
+            Ok(index) => self.keyframes[index].value = value,
+            Err(index) => self.keyframes.insert(index, Keyframe { time, value }),
+        }
+    }
+
+    pub fn duration(&self) -> f32 {
+        self.keyframes.last().map_or(0.0, |keyframe| keyframe.time)
+    }
+
+    // Before the first and after the last keyframe the track holds its value
+    pub fn sample(&self, time: f32) -> Option<T> {
+        let first = self.keyframes.first()?;
+        let last = self.keyframes.last()?;
+
+        if time <= first.time {
+            return Some(first.value);
+        }
+        if time >= last.time {
+            return Some(last.value);
+        }
+
+        let next = self
+            .keyframes
+            .partition_point(|keyframe| keyframe.time <= time);
+        let current = next - 1;
+
+        let a = &self.keyframes[current];
+        let b = &self.keyframes[next];
+        let t = self.easing.apply((time - a.time) / (b.time - a.time));
+
+        Some(match self.interpolation {
+            Interpolation::Step => a.value,
+            Interpolation::Linear => a.value.lerp(&b.value.align(&a.value), t),
+            Interpolation::Cubic => self.cubic(current, t),
+        })
+    }
+
+    // Hermite spline with Catmull-Rom tangents, scaled for uneven keyframe spacing
+    fn cubic(&self, index: usize, t: f32) -> T {
+        let a = &self.keyframes[index];
+        let b = &self.keyframes[index + 1];
+        let span = b.time - a.time;
+
+        let p1 = a.value;
+        let p2 = b.value.align(&p1);
+        let p0 = match index.checked_sub(1) {
+            Some(i) => self.keyframes[i].value.align(&p1),
+            None => p1,
+        };
+        let p3 = match self.keyframes.get(index + 2) {
+            Some(keyframe) => keyframe.value.align(&p2),
+            None => p2,
+        };
+        let t0 = index
+            .checked_sub(1)
+            .map_or(a.time - span, |i| self.keyframes[i].time);
+        let t3 = self
+            .keyframes
+            .get(index + 2)
+            .map_or(b.time + span, |keyframe| keyframe.time);
+
+        let m1 = (p2 - p0) * (span / (b.time - t0));
+        let m2 = (p3 - p1) * (span / (t3 - a.time));
+
+        let t2 = t * t;
+        let t3 = t2 * t;
+
+        (p1 * (2.0 * t3 - 3.0 * t2 + 1.0)
+            + m1 * (t3 - 2.0 * t2 + t)
+            + p2 * (-2.0 * t3 + 3.0 * t2)
+            + m2 * (t3 - t2))
+            .normalized()
+    }
+}
+
+// Sampled values of a clip, tracks that are missing leave the transform untouched
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Pose {
+    pub position: Option<nalgebra_glm::Vec3>,
+    pub rotation: Option<nalgebra_glm::Quat>,
+    pub scale: Option<nalgebra_glm::Vec3>,
+}
+
+impl Pose {
+    pub fn blend(&self, other: &Pose, weight: f32) -> Pose {
+        fn blend<T: Animatable>(a: Option<T>, b: Option<T>, weight: f32) -> Option<T> {
+            match (a, b) {
+                (Some(a), Some(b)) => Some(a.lerp(&b.align(&a), weight)),
+                (a, b) => a.or(b),
+            }
+        }
+
+        Pose {
+            position: blend(self.position, other.position, weight),
+            rotation: blend(self.rotation, other.rotation, weight),
+            scale: blend(self.scale, other.scale, weight),
+        }
+    }
+
+    pub fn apply(&self, transform: &mut Transform) {
+        if let Some(position) = self.position {
+            transform.set_position(&position);
+        }
+        if let Some(rotation) = self.rotation {
+            transform.set_rotation(&rotation);
+        }
+        if let Some(scale) = self.scale {
+            transform.set_scale(&scale);
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct AnimationClip {
+    pub name: String,
+    pub position: Option<Track<nalgebra_glm::Vec3>>,
+    pub rotation: Option<Track<nalgebra_glm::Quat>>,
+    pub scale: Option<Track<nalgebra_glm::Vec3>>,
+}
+
+impl AnimationClip {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_owned(),
+            position: None,
+            rotation: None,
+            scale: None,
+        }
+    }
+
+    pub fn with_position(mut self, track: Track<nalgebra_glm::Vec3>) -> Self {
+        self.position = Some(track);
+        self
+    }
+
+    pub fn with_rotation(mut self, track: Track<nalgebra_glm::Quat>) -> Self {
+        self.rotation = Some(track);
+        self
+    }
+
+    pub fn with_scale(mut self, track: Track<nalgebra_glm::Vec3>) -> Self {
+        self.scale = Some(track);
+        self
+    }
+
+    // The end of the longest track
+    pub fn duration(&self) -> f32 {
+        [
+            self.position.as_ref().map(Track::duration),
+            self.rotation.as_ref().map(Track::duration),
+            self.scale.as_ref().map(Track::duration),
+        ]
+        .into_iter()
+        .flatten()
+        .fold(0.0, f32::max)
+    }
+
+    pub fn sample(&self, time: f32) -> Pose {
+        Pose {
+            position: self.position.as_ref().and_then(|track| track.sample(time)),
+            rotation: self.rotation.as_ref().and_then(|track| track.sample(time)),
+            scale: self.scale.as_ref().and_then(|track| track.sample(time)),
+        }
+    }
+}
+
+// Advances every player and writes its pose into the transform of the entity
+pub fn update(world: &mut hecs::World, delta: f32) {
+    world
+        .query_mut::<(&mut AnimationPlayer, &mut Transform)>()
+        .into_iter()
+        .for_each(|(_, (player, transform))| {
+            if let Some(pose) = player.advance(delta) {
+                pose.apply(transform);
+            }
+        });
+}
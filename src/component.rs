@@ -1,12 +1,23 @@
+pub mod animation;
 pub mod camera;
-// pub mod input;
+pub mod camera_controller;
+pub mod hierarchy;
+pub mod input;
+pub mod light;
+pub mod name;
 pub mod render;
+pub mod skin;
 pub mod transform;
 
-pub use camera::{Camera, CameraType};
+pub use animation::AnimationPlayer;
+pub use camera::{Camera, CameraOutput, CameraTarget, CameraType, FrameTime, Viewport};
+pub use camera_controller::{CameraController, CameraInput, ControllerMode};
+pub use hierarchy::{Children, Parent};
+pub use light::{Light, LightType};
+pub use name::Name;
 pub use render::Render;
+pub use skin::Skin;
 
+pub use transform::GlobalTransform;
 pub use transform::Transform;
 pub use transform::TransformBuild;
-
-pub type TransformType = std::sync::Arc<std::sync::Mutex<Transform>>;
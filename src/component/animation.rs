@@ -0,0 +1,228 @@
+use std::sync::Arc;
+
+use crate::animation::{AnimationClip, Pose};
+
+#[derive(Debug, Clone)]
+struct Playback {
+    clip: Arc<AnimationClip>,
+    time: f32,
+}
+
+impl Playback {
+    // Returns false once a clip that doesn't loop reached its end
+    fn advance(&mut self, delta: f32, looping: bool) -> bool {
+        let duration = self.clip.duration();
+        self.time += delta;
+
+        if looping && duration > 0.0 {
+            self.time = self.time.rem_euclid(duration);
+            return true;
+        }
+
+        let finished = self.time >= duration && delta > 0.0 || self.time <= 0.0 && delta < 0.0;
+        self.time = self.time.clamp(0.0, duration);
+        !finished
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Fade {
+    from: Playback,
+    elapsed: f32,
+    duration: f32,
+}
+
+// Plays clips on the `Transform` of its entity, see `crate::animation::update`
+#[derive(Debug, Clone)]
+pub struct AnimationPlayer {
+    current: Option<Playback>,
+    fade: Option<Fade>,
+    playing: bool,
+    pub speed: f32,
+    pub looping: bool,
+}
+
+impl Default for AnimationPlayer {
+    fn default() -> Self {
+        Self {
+            current: None,
+            fade: None,
+            playing: false,
+            speed: 1.0,
+            looping: true,
+        }
+    }
+}
+
+impl AnimationPlayer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_clip(mut self, clip: Arc<AnimationClip>) -> Self {
+        self.play(clip);
+        self
+    }
+
+    pub fn with_speed(mut self, speed: f32) -> Self {
+        self.speed = speed;
+        self
+    }
+
+    pub fn with_looping(mut self, looping: bool) -> Self {
+        self.looping = looping;
+        self
+    }
+
+    // Starts `clip` from the beginning, or from the end when playing backwards
+    pub fn play(&mut self, clip: Arc<AnimationClip>) {
+        self.fade = None;
+        self.current = Some(self.start(clip));
+        self.playing = true;
+    }
+
+    // Blends from the current pose to `clip` over `duration` seconds
+    pub fn crossfade(&mut self, clip: Arc<AnimationClip>, duration: f32) {
+        let next = self.start(clip);
+
+        self.fade = match (self.current.take(), duration > 0.0) {
+            (Some(from), true) => Some(Fade {
+                from,
+                elapsed: 0.0,
+                duration,
+            }),
+            _ => None,
+        };
+        self.current = Some(next);
+        self.playing = true;
+    }
+
+    fn start(&self, clip: Arc<AnimationClip>) -> Playback {
+        let time = match self.speed < 0.0 {
+            true => clip.duration(),
+            false => 0.0,
+        };
+
+        Playback { clip, time }
+    }
+
+    pub fn pause(&mut self) {
+        self.playing = false;
+    }
+
+    pub fn resume(&mut self) {
+        self.playing = self.current.is_some();
+    }
+
+    pub fn stop(&mut self) {
+        self.current = None;
+        self.fade = None;
+        self.playing = false;
+    }
+
+    pub fn seek(&mut self, time: f32) {
+        if let Some(current) = self.current.as_mut() {
+            current.time = time.clamp(0.0, current.clip.duration());
+        }
+    }
+
+    pub fn is_playing(&self) -> bool {
+        self.playing
+    }
+
+    pub fn time(&self) -> f32 {
+        self.current.as_ref().map_or(0.0, |current| current.time)
+    }
+
+    pub fn clip(&self) -> Option<&Arc<AnimationClip>> {
+        self.current.as_ref().map(|current| &current.clip)
+    }
+
+    // Steps the playback by `delta` seconds and returns the pose to apply, `None`
+    // while nothing is playing so the transform can be edited freely
+    pub fn advance(&mut self, delta: f32) -> Option<Pose> {
+        if !self.playing {
+            return None;
+        }
+
+        let current = self.current.as_mut()?;
+        let step = delta * self.speed;
+
+        // The last pose of a clip that doesn't loop is still applied once
+        self.playing = current.advance(step, self.looping);
+        let mut pose = current.clip.sample(current.time);
+
+        if let Some(fade) = self.fade.as_mut() {
+            fade.from.advance(step, self.looping);
+            fade.elapsed += delta;
+
+            let weight = fade.elapsed / fade.duration;
+            pose = fade
+                .from
+                .clip
+                .sample(fade.from.time)
+                .blend(&pose, weight.min(1.0));
+
+            if weight >= 1.0 {
+                self.fade = None;
+            } else {
+                self.playing = true;
+            }
+        }
+
+        Some(pose)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn clip() -> Arc<AnimationClip> {
+        Arc::new(
+            AnimationClip::new("Slide").with_position(
+                crate::animation::Track::new(crate::animation::Interpolation::Linear)
+                    .with_keyframe(0.0, nalgebra_glm::vec3(0.0, 0.0, 0.0))
+                    .with_keyframe(2.0, nalgebra_glm::vec3(2.0, 0.0, 0.0)),
+            ),
+        )
+    }
+
+    fn x(pose: Option<Pose>) -> Option<f32> {
+        pose.and_then(|pose| pose.position)
+            .map(|position| position.x)
+    }
+
+    #[test]
+    fn playback_can_be_paused_sought_and_stopped() {
+        let mut player = AnimationPlayer::new().with_clip(clip()).with_looping(false);
+        assert_eq!(x(player.advance(0.5)), Some(0.5));
+
+        // Paused players leave the transform alone
+        player.pause();
+        assert!(!player.is_playing());
+        assert_eq!(x(player.advance(0.5)), None);
+
+        player.resume();
+        player.seek(1.5);
+        assert_eq!(x(player.advance(0.0)), Some(1.5));
+
+        // The last pose is applied once, then the clip is over
+        assert_eq!(x(player.advance(1.0)), Some(2.0));
+        assert!(!player.is_playing());
+        assert_eq!(player.time(), 2.0);
+
+        player.stop();
+        assert!(player.clip().is_none());
+        assert_eq!(x(player.advance(0.5)), None);
+    }
+
+    #[test]
+    fn negative_speeds_play_backwards() {
+        let mut player = AnimationPlayer::new().with_speed(-1.0).with_clip(clip());
+
+        assert_eq!(x(player.advance(0.5)), Some(1.5));
+        // Looping wraps around to the end
+        assert_eq!(x(player.advance(2.0)), Some(1.5));
+    }
+}
@@ -1,15 +1,88 @@
+// Same remap the shaders apply, nalgebra_glm projections output depth in [-1, 1]
+#[rustfmt::skip]
+pub const OPENGL_TO_WGPU_MATRIX: nalgebra_glm::Mat4 = nalgebra_glm::Mat4::new(
+    1.0, 0.0, 0.0, 0.0,
+    0.0, 1.0, 0.0, 0.0,
+    0.0, 0.0, 0.5, 0.5,
+    0.0, 0.0, 0.0, 1.0,
+);
+
+#[allow(dead_code)]
 #[derive(Debug)]
 pub struct Camera {
     pub camera_type: CameraType,
+    pub output: CameraOutput,
     pub projection: nalgebra_glm::Mat4,
     pub buffer: wgpu::Buffer,
+    pub bind_group: std::sync::Arc<wgpu::BindGroup>,
     pub uniform: CameraUniform,
+    // Size of the target in pixels, set by the engine before every update
+    pub target_size: nalgebra_glm::Vec2,
+    // Vertical field of view of `CameraType::Perspective` in degrees
+    pub fov: f32,
+    // Clip planes of `CameraType::Perspective` and `CameraType::Orthographic`
+    pub near: f32,
+    pub far: f32,
     pub device: std::sync::Arc<wgpu::Device>,
     pub window: std::sync::Arc<winit::window::Window>,
 }
 
+// Part of the target the camera renders to, in [0, 1] from the top left corner
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Viewport {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+impl Default for Viewport {
+    fn default() -> Self {
+        Self {
+            x: 0.0,
+            y: 0.0,
+            width: 1.0,
+            height: 1.0,
+        }
+    }
+}
+
+impl Viewport {
+    // Position and size in pixels for a target of `size`, kept inside the target
+    pub fn to_pixels(self, size: &nalgebra_glm::Vec2) -> (nalgebra_glm::Vec2, nalgebra_glm::Vec2) {
+        let x = self.x.clamp(0.0, 1.0);
+        let y = self.y.clamp(0.0, 1.0);
+        let width = self.width.clamp(0.0, 1.0 - x);
+        let height = self.height.clamp(0.0, 1.0 - y);
+
+        (
+            nalgebra_glm::vec2(x * size.x, y * size.y),
+            nalgebra_glm::vec2(width * size.x, height * size.y),
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default, serde::Serialize, serde::Deserialize)]
+pub enum CameraTarget {
+    #[default]
+    Window,
+    // Name of a render target of the engine
+    Texture(String),
+}
+
+// Where and when a camera is drawn, cameras with a lower order are rendered first
+#[derive(Debug, Clone, PartialEq, Default, serde::Serialize, serde::Deserialize)]
+pub struct CameraOutput {
+    #[serde(default)]
+    pub viewport: Viewport,
+    #[serde(default)]
+    pub order: i32,
+    #[serde(default)]
+    pub target: CameraTarget,
+}
+
 #[allow(dead_code)]
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
 pub enum CameraType {
     Orthographic,
     OrthographicCustom {
@@ -23,18 +96,47 @@ pub enum CameraType {
     Perspective,
     PerspectiveCustom {
         aspect: f32,
+        // Radians
         fovy: f32,
         near: f32,
         far: f32,
     },
 }
 
+impl CameraType {
+    pub fn name(&self) -> &'static str {
+        match self {
+            CameraType::Orthographic => "Orthographic",
+            CameraType::OrthographicCustom { .. } => "Orthographic Custom",
+            CameraType::Perspective => "Perspective",
+            CameraType::PerspectiveCustom { .. } => "Perspective Custom",
+        }
+    }
+}
+
 impl Camera {
+    pub fn create_bind_group_layout(device: &wgpu::Device) -> wgpu::BindGroupLayout {
+        device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+            label: Some("Camera Bind Group Layout"),
+        })
+    }
+
+    // Identical layouts are compatible, so every camera can create its own
     pub fn new(
         camera_type: CameraType,
         window: std::sync::Arc<winit::window::Window>,
         device: std::sync::Arc<wgpu::Device>,
-    ) -> (Self, wgpu::BindGroup, wgpu::BindGroupLayout) {
+    ) -> Self {
         let uniform = CameraUniform::new();
 
         let buffer = wgpu::util::DeviceExt::create_buffer_init(
@@ -46,20 +148,7 @@ impl Camera {
             },
         );
 
-        let camera_bind_group_layout =
-            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-                entries: &[wgpu::BindGroupLayoutEntry {
-                    binding: 0,
-                    visibility: wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT,
-                    ty: wgpu::BindingType::Buffer {
-                        ty: wgpu::BufferBindingType::Uniform,
-                        has_dynamic_offset: false,
-                        min_binding_size: None,
-                    },
-                    count: None,
-                }],
-                label: Some("Camera Bind Group Layout"),
-            });
+        let camera_bind_group_layout = Self::create_bind_group_layout(device.as_ref());
 
         let camera_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
             layout: &camera_bind_group_layout,
@@ -70,23 +159,67 @@ impl Camera {
             label: Some("Camera Bind Group"),
         });
 
+        let size = window.inner_size();
+
         let mut camera = Self {
             camera_type,
+            output: CameraOutput::default(),
             projection: nalgebra_glm::Mat4::identity(),
             buffer,
+            bind_group: std::sync::Arc::new(camera_bind_group),
             uniform,
+            target_size: nalgebra_glm::vec2(size.width as f32, size.height as f32),
+            fov: 45.0,
+            near: 0.1,
+            far: 1000.0,
             device,
             window,
         };
 
         camera.projection = camera.build_projection();
 
-        (camera, camera_bind_group, camera_bind_group_layout)
+        camera
+    }
+
+    pub fn with_output(mut self, output: CameraOutput) -> Self {
+        self.output = output;
+        self
+    }
+
+    pub fn with_fov(mut self, fov: f32) -> Self {
+        self.fov = fov;
+        self.projection = self.build_projection();
+        self
     }
 
-    pub fn update(&mut self, transform: &super::Transform, queue: &wgpu::Queue) {
-        self.uniform
-            .update(self.build_projection(), self.build_view(transform));
+    pub fn with_clip_planes(mut self, near: f32, far: f32) -> Self {
+        self.near = near;
+        self.far = far;
+        self.projection = self.build_projection();
+        self
+    }
+
+    // Size of the viewport in pixels
+    pub fn viewport_size(&self) -> nalgebra_glm::Vec2 {
+        let (_, size) = self.output.viewport.to_pixels(&self.target_size);
+
+        nalgebra_glm::max(&size, 1.0)
+    }
+
+    pub fn update(
+        &mut self,
+        transform: &super::GlobalTransform,
+        time: &FrameTime,
+        queue: &wgpu::Queue,
+    ) {
+        self.projection = self.build_projection();
+        self.uniform.update(
+            OPENGL_TO_WGPU_MATRIX * self.projection,
+            self.build_view(transform),
+            transform.position(),
+            self.viewport_size(),
+            time,
+        );
 
         queue.write_buffer(&self.buffer, 0, bytemuck::cast_slice(&[self.uniform]))
     }
@@ -94,10 +227,17 @@ impl Camera {
     pub fn build_projection(&self) -> nalgebra_glm::Mat4 {
         match self.camera_type {
             CameraType::Orthographic => {
-                nalgebra_glm::ortho_rh(0.0, 1280.0, 0.0, 720.0, 0.025, 1000.0)
+                let size = self.viewport_size();
+                nalgebra_glm::ortho_rh(0.0, size.x, 0.0, size.y, self.near, self.far)
             }
             CameraType::Perspective => {
-                nalgebra_glm::perspective_rh(1280.0 / 720.0, 45.0, 0.1, 1000.0)
+                let size = self.viewport_size();
+                nalgebra_glm::perspective_rh(
+                    size.x / size.y,
+                    self.fov.clamp(1.0, 179.0).to_radians(),
+                    self.near,
+                    self.far,
+                )
             }
             CameraType::OrthographicCustom {
                 left,
@@ -116,32 +256,133 @@ impl Camera {
         }
     }
 
-    pub fn build_view(&self, transform: &super::Transform) -> nalgebra_glm::Mat4 {
-        nalgebra_glm::Mat4::look_at_rh(
-            &transform.get_position().into(),
-            &(transform.get_position() + transform.forward()).into(),
-            &transform.up(),
-        ) * nalgebra_glm::inverse(&(transform.get_parent_matrix()))
+    // Projection and view with the depth remapped to [0, 1], the matrix the shaders use
+    pub fn view_projection(&self, transform: &super::GlobalTransform) -> nalgebra_glm::Mat4 {
+        OPENGL_TO_WGPU_MATRIX * self.build_projection() * self.build_view(transform)
+    }
+
+    // Pixels of the target with the origin at the top left corner, the viewport offset
+    // included. `z` is the value the point would write to the depth buffer, `None` for
+    // points behind a perspective camera
+    pub fn world_to_screen(
+        &self,
+        transform: &super::GlobalTransform,
+        point: &nalgebra_glm::Vec3,
+    ) -> Option<nalgebra_glm::Vec3> {
+        let (offset, size) = self.output.viewport.to_pixels(&self.target_size);
+
+        crate::math::project(&self.view_projection(transform), point, &offset, &size)
+    }
+
+    // Inverse of `world_to_screen`, `depth` is the value stored in the depth buffer
+    pub fn unproject(
+        &self,
+        transform: &super::GlobalTransform,
+        position: &nalgebra_glm::Vec2,
+        depth: f32,
+    ) -> nalgebra_glm::Vec3 {
+        let (offset, _) = self.output.viewport.to_pixels(&self.target_size);
+
+        crate::math::unproject(
+            &self.view_projection(transform),
+            position,
+            depth,
+            &offset,
+            &self.viewport_size(),
+        )
+    }
+
+    // Starts on the near plane and goes through the pixel at `position`
+    pub fn screen_to_ray(
+        &self,
+        transform: &super::GlobalTransform,
+        position: &nalgebra_glm::Vec2,
+    ) -> crate::math::Ray {
+        let near = self.unproject(transform, position, 0.0);
+        let far = self.unproject(transform, position, 1.0);
+
+        crate::math::Ray::new(near, nalgebra_glm::normalize(&(far - near)))
+    }
+
+    pub fn frustum(&self, transform: &super::GlobalTransform) -> crate::math::Frustum {
+        crate::math::Frustum::from_matrix(&self.view_projection(transform))
+    }
+
+    // Whether `position` in target pixels falls inside the viewport
+    pub fn contains_pixel(&self, position: &nalgebra_glm::Vec2) -> bool {
+        let (offset, size) = self.output.viewport.to_pixels(&self.target_size);
+
+        position.x >= offset.x
+            && position.y >= offset.y
+            && position.x < offset.x + size.x
+            && position.y < offset.y + size.y
+    }
+
+    // The camera looks along the local Z axis of its transform
+    pub fn build_view(&self, transform: &super::GlobalTransform) -> nalgebra_glm::Mat4 {
+        let eye = transform.position();
+
+        nalgebra_glm::look_at_rh(&eye, &(eye + transform.forward()), &transform.up())
     }
 }
 
+// Matches `CameraUniform` in `shaders/camera.wgsl`
 #[repr(C)]
 #[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
 pub struct CameraUniform {
-    projection: [[f32; 4]; 4],
+    view_proj: [[f32; 4]; 4],
     view: [[f32; 4]; 4],
+    projection: [[f32; 4]; 4],
+    inverse_view: [[f32; 4]; 4],
+    inverse_projection: [[f32; 4]; 4],
+    position: [f32; 3],
+    time: f32,
+    viewport_size: [f32; 2],
+    frame: u32,
+    _padding: u32,
 }
 
 impl CameraUniform {
     pub fn new() -> Self {
         Self {
-            projection: nalgebra_glm::Mat4::identity().into(),
+            view_proj: nalgebra_glm::Mat4::identity().into(),
             view: nalgebra_glm::Mat4::identity().into(),
+            projection: nalgebra_glm::Mat4::identity().into(),
+            inverse_view: nalgebra_glm::Mat4::identity().into(),
+            inverse_projection: nalgebra_glm::Mat4::identity().into(),
+            position: [0.0; 3],
+            time: 0.0,
+            viewport_size: [1.0; 2],
+            frame: 0,
+            _padding: 0,
         }
     }
 
-    pub fn update(&mut self, projection: nalgebra_glm::Mat4, view: nalgebra_glm::Mat4) {
-        self.projection = projection.into();
+    // `projection` already includes the depth remap
+    pub fn update(
+        &mut self,
+        projection: nalgebra_glm::Mat4,
+        view: nalgebra_glm::Mat4,
+        position: nalgebra_glm::Vec3,
+        viewport_size: nalgebra_glm::Vec2,
+        time: &FrameTime,
+    ) {
+        self.view_proj = (projection * view).into();
         self.view = view.into();
+        self.projection = projection.into();
+        self.inverse_view = nalgebra_glm::inverse(&view).into();
+        self.inverse_projection = nalgebra_glm::inverse(&projection).into();
+        self.position = position.into();
+        self.time = time.elapsed;
+        self.viewport_size = viewport_size.into();
+        self.frame = time.frame;
     }
 }
+
+// Shared by every camera uniform of a frame
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FrameTime {
+    // Seconds since the engine started
+    pub elapsed: f32,
+    pub frame: u32,
+}
@@ -0,0 +1,276 @@
+// What the controllers read each frame, `movement` is in screen terms with x to the
+// right, y up and z forward
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CameraInput {
+    pub movement: nalgebra_glm::Vec3,
+    pub roll: f32,
+    // Mouse movement in pixels, ignored while the cursor is used for something else
+    pub look: nalgebra_glm::Vec2,
+    // Scroll wheel, positive zooms in
+    pub zoom: f32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ControllerMode {
+    // Moves along the local axes, can roll
+    Fly,
+    // Walks on the horizontal plane, the pitch is clamped in degrees
+    Fps {
+        min_pitch: f32,
+        max_pitch: f32,
+    },
+    // Turns around `target` at `distance`
+    Orbit {
+        target: nalgebra_glm::Vec3,
+        distance: f32,
+        min_distance: f32,
+        max_distance: f32,
+    },
+    // Orthographic, pans in the view plane and `height` is the visible height in world units
+    PanZoom {
+        height: f32,
+        min_height: f32,
+        max_height: f32,
+    },
+}
+
+impl ControllerMode {
+    pub fn fps() -> Self {
+        ControllerMode::Fps {
+            min_pitch: -89.0,
+            max_pitch: 89.0,
+        }
+    }
+
+    pub fn orbit(target: nalgebra_glm::Vec3, distance: f32) -> Self {
+        ControllerMode::Orbit {
+            target,
+            distance,
+            min_distance: 0.5,
+            max_distance: 500.0,
+        }
+    }
+
+    pub fn pan_zoom(height: f32) -> Self {
+        ControllerMode::PanZoom {
+            height,
+            min_height: 0.5,
+            max_height: 1000.0,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ControllerMode::Fly => "Fly",
+            ControllerMode::Fps { .. } => "FPS",
+            ControllerMode::Orbit { .. } => "Orbit",
+            ControllerMode::PanZoom { .. } => "Pan Zoom",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct CameraController {
+    pub mode: ControllerMode,
+    // Units per second
+    pub speed: f32,
+    // Degrees per pixel of mouse movement
+    pub sensitivity: f32,
+    // Time in seconds to catch up with the input, 0 reacts immediately
+    pub smoothing: f32,
+    velocity: nalgebra_glm::Vec3,
+    look: nalgebra_glm::Vec2,
+    // Yaw and pitch in degrees, read from the transform on the first update
+    angles: Option<nalgebra_glm::Vec2>,
+    // Projection the camera had before the pan and zoom mode replaced it
+    previous_type: Option<super::CameraType>,
+}
+
+impl CameraController {
+    pub fn new(mode: ControllerMode) -> Self {
+        Self {
+            mode,
+            speed: 3.0,
+            sensitivity: 0.5,
+            smoothing: 0.0,
+            velocity: nalgebra_glm::zero(),
+            look: nalgebra_glm::zero(),
+            angles: None,
+            previous_type: None,
+        }
+    }
+
+    pub fn with_speed(mut self, speed: f32) -> Self {
+        self.speed = speed;
+        self
+    }
+
+    pub fn with_sensitivity(mut self, sensitivity: f32) -> Self {
+        self.sensitivity = sensitivity;
+        self
+    }
+
+    pub fn with_smoothing(mut self, smoothing: f32) -> Self {
+        self.smoothing = smoothing;
+        self
+    }
+
+    pub fn set_mode(&mut self, mode: ControllerMode) {
+        self.mode = mode;
+        self.angles = None;
+        self.velocity = nalgebra_glm::zero();
+        self.look = nalgebra_glm::zero();
+    }
+
+    pub fn update(
+        &mut self,
+        transform: &mut super::Transform,
+        mut camera: Option<&mut super::Camera>,
+        input: &CameraInput,
+        delta: f32,
+    ) {
+        let blend = match self.smoothing > 0.0 {
+            true => 1.0 - (-delta / self.smoothing).exp(),
+            false => 1.0,
+        };
+
+        self.velocity = nalgebra_glm::lerp(&self.velocity, &(input.movement * self.speed), blend);
+        self.look = nalgebra_glm::lerp(&self.look, &(input.look * self.sensitivity), blend);
+
+        if !matches!(self.mode, ControllerMode::PanZoom { .. }) {
+            if let (Some(camera_type), Some(camera)) = (self.previous_type, camera.as_mut()) {
+                camera.camera_type = camera_type;
+                self.previous_type = None;
+            }
+        }
+
+        match self.mode {
+            ControllerMode::Fly => {
+                // The local x axis points to the left of the screen
+                let offset = -transform.right() * self.velocity.x
+                    + nalgebra_glm::Vec3::y() * self.velocity.y
+                    + transform.forward() * self.velocity.z;
+                transform.add_position(&(offset * delta));
+
+                if input.roll != 0.0 {
+                    transform.add_rotation_z(input.roll * self.sensitivity * 20.0 * delta);
+                }
+
+                // Pitch around the local right axis, yaw around the world up axis
+                transform.add_rotation_x(self.look.y);
+                transform.add_rotation_global_y(-self.look.x);
+            }
+            ControllerMode::Fps {
+                min_pitch,
+                max_pitch,
+            } => {
+                let angles = self.turn(transform, min_pitch, max_pitch);
+                transform.set_rotation(&Self::yaw_pitch(&angles));
+
+                let forward = nalgebra_glm::vec3(
+                    angles.x.to_radians().sin(),
+                    0.0,
+                    angles.x.to_radians().cos(),
+                );
+                let left = nalgebra_glm::vec3(forward.z, 0.0, -forward.x);
+
+                let offset = -left * self.velocity.x + forward * self.velocity.z;
+                transform.add_position(&(offset * delta));
+            }
+            ControllerMode::Orbit {
+                target,
+                distance,
+                min_distance,
+                max_distance,
+            } => {
+                let angles = self.turn(transform, -89.0, 89.0);
+                let rotation = Self::yaw_pitch(&angles);
+
+                // Moving forward gets closer, the target follows sideways movement
+                let distance =
+                    (distance * 0.9f32.powf(input.zoom) * (1.0 - self.velocity.z * 0.25 * delta))
+                        .clamp(min_distance, max_distance);
+
+                let right = -nalgebra_glm::quat_rotate_vec3(&rotation, &nalgebra_glm::Vec3::x());
+                let up = nalgebra_glm::quat_rotate_vec3(&rotation, &nalgebra_glm::Vec3::y());
+                let target = target
+                    + (right * self.velocity.x + up * self.velocity.y) * distance * 0.1 * delta;
+
+                let forward = nalgebra_glm::quat_rotate_vec3(&rotation, &nalgebra_glm::Vec3::z());
+                transform.set_rotation(&rotation);
+                transform.set_position(&(target - forward * distance));
+
+                self.mode = ControllerMode::Orbit {
+                    target,
+                    distance,
+                    min_distance,
+                    max_distance,
+                };
+            }
+            ControllerMode::PanZoom {
+                height,
+                min_height,
+                max_height,
+            } => {
+                let height =
+                    (height * 0.9f32.powf(input.zoom) * (1.0 - self.velocity.z * 0.25 * delta))
+                        .clamp(min_height, max_height);
+
+                // Panning is scaled with the zoom so it feels the same at every height
+                let offset =
+                    -transform.right() * self.velocity.x + transform.up() * self.velocity.y;
+                transform.add_position(&(offset * height * 0.1 * delta));
+
+                if let Some(camera) = camera {
+                    let size = camera.viewport_size();
+                    let aspect = size.x / size.y;
+                    let half_height = height * 0.5;
+
+                    self.previous_type.get_or_insert(camera.camera_type);
+                    camera.camera_type = super::CameraType::OrthographicCustom {
+                        left: -half_height * aspect,
+                        right: half_height * aspect,
+                        bottom: -half_height,
+                        top: half_height,
+                        znear: -1000.0,
+                        zfar: 1000.0,
+                    };
+                }
+
+                self.mode = ControllerMode::PanZoom {
+                    height,
+                    min_height,
+                    max_height,
+                };
+            }
+        }
+    }
+
+    // Applies the mouse movement to the yaw and pitch of the controller
+    fn turn(
+        &mut self,
+        transform: &super::Transform,
+        min_pitch: f32,
+        max_pitch: f32,
+    ) -> nalgebra_glm::Vec2 {
+        let mut angles = self.angles.unwrap_or_else(|| {
+            let forward = transform.forward();
+
+            nalgebra_glm::vec2(
+                forward.x.atan2(forward.z).to_degrees(),
+                (-forward.y).clamp(-1.0, 1.0).asin().to_degrees(),
+            )
+        });
+
+        angles.x -= self.look.x;
+        angles.y = (angles.y + self.look.y).clamp(min_pitch, max_pitch);
+        self.angles = Some(angles);
+
+        angles
+    }
+
+    fn yaw_pitch(angles: &nalgebra_glm::Vec2) -> nalgebra_glm::Quat {
+        nalgebra_glm::quat_angle_axis(angles.x.to_radians(), &nalgebra_glm::Vec3::y())
+            * nalgebra_glm::quat_angle_axis(angles.y.to_radians(), &nalgebra_glm::Vec3::x())
+    }
+}
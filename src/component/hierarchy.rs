@@ -0,0 +1,6 @@
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Parent(pub hecs::Entity);
+
+// Kept in sync with `Parent` by `crate::hierarchy::set_parent`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Children(pub Vec<hecs::Entity>);
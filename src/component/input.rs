@@ -1,13 +1,1011 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
 
-pub struct Input<'a> {
-    actions: HashMap<&'a str, InputType>,
+#[derive(Debug)]
+pub enum InputError {
+    Io(std::io::Error),
+    Parse(ron::error::SpannedError),
+    Serialize(ron::Error),
 }
 
-pub enum InputType {
-    Keyboard {
-        input: winit::event::KeyboardInput,
-        active: bool,
+impl std::fmt::Display for InputError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            InputError::Io(e) => write!(f, "io error: {}", e),
+            InputError::Parse(e) => write!(f, "parse error: {}", e),
+            InputError::Serialize(e) => write!(f, "serialize error: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for InputError {}
+
+impl From<std::io::Error> for InputError {
+    fn from(e: std::io::Error) -> Self {
+        InputError::Io(e)
+    }
+}
+
+impl From<ron::error::SpannedError> for InputError {
+    fn from(e: ron::error::SpannedError) -> Self {
+        InputError::Parse(e)
+    }
+}
+
+impl From<ron::Error> for InputError {
+    fn from(e: ron::Error) -> Self {
+        InputError::Serialize(e)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+pub enum Button {
+    Key(winit::event::VirtualKeyCode),
+    Mouse(winit::event::MouseButton),
+    Gamepad(gilrs::Button),
+}
+
+impl std::fmt::Display for Button {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Button::Key(key) => write!(f, "{:?}", key),
+            Button::Mouse(winit::event::MouseButton::Other(button)) => {
+                write!(f, "Mouse {}", button)
+            }
+            Button::Mouse(button) => write!(f, "Mouse {:?}", button),
+            Button::Gamepad(button) => write!(f, "Pad {:?}", button),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
+pub enum AxisBinding {
+    // -1 while `negative` is held, 1 while `positive` is, 0 for both
+    Buttons { negative: Button, positive: Button },
+    // Pixels moved since the last frame
+    MouseX,
+    MouseY,
+    // Lines scrolled since the last frame, positive away from the user
+    Scroll,
+    // Stick position in [-1, 1], after the dead zone and the response curve
+    GamepadAxis(gilrs::Axis),
+    // How far an analog button like a trigger is pushed, in [0, 1]
+    GamepadTrigger(gilrs::Button),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct GamepadSettings {
+    // Stick values below it read as 0, the rest is stretched back to [0, 1]
+    pub dead_zone: f32,
+    // Response curve, 1 is linear and higher values give more precision near the center
+    pub exponent: f32,
+}
+
+impl Default for GamepadSettings {
+    fn default() -> Self {
+        Self {
+            dead_zone: 0.15,
+            exponent: 1.5,
+        }
+    }
+}
+
+impl GamepadSettings {
+    pub fn apply(&self, value: f32) -> f32 {
+        let magnitude = value.abs();
+        if magnitude <= self.dead_zone {
+            return 0.0;
+        }
+
+        let magnitude = ((magnitude - self.dead_zone) / (1.0 - self.dead_zone).max(f32::EPSILON))
+            .min(1.0)
+            .powf(self.exponent);
+
+        magnitude.copysign(value)
+    }
+}
+
+// State of an action for the current frame, a tap shorter than a frame is pressed and
+// released without being held
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ActionState {
+    pub pressed: bool,
+    pub held: bool,
+    pub released: bool,
+}
+
+// What can be saved to a file, sorted so the files and the settings stay in order
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Bindings {
+    pub actions: BTreeMap<String, Vec<Button>>,
+    // The value of a binding is multiplied by its scale, the axis is the sum of its bindings
+    pub axes: BTreeMap<String, Vec<(AxisBinding, f32)>>,
+    #[serde(default)]
+    pub gamepad: GamepadSettings,
+}
+
+impl Bindings {
+    pub fn load(path: &str) -> Result<Self, InputError> {
+        let content = std::fs::read_to_string(path)?;
+        Ok(ron::from_str(&content)?)
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), InputError> {
+        let content = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())?;
+        std::fs::write(path, content)?;
+        Ok(())
+    }
+
+    // Names of the actions and axes listening to each button, for buttons with more than one
+    pub fn conflicts(&self) -> Vec<(Button, Vec<String>)> {
+        let mut users = std::collections::HashMap::<Button, Vec<String>>::new();
+
+        self.actions.iter().for_each(|(action, buttons)| {
+            buttons.iter().for_each(|button| {
+                users.entry(*button).or_default().push(action.clone());
+            });
+        });
+        self.axes.iter().for_each(|(axis, bindings)| {
+            bindings.iter().for_each(|(binding, _)| {
+                if let AxisBinding::Buttons { negative, positive } = binding {
+                    users
+                        .entry(*negative)
+                        .or_default()
+                        .push(format!("{} -", axis));
+                    users
+                        .entry(*positive)
+                        .or_default()
+                        .push(format!("{} +", axis));
+                }
+            });
+        });
+
+        let mut conflicts = users
+            .into_iter()
+            .filter(|(_, users)| users.len() > 1)
+            .collect::<Vec<_>>();
+        conflicts.sort_by_key(|(button, _)| button.to_string());
+
+        conflicts
+    }
+
+    // Entries of `other` replace the ones with the same name
+    pub fn merge(&mut self, other: Bindings) {
+        self.actions.extend(other.actions);
+        self.axes.extend(other.axes);
+        self.gamepad = other.gamepad;
+    }
+}
+
+// A binding waiting for the next button pressed, `index` past the end adds a binding
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Rebind {
+    Action { action: String, index: usize },
+    AxisNegative { axis: String, index: usize },
+    AxisPositive { axis: String, index: usize },
+}
+
+// Every change made to the input, recordings keep them for every frame
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub enum InputEvent {
+    Press {
+        player: usize,
+        button: Button,
     },
-    Mouse {},
+    Release {
+        player: usize,
+        button: Button,
+    },
+    // Held since before the recording started, there is no press
+    Hold {
+        player: usize,
+        button: Button,
+    },
+    ReleaseAll,
+    MouseDelta {
+        x: f32,
+        y: f32,
+    },
+    Scroll(f32),
+    Blocked {
+        keyboard: bool,
+        pointer: bool,
+    },
+    GamepadConnected(usize),
+    GamepadDisconnected(usize),
+    GamepadPress {
+        gamepad: usize,
+        button: gilrs::Button,
+    },
+    GamepadRelease {
+        gamepad: usize,
+        button: gilrs::Button,
+    },
+    GamepadAxis {
+        gamepad: usize,
+        axis: gilrs::Axis,
+        value: f32,
+    },
+    GamepadTrigger {
+        gamepad: usize,
+        button: gilrs::Button,
+        value: f32,
+    },
+}
+
+#[derive(Debug, Clone, Default)]
+struct Player {
+    // Id given by gilrs, `None` until a gamepad is connected
+    gamepad: Option<usize>,
+    held: HashSet<Button>,
+    pressed: HashSet<Button>,
+    released: HashSet<Button>,
+    axes: HashMap<gilrs::Axis, f32>,
+    triggers: HashMap<gilrs::Button, f32>,
+}
+
+// Every player has the same bindings, the first one also gets the keyboard and the mouse
+#[derive(Debug, Clone)]
+pub struct Input {
+    bindings: Bindings,
+    rebind: Option<Rebind>,
+    players: Vec<Player>,
+    mouse_delta: nalgebra_glm::Vec2,
+    scroll_delta: f32,
+    // Devices the UI is using, they read as idle without losing their state
+    keyboard_blocked: bool,
+    pointer_blocked: bool,
+    // Events applied since they were last taken, `None` while not recording
+    recorded: Option<Vec<InputEvent>>,
+}
+
+impl Default for Input {
+    fn default() -> Self {
+        Self {
+            bindings: Bindings::default(),
+            rebind: None,
+            players: vec![Player::default()],
+            mouse_delta: nalgebra_glm::zero(),
+            scroll_delta: 0.0,
+            keyboard_blocked: false,
+            pointer_blocked: false,
+            recorded: None,
+        }
+    }
+}
+
+impl Input {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_action(mut self, action: &str, buttons: &[Button]) -> Self {
+        buttons
+            .iter()
+            .for_each(|button| self.bind_action(action, *button));
+        self
+    }
+
+    pub fn with_axis(mut self, axis: &str, binding: AxisBinding, scale: f32) -> Self {
+        self.bind_axis(axis, binding, scale);
+        self
+    }
+
+    pub fn bind_action(&mut self, action: &str, button: Button) {
+        let buttons = self.bindings.actions.entry(action.to_owned()).or_default();
+        if !buttons.contains(&button) {
+            buttons.push(button);
+        }
+    }
+
+    pub fn bind_axis(&mut self, axis: &str, binding: AxisBinding, scale: f32) {
+        self.bindings
+            .axes
+            .entry(axis.to_owned())
+            .or_default()
+            .push((binding, scale));
+    }
+
+    pub fn unbind_action(&mut self, action: &str, button: Button) {
+        if let Some(buttons) = self.bindings.actions.get_mut(action) {
+            buttons.retain(|bound| *bound != button);
+        }
+    }
+
+    pub fn action_bindings(&self, action: &str) -> &[Button] {
+        self.bindings.actions.get(action).map_or(&[], Vec::as_slice)
+    }
+
+    pub fn axis_bindings(&self, axis: &str) -> &[(AxisBinding, f32)] {
+        self.bindings.axes.get(axis).map_or(&[], Vec::as_slice)
+    }
+
+    // Whether any action or axis listens to `button`
+    pub fn is_bound(&self, button: Button) -> bool {
+        self.bindings
+            .actions
+            .values()
+            .flatten()
+            .any(|bound| *bound == button)
+            || self
+                .bindings
+                .axes
+                .values()
+                .flatten()
+                .any(|(binding, _)| match binding {
+                    AxisBinding::Buttons { negative, positive } => {
+                        *negative == button || *positive == button
+                    }
+                    _ => false,
+                })
+    }
+
+    pub fn player_count(&self) -> usize {
+        self.players.len()
+    }
+
+    // Whether the player has a gamepad connected
+    pub fn has_gamepad(&self, player: usize) -> bool {
+        self.players
+            .get(player)
+            .is_some_and(|player| player.gamepad.is_some())
+    }
+
+    pub fn button_state(&self, button: Button) -> ActionState {
+        self.player_button_state(0, button)
+    }
+
+    pub fn set_blocked(&mut self, keyboard: bool, pointer: bool) {
+        if (keyboard, pointer) != (self.keyboard_blocked, self.pointer_blocked) {
+            self.apply(InputEvent::Blocked { keyboard, pointer });
+        }
+    }
+
+    fn is_blocked(&self, button: Button) -> bool {
+        match button {
+            Button::Key(_) => self.keyboard_blocked,
+            Button::Mouse(_) => self.pointer_blocked,
+            Button::Gamepad(_) => false,
+        }
+    }
+
+    pub fn player_button_state(&self, player: usize, button: Button) -> ActionState {
+        if self.is_blocked(button) {
+            return ActionState::default();
+        }
+
+        match self.players.get(player) {
+            Some(player) => ActionState {
+                pressed: player.pressed.contains(&button),
+                held: player.held.contains(&button),
+                released: player.released.contains(&button),
+            },
+            None => ActionState::default(),
+        }
+    }
+
+    pub fn action(&self, action: &str) -> ActionState {
+        self.player_action(0, action)
+    }
+
+    // Combined over every button bound to the action
+    pub fn player_action(&self, player: usize, action: &str) -> ActionState {
+        self.action_bindings(action)
+            .iter()
+            .map(|button| self.player_button_state(player, *button))
+            .fold(ActionState::default(), |state, button| ActionState {
+                pressed: state.pressed || button.pressed,
+                held: state.held || button.held,
+                released: state.released || button.released,
+            })
+    }
+
+    pub fn pressed(&self, action: &str) -> bool {
+        self.action(action).pressed
+    }
+
+    pub fn held(&self, action: &str) -> bool {
+        self.action(action).held
+    }
+
+    pub fn released(&self, action: &str) -> bool {
+        self.action(action).released
+    }
+
+    pub fn axis(&self, axis: &str) -> f32 {
+        self.player_axis(0, axis)
+    }
+
+    pub fn player_axis(&self, player: usize, axis: &str) -> f32 {
+        let Some(state) = self.players.get(player) else {
+            return 0.0;
+        };
+        let mouse = player == 0 && !self.pointer_blocked;
+        let held = |button: &Button| !self.is_blocked(*button) && state.held.contains(button);
+
+        self.axis_bindings(axis)
+            .iter()
+            .map(|(binding, scale)| {
+                let value = match binding {
+                    AxisBinding::Buttons { negative, positive } => {
+                        held(positive) as i32 as f32 - held(negative) as i32 as f32
+                    }
+                    AxisBinding::MouseX if mouse => self.mouse_delta.x,
+                    AxisBinding::MouseY if mouse => self.mouse_delta.y,
+                    AxisBinding::Scroll if mouse => self.scroll_delta,
+                    AxisBinding::MouseX | AxisBinding::MouseY | AxisBinding::Scroll => 0.0,
+                    AxisBinding::GamepadAxis(axis) => self
+                        .bindings
+                        .gamepad
+                        .apply(state.axes.get(axis).copied().unwrap_or_default()),
+                    // Digital triggers only report presses
+                    AxisBinding::GamepadTrigger(button) => match state.triggers.get(button) {
+                        Some(value) => *value,
+                        None => held(&Button::Gamepad(*button)) as i32 as f32,
+                    },
+                };
+
+                value * scale
+            })
+            .sum()
+    }
+
+    pub fn bindings(&self) -> &Bindings {
+        &self.bindings
+    }
+
+    pub fn bindings_mut(&mut self) -> &mut Bindings {
+        &mut self.bindings
+    }
+
+    // Held buttons that are no longer bound stay held until they are released
+    pub fn set_bindings(&mut self, bindings: Bindings) {
+        self.bindings = bindings;
+    }
+
+    pub fn start_rebind(&mut self, rebind: Rebind) {
+        self.rebind = Some(rebind);
+    }
+
+    pub fn cancel_rebind(&mut self) {
+        self.rebind = None;
+    }
+
+    pub fn rebinding(&self) -> Option<&Rebind> {
+        self.rebind.as_ref()
+    }
+
+    // Escape cancels, any other button replaces the binding
+    fn apply_rebind(&mut self, rebind: Rebind, button: Button) {
+        if button == Button::Key(winit::event::VirtualKeyCode::Escape) {
+            return;
+        }
+
+        match rebind {
+            Rebind::Action { action, index } => {
+                let buttons = self.bindings.actions.entry(action).or_default();
+                match buttons.get_mut(index) {
+                    Some(bound) => *bound = button,
+                    None => buttons.push(button),
+                }
+            }
+            // Only the sides of existing slots, a new slot would need both of them
+            Rebind::AxisNegative { axis, index } => {
+                if let Some((AxisBinding::Buttons { negative, .. }, _)) = self
+                    .bindings
+                    .axes
+                    .get_mut(&axis)
+                    .and_then(|bindings| bindings.get_mut(index))
+                {
+                    *negative = button;
+                }
+            }
+            Rebind::AxisPositive { axis, index } => {
+                if let Some((AxisBinding::Buttons { positive, .. }, _)) = self
+                    .bindings
+                    .axes
+                    .get_mut(&axis)
+                    .and_then(|bindings| bindings.get_mut(index))
+                {
+                    *positive = button;
+                }
+            }
+        }
+    }
+
+    pub fn press(&mut self, button: Button) {
+        self.player_press(0, button);
+    }
+
+    pub fn release(&mut self, button: Button) {
+        self.player_release(0, button);
+    }
+
+    pub fn player_press(&mut self, player: usize, button: Button) {
+        self.apply(InputEvent::Press { player, button });
+    }
+
+    pub fn player_release(&mut self, player: usize, button: Button) {
+        self.apply(InputEvent::Release { player, button });
+    }
+
+    // Takes the first player without a gamepad, returns the player
+    pub fn connect_gamepad(&mut self, gamepad: usize) -> usize {
+        if let Some(player) = self.gamepad_player(gamepad) {
+            return player;
+        }
+
+        self.apply(InputEvent::GamepadConnected(gamepad));
+        self.gamepad_player(gamepad).unwrap_or_default()
+    }
+
+    // The player keeps its slot for the next gamepad that is connected
+    pub fn disconnect_gamepad(&mut self, gamepad: usize) {
+        self.apply(InputEvent::GamepadDisconnected(gamepad));
+    }
+
+    fn gamepad_player(&self, gamepad: usize) -> Option<usize> {
+        self.players
+            .iter()
+            .position(|player| player.gamepad == Some(gamepad))
+    }
+
+    pub fn handle_gamepad_event(&mut self, gamepad: usize, event: &gilrs::EventType) {
+        match event {
+            gilrs::EventType::Connected => {
+                self.connect_gamepad(gamepad);
+            }
+            gilrs::EventType::Disconnected => self.disconnect_gamepad(gamepad),
+            gilrs::EventType::ButtonPressed(button, _) => self.apply(InputEvent::GamepadPress {
+                gamepad,
+                button: *button,
+            }),
+            gilrs::EventType::ButtonReleased(button, _) => self.apply(InputEvent::GamepadRelease {
+                gamepad,
+                button: *button,
+            }),
+            gilrs::EventType::ButtonChanged(button, value, _) => {
+                self.apply(InputEvent::GamepadTrigger {
+                    gamepad,
+                    button: *button,
+                    value: *value,
+                })
+            }
+            gilrs::EventType::AxisChanged(axis, value, _) => self.apply(InputEvent::GamepadAxis {
+                gamepad,
+                axis: *axis,
+                value: *value,
+            }),
+            _ => {}
+        }
+    }
+
+    pub fn add_mouse_delta(&mut self, delta: &nalgebra_glm::Vec2) {
+        self.apply(InputEvent::MouseDelta {
+            x: delta.x,
+            y: delta.y,
+        });
+    }
+
+    pub fn add_scroll(&mut self, delta: f32) {
+        self.apply(InputEvent::Scroll(delta));
+    }
+
+    // Releases the keyboard and the mouse, for when the window loses focus
+    pub fn release_all(&mut self) {
+        self.apply(InputEvent::ReleaseAll);
+    }
+
+    // Every change goes through here, so a replay of the events ends up in the same state
+    pub fn apply(&mut self, event: InputEvent) {
+        if let Some(recorded) = &mut self.recorded {
+            // Motion comes in many small events, the sum is the same
+            match (recorded.last_mut(), &event) {
+                (
+                    Some(InputEvent::MouseDelta { x, y }),
+                    InputEvent::MouseDelta { x: dx, y: dy },
+                ) => {
+                    *x += dx;
+                    *y += dy;
+                }
+                (Some(InputEvent::Scroll(scroll)), InputEvent::Scroll(delta)) => *scroll += delta,
+                _ => recorded.push(event.clone()),
+            }
+        }
+
+        self.update(event);
+    }
+
+    fn update(&mut self, event: InputEvent) {
+        match event {
+            InputEvent::Press { player, button } => {
+                if let Some(rebind) = self.rebind.take() {
+                    self.apply_rebind(rebind, button);
+                    return;
+                }
+
+                let Some(player) = self.players.get_mut(player) else {
+                    return;
+                };
+
+                // Key repeats arrive as more presses
+                if player.held.insert(button) {
+                    player.pressed.insert(button);
+                }
+            }
+            InputEvent::Release { player, button } => {
+                let Some(player) = self.players.get_mut(player) else {
+                    return;
+                };
+
+                if player.held.remove(&button) {
+                    player.released.insert(button);
+                }
+            }
+            InputEvent::Hold { player, button } => {
+                if let Some(player) = self.players.get_mut(player) {
+                    player.held.insert(button);
+                }
+            }
+            InputEvent::ReleaseAll => {
+                let player = &mut self.players[0];
+                let buttons = player
+                    .held
+                    .iter()
+                    .filter(|button| !matches!(button, Button::Gamepad(_)))
+                    .copied()
+                    .collect::<Vec<_>>();
+
+                buttons.into_iter().for_each(|button| {
+                    player.held.remove(&button);
+                    player.released.insert(button);
+                });
+            }
+            InputEvent::MouseDelta { x, y } => self.mouse_delta += nalgebra_glm::vec2(x, y),
+            InputEvent::Scroll(delta) => self.scroll_delta += delta,
+            InputEvent::Blocked { keyboard, pointer } => {
+                self.keyboard_blocked = keyboard;
+                self.pointer_blocked = pointer;
+            }
+            InputEvent::GamepadConnected(gamepad) => {
+                if self.gamepad_player(gamepad).is_some() {
+                    return;
+                }
+
+                match self
+                    .players
+                    .iter()
+                    .position(|player| player.gamepad.is_none())
+                {
+                    Some(player) => self.players[player].gamepad = Some(gamepad),
+                    None => self.players.push(Player {
+                        gamepad: Some(gamepad),
+                        ..Default::default()
+                    }),
+                }
+            }
+            InputEvent::GamepadDisconnected(gamepad) => {
+                let Some(index) = self.gamepad_player(gamepad) else {
+                    return;
+                };
+
+                let player = &mut self.players[index];
+                player.gamepad = None;
+                player.axes.clear();
+                player.triggers.clear();
+
+                let buttons = player
+                    .held
+                    .iter()
+                    .filter(|button| matches!(button, Button::Gamepad(_)))
+                    .copied()
+                    .collect::<Vec<_>>();
+                buttons.into_iter().for_each(|button| {
+                    player.held.remove(&button);
+                    player.released.insert(button);
+                });
+            }
+            InputEvent::GamepadPress { gamepad, button } => {
+                let player = self.connected_player(gamepad);
+                self.update(InputEvent::Press {
+                    player,
+                    button: Button::Gamepad(button),
+                });
+            }
+            InputEvent::GamepadRelease { gamepad, button } => {
+                let player = self.connected_player(gamepad);
+                self.update(InputEvent::Release {
+                    player,
+                    button: Button::Gamepad(button),
+                });
+            }
+            InputEvent::GamepadAxis {
+                gamepad,
+                axis,
+                value,
+            } => {
+                let player = self.connected_player(gamepad);
+                self.players[player].axes.insert(axis, value);
+            }
+            InputEvent::GamepadTrigger {
+                gamepad,
+                button,
+                value,
+            } => {
+                let player = self.connected_player(gamepad);
+                self.players[player].triggers.insert(button, value);
+            }
+        }
+    }
+
+    // Gamepad events connect the gamepad if it wasn't already
+    fn connected_player(&mut self, gamepad: usize) -> usize {
+        self.update(InputEvent::GamepadConnected(gamepad));
+        self.gamepad_player(gamepad).unwrap_or_default()
+    }
+
+    // Keeps the events from now on, starting with the ones that give the current state to
+    // an input that was just created. Meant to be called between frames
+    pub fn start_recording(&mut self) {
+        let mut events = vec![InputEvent::Blocked {
+            keyboard: self.keyboard_blocked,
+            pointer: self.pointer_blocked,
+        }];
+
+        self.players.iter().for_each(|player| {
+            let Some(gamepad) = player.gamepad else {
+                return;
+            };
+
+            events.push(InputEvent::GamepadConnected(gamepad));
+            events.extend(
+                player
+                    .axes
+                    .iter()
+                    .map(|(axis, value)| InputEvent::GamepadAxis {
+                        gamepad,
+                        axis: *axis,
+                        value: *value,
+                    }),
+            );
+            events.extend(player.triggers.iter().map(|(button, value)| {
+                InputEvent::GamepadTrigger {
+                    gamepad,
+                    button: *button,
+                    value: *value,
+                }
+            }));
+        });
+        self.players.iter().enumerate().for_each(|(index, player)| {
+            events.extend(player.held.iter().map(|button| InputEvent::Hold {
+                player: index,
+                button: *button,
+            }));
+        });
+
+        self.recorded = Some(events);
+    }
+
+    pub fn stop_recording(&mut self) {
+        self.recorded = None;
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.recorded.is_some()
+    }
+
+    // Events applied since the last call, `None` while not recording
+    pub fn take_recorded(&mut self) -> Option<Vec<InputEvent>> {
+        self.recorded.as_mut().map(std::mem::take)
+    }
+
+    // Returns whether the event was used by a binding or a rebind
+    pub fn handle_window_event(&mut self, event: &winit::event::WindowEvent) -> bool {
+        let (button, state) = match event {
+            winit::event::WindowEvent::KeyboardInput {
+                input:
+                    winit::event::KeyboardInput {
+                        state,
+                        virtual_keycode: Some(key),
+                        ..
+                    },
+                ..
+            } => (Button::Key(*key), state),
+            winit::event::WindowEvent::MouseInput { state, button, .. } => {
+                (Button::Mouse(*button), state)
+            }
+            winit::event::WindowEvent::MouseWheel { delta, .. } => {
+                self.add_scroll(match delta {
+                    winit::event::MouseScrollDelta::LineDelta(_, y) => *y,
+                    winit::event::MouseScrollDelta::PixelDelta(position) => {
+                        position.y as f32 / 40.0
+                    }
+                });
+                return true;
+            }
+            winit::event::WindowEvent::Focused(false) => {
+                self.release_all();
+                return false;
+            }
+            _ => return false,
+        };
+
+        // The button pressed to rebind is not passed on, Escape would close the window
+        let rebinding = self.rebind.is_some();
+
+        match state {
+            winit::event::ElementState::Pressed => self.press(button),
+            winit::event::ElementState::Released => self.release(button),
+        }
+
+        rebinding || self.is_bound(button)
+    }
+
+    pub fn handle_device_event(&mut self, event: &winit::event::DeviceEvent) {
+        if let winit::event::DeviceEvent::MouseMotion { delta } = event {
+            self.add_mouse_delta(&nalgebra_glm::vec2(delta.0 as f32, delta.1 as f32));
+        }
+    }
+
+    // Called once the frame has read the input
+    pub fn end_frame(&mut self) {
+        self.players.iter_mut().for_each(|player| {
+            player.pressed.clear();
+            player.released.clear();
+        });
+        self.mouse_delta = nalgebra_glm::zero();
+        self.scroll_delta = 0.0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use winit::event::VirtualKeyCode;
+
+    fn input() -> Input {
+        Input::new()
+            .with_axis(
+                "Move X",
+                AxisBinding::Buttons {
+                    negative: Button::Key(VirtualKeyCode::A),
+                    positive: Button::Key(VirtualKeyCode::D),
+                },
+                1.0,
+            )
+            .with_action(
+                "Jump",
+                &[
+                    Button::Key(VirtualKeyCode::Space),
+                    Button::Gamepad(gilrs::Button::South),
+                ],
+            )
+    }
+
+    fn state(input: &Input, action: &str) -> (bool, bool, bool) {
+        let state = input.action(action);
+        (state.pressed, state.held, state.released)
+    }
+
+    #[test]
+    fn actions_go_through_press_hold_and_release() {
+        let mut input = input();
+        let space = Button::Key(VirtualKeyCode::Space);
+
+        input.press(space);
+        assert_eq!(state(&input, "Jump"), (true, true, false));
+        input.end_frame();
+        assert_eq!(state(&input, "Jump"), (false, true, false));
+        input.end_frame();
+        assert_eq!(state(&input, "Jump"), (false, true, false));
+
+        input.release(space);
+        assert_eq!(state(&input, "Jump"), (false, false, true));
+        input.end_frame();
+        assert_eq!(state(&input, "Jump"), (false, false, false));
+    }
+
+    #[test]
+    fn a_press_and_release_in_one_frame_is_seen() {
+        let mut input = input();
+        let space = Button::Key(VirtualKeyCode::Space);
+
+        input.press(space);
+        input.release(space);
+        assert_eq!(state(&input, "Jump"), (true, false, true));
+    }
+
+    #[test]
+    fn actions_combine_their_buttons() {
+        let mut input = input();
+        input.press(Button::Key(VirtualKeyCode::Space));
+        input.end_frame();
+        input.press(Button::Gamepad(gilrs::Button::South));
+
+        assert_eq!(state(&input, "Jump"), (true, true, false));
+    }
+
+    #[test]
+    fn opposite_buttons_cancel_on_an_axis() {
+        let mut input = input();
+
+        input.press(Button::Key(VirtualKeyCode::D));
+        assert_eq!(input.axis("Move X"), 1.0);
+        input.press(Button::Key(VirtualKeyCode::A));
+        assert_eq!(input.axis("Move X"), 0.0);
+        input.release(Button::Key(VirtualKeyCode::D));
+        assert_eq!(input.axis("Move X"), -1.0);
+    }
+
+    #[test]
+    fn blocked_devices_read_idle_and_keep_their_state() {
+        let mut input = input();
+        input.press(Button::Key(VirtualKeyCode::Space));
+        input.end_frame();
+
+        input.set_blocked(true, false);
+        assert_eq!(state(&input, "Jump"), (false, false, false));
+        input.set_blocked(false, false);
+        assert_eq!(state(&input, "Jump"), (false, true, false));
+    }
+
+    #[test]
+    fn release_all_lets_go_of_every_button() {
+        let mut input = input();
+        input.press(Button::Key(VirtualKeyCode::Space));
+        input.press(Button::Key(VirtualKeyCode::D));
+        input.end_frame();
+
+        input.release_all();
+        assert!(!input.held("Jump"));
+        assert_eq!(input.axis("Move X"), 0.0);
+    }
+
+    #[test]
+    fn recorded_events_rebuild_the_state() {
+        let mut input = input();
+        input.press(Button::Key(VirtualKeyCode::Space));
+        input.end_frame();
+
+        input.start_recording();
+        input.press(Button::Key(VirtualKeyCode::D));
+        let events = input.take_recorded().unwrap();
+
+        let mut replayed = Input::new();
+        replayed.set_bindings(input.bindings().clone());
+        events.into_iter().for_each(|event| replayed.apply(event));
+
+        assert!(replayed.held("Jump"));
+        assert!(!replayed.pressed("Jump"));
+        assert_eq!(replayed.axis("Move X"), 1.0);
+    }
+
+    #[test]
+    fn rebinding_a_side_keeps_the_other() {
+        let mut input = input();
+        input.start_rebind(Rebind::AxisPositive {
+            axis: "Move X".to_owned(),
+            index: 0,
+        });
+        input.press(Button::Key(VirtualKeyCode::Right));
+        input.release(Button::Key(VirtualKeyCode::Right));
+        input.end_frame();
+
+        input.press(Button::Key(VirtualKeyCode::Right));
+        assert_eq!(input.axis("Move X"), 1.0);
+        input.press(Button::Key(VirtualKeyCode::A));
+        assert_eq!(input.axis("Move X"), 0.0);
+    }
+
+    #[test]
+    fn rebinding_a_missing_axis_slot_does_nothing() {
+        let mut input = input();
+        input.start_rebind(Rebind::AxisNegative {
+            axis: "Move X".to_owned(),
+            index: 1,
+        });
+        input.press(Button::Key(VirtualKeyCode::Left));
+
+        assert_eq!(input.bindings().axes["Move X"].len(), 1);
+        assert!(input.rebinding().is_none());
+    }
 }
@@ -0,0 +1,23 @@
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
+pub enum LightType {
+    Directional,
+    Point { range: f32 },
+    Spot { range: f32, angle: f32 },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Light {
+    pub light_type: LightType,
+    pub color: [f32; 3],
+    pub intensity: f32,
+}
+
+impl Light {
+    pub fn new(light_type: LightType, color: [f32; 3], intensity: f32) -> Self {
+        Self {
+            light_type,
+            color,
+            intensity,
+        }
+    }
+}
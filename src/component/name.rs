@@ -0,0 +1,8 @@
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Name(pub String);
+
+impl Name {
+    pub fn new(name: &str) -> Self {
+        Self(name.to_owned())
+    }
+}
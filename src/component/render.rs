@@ -3,12 +3,22 @@ pub struct Render {
     pub vertex_buffer: wgpu::Buffer,
     pub index_buffer: wgpu::Buffer,
     pub pipeline: String,
+    pub material: String,
+    // Name of a render target sampled by textured pipelines
+    pub texture: Option<String>,
     pub index_count: u32,
+    pub vertex_type: crate::vertex_type::VertexType,
     pub transform_buffer: Option<std::sync::Arc<wgpu::Buffer>>,
+    // Bound at group 2 after the camera and the material, e.g. the joints of a skin
+    pub bind_group: Option<std::sync::Arc<wgpu::BindGroup>>,
+    pub bounds: crate::math::Aabb,
+    pub positions: Vec<nalgebra_glm::Vec3>,
+    pub indices: Vec<u16>,
+    pub source: Option<crate::shapes::MeshSource>,
 }
 
 impl Render {
-    pub fn new<T: bytemuck::Pod>(
+    pub fn new<T: bytemuck::Pod + crate::vertex_type::Vertex>(
         device: &wgpu::Device,
         mesh: (Vec<T>, Vec<u16>),
         pipeline: String,
@@ -32,28 +42,67 @@ impl Render {
             },
         );
 
+        let positions = mesh
+            .0
+            .iter()
+            .map(|vertex| nalgebra_glm::Vec3::from(vertex.position()))
+            .collect::<Vec<_>>();
+
         Self {
             vertex_buffer,
             index_buffer,
             pipeline,
+            material: "Default".to_owned(),
+            texture: None,
             index_count: mesh.1.len() as u32,
+            vertex_type: T::TYPE,
             transform_buffer,
+            bind_group: None,
+            bounds: crate::math::Aabb::from_points(positions.iter()),
+            positions,
+            indices: mesh.1,
+            source: None,
         }
     }
 
+    pub fn with_source(mut self, source: crate::shapes::MeshSource) -> Self {
+        self.source = Some(source);
+        self
+    }
+
+    pub fn with_texture(mut self, texture: Option<String>) -> Self {
+        self.texture = texture;
+        self
+    }
+
+    pub fn with_bind_group(mut self, bind_group: std::sync::Arc<wgpu::BindGroup>) -> Self {
+        self.bind_group = Some(bind_group);
+        self
+    }
+
+    pub fn with_material(mut self, material: String) -> Self {
+        self.material = material;
+        self
+    }
+
     pub fn draw<'a>(
         &'a self,
         render_pass: &mut wgpu::RenderPass<'a>,
+        state: &mut crate::render_queue::DrawState<'a>,
         render_pipeline: &'a wgpu::RenderPipeline,
         bind_groups: Option<Vec<(u32, &'a wgpu::BindGroup)>>,
+        topology: wgpu::PrimitiveTopology,
+        stats: &mut crate::profiler::RenderStats,
     ) {
-        render_pass.set_pipeline(render_pipeline);
+        state.set_pipeline(render_pass, render_pipeline);
 
-        if let Some(groups) = bind_groups {
-            groups.iter().for_each(|(id, bind_group)| {
-                render_pass.set_bind_group(*id, bind_group, &[]);
-            });
-        }
+        // The bind group of the render takes the place of the one given for its index
+        bind_groups
+            .into_iter()
+            .flatten()
+            .filter(|(id, _)| self.bind_group.is_none() || *id != 2)
+            .chain(self.bind_group.as_deref().map(|bind_group| (2, bind_group)))
+            .for_each(|(id, bind_group)| state.set_bind_group(render_pass, id, bind_group));
 
         render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
 
@@ -63,5 +112,11 @@ impl Render {
 
         render_pass.set_index_buffer(self.index_buffer.slice(..), wgpu::IndexFormat::Uint16);
         render_pass.draw_indexed(0..self.index_count, 0, 0..1);
+
+        stats.add_draw(
+            topology,
+            self.positions.len() as u64,
+            self.index_count as u64,
+        );
     }
 }
@@ -0,0 +1,68 @@
+// Joints are entities with a `Transform`, their matrices are written into `buffer` by
+// `crate::skinning::update`
+#[derive(Debug)]
+pub struct Skin {
+    pub joints: Vec<hecs::Entity>,
+    // Mesh space to the space of each joint in the bind pose
+    pub inverse_bind_matrices: Vec<nalgebra_glm::Mat4>,
+    pub buffer: wgpu::Buffer,
+    pub bind_group: std::sync::Arc<wgpu::BindGroup>,
+}
+
+impl Skin {
+    pub fn create_bind_group_layout(device: &wgpu::Device) -> wgpu::BindGroupLayout {
+        device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::VERTEX,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Storage { read_only: true },
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+            label: Some("Skin Bind Group Layout"),
+        })
+    }
+
+    pub fn new(
+        device: &wgpu::Device,
+        layout: &wgpu::BindGroupLayout,
+        joints: Vec<hecs::Entity>,
+        inverse_bind_matrices: Vec<nalgebra_glm::Mat4>,
+    ) -> Self {
+        // The identity for every joint renders the bind pose until the first update
+        let matrices = vec![
+            crate::component::transform::TransformRaw {
+                transform: nalgebra_glm::Mat4::identity().into(),
+            };
+            joints.len().max(1)
+        ];
+
+        let buffer = wgpu::util::DeviceExt::create_buffer_init(
+            device,
+            &wgpu::util::BufferInitDescriptor {
+                label: Some("Skin Buffer"),
+                contents: bytemuck::cast_slice(&matrices),
+                usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+            },
+        );
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: buffer.as_entire_binding(),
+            }],
+            label: Some("Skin Bind Group"),
+        });
+
+        Self {
+            joints,
+            inverse_bind_matrices,
+            buffer,
+            bind_group: std::sync::Arc::new(bind_group),
+        }
+    }
+}
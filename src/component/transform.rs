@@ -1,14 +1,23 @@
 #![allow(dead_code)]
 
+// Position, rotation and scale are relative to the parent, `matrix` is always
+// rebuilt from them as translation * rotation * scale
 #[derive(Debug)]
 pub struct Transform {
     position: nalgebra_glm::Vec3,
-    rotation: nalgebra_glm::Vec3,
-    size: nalgebra_glm::Vec3,
     q_rotation: nalgebra_glm::Quat,
-    pub parent: Option<std::sync::Arc<std::sync::Mutex<Transform>>>,
+    scale: nalgebra_glm::Vec3,
     pub buffer: Option<std::sync::Arc<wgpu::Buffer>>,
     matrix: nalgebra_glm::Mat4,
+    // Set by every change, the propagation clears it after updating `GlobalTransform`
+    changed: bool,
+}
+
+// World matrix of an entity, computed by `crate::hierarchy::propagate`
+#[derive(Debug, Clone, Copy)]
+pub struct GlobalTransform {
+    pub matrix: nalgebra_glm::Mat4,
+    pub changed: bool,
 }
 
 #[repr(C)]
@@ -19,40 +28,128 @@ pub struct TransformRaw {
 
 impl Default for Transform {
     fn default() -> Self {
-        let position = nalgebra_glm::zero();
-
-        let rot_x = nalgebra_glm::quat_rotate(
-            &nalgebra_glm::Quat::identity(),
-            f32::to_radians(0.0),
-            &nalgebra_glm::Vec3::x(),
-        );
-        let rot_y = nalgebra_glm::quat_rotate(
-            &nalgebra_glm::Quat::identity(),
-            f32::to_radians(0.0),
-            &nalgebra_glm::Vec3::y(),
-        );
-        let rot_z = nalgebra_glm::quat_rotate(
-            &nalgebra_glm::Quat::identity(),
-            f32::to_radians(0.0),
-            &nalgebra_glm::Vec3::z(),
-        );
-
-        let q_rotation = (rot_x * rot_y * rot_z).normalize();
-        let rotation = nalgebra_glm::quat_euler_angles(&q_rotation);
+        Self {
+            position: nalgebra_glm::zero(),
+            q_rotation: nalgebra_glm::Quat::identity(),
+            scale: nalgebra_glm::vec3(1.0, 1.0, 1.0),
+            buffer: None,
+            matrix: nalgebra_glm::Mat4::identity(),
+            changed: true,
+        }
+    }
+}
 
-        let mut matrix = nalgebra_glm::translate(&nalgebra_glm::Mat4::identity(), &position);
-        matrix = matrix * nalgebra_glm::quat_to_mat4(&q_rotation);
+impl Default for GlobalTransform {
+    fn default() -> Self {
+        Self {
+            matrix: nalgebra_glm::Mat4::identity(),
+            changed: true,
+        }
+    }
+}
 
+impl GlobalTransform {
+    pub fn new(matrix: nalgebra_glm::Mat4) -> Self {
         Self {
-            position,
-            rotation,
-            size: nalgebra_glm::vec3(1.0, 1.0, 1.0),
-            q_rotation,
-            parent: None,
-            buffer: None,
             matrix,
+            changed: true,
+        }
+    }
+
+    pub fn to_raw(self) -> TransformRaw {
+        TransformRaw {
+            transform: self.matrix.into(),
         }
     }
+
+    pub fn position(&self) -> nalgebra_glm::Vec3 {
+        (self.matrix * nalgebra_glm::vec4(0.0, 0.0, 0.0, 1.0)).xyz()
+    }
+
+    pub fn forward(&self) -> nalgebra_glm::Vec3 {
+        nalgebra_glm::normalize(&(self.matrix * nalgebra_glm::vec4(0.0, 0.0, 1.0, 0.0)).xyz())
+    }
+
+    pub fn up(&self) -> nalgebra_glm::Vec3 {
+        nalgebra_glm::normalize(&(self.matrix * nalgebra_glm::vec4(0.0, 1.0, 0.0, 0.0)).xyz())
+    }
+
+    pub fn right(&self) -> nalgebra_glm::Vec3 {
+        nalgebra_glm::normalize(&(self.matrix * nalgebra_glm::vec4(1.0, 0.0, 0.0, 0.0)).xyz())
+    }
+
+    pub fn inverse(&self) -> Self {
+        Self::new(nalgebra_glm::inverse(&self.matrix))
+    }
+
+    // Local space of the entity to world space and back
+    pub fn transform_point(&self, point: &nalgebra_glm::Vec3) -> nalgebra_glm::Vec3 {
+        (self.matrix * point.push(1.0)).xyz()
+    }
+
+    pub fn transform_vector(&self, vector: &nalgebra_glm::Vec3) -> nalgebra_glm::Vec3 {
+        (self.matrix * vector.push(0.0)).xyz()
+    }
+
+    pub fn inverse_transform_point(&self, point: &nalgebra_glm::Vec3) -> nalgebra_glm::Vec3 {
+        (nalgebra_glm::inverse(&self.matrix) * point.push(1.0)).xyz()
+    }
+
+    pub fn inverse_transform_vector(&self, vector: &nalgebra_glm::Vec3) -> nalgebra_glm::Vec3 {
+        (nalgebra_glm::inverse(&self.matrix) * vector.push(0.0)).xyz()
+    }
+
+    pub fn to_transform(self) -> Transform {
+        Transform::from_matrix(&self.matrix)
+    }
+}
+
+// Euler angles are in degrees and applied in X, Y, Z order around the parent axes
+fn euler_to_quat(rotation: &nalgebra_glm::Vec3) -> nalgebra_glm::Quat {
+    nalgebra::UnitQuaternion::from_euler_angles(
+        rotation.x.to_radians(),
+        rotation.y.to_radians(),
+        rotation.z.to_radians(),
+    )
+    .into_inner()
+}
+
+fn quat_to_euler(rotation: &nalgebra_glm::Quat) -> nalgebra_glm::Vec3 {
+    let (x, y, z) = nalgebra::UnitQuaternion::new_normalize(*rotation).euler_angles();
+
+    nalgebra_glm::vec3(x.to_degrees(), y.to_degrees(), z.to_degrees())
+}
+
+// Splits a matrix into translation, rotation and scale. Shear can't be represented
+// and is dropped, a mirroring is moved into a negative x scale
+pub fn decompose(
+    matrix: &nalgebra_glm::Mat4,
+) -> (nalgebra_glm::Vec3, nalgebra_glm::Quat, nalgebra_glm::Vec3) {
+    let position = matrix.column(3).xyz();
+
+    let mut axes = nalgebra_glm::mat4_to_mat3(matrix);
+    let mut scale = nalgebra_glm::vec3(
+        axes.column(0).norm(),
+        axes.column(1).norm(),
+        axes.column(2).norm(),
+    );
+    if axes.determinant() < 0.0 {
+        scale.x = -scale.x;
+    }
+
+    (0..3).for_each(|i| {
+        if scale[i] != 0.0 {
+            let column = axes.column(i) / scale[i];
+            axes.set_column(i, &column);
+        }
+    });
+
+    let rotation = match scale.iter().all(|scale| *scale != 0.0) {
+        true => nalgebra::UnitQuaternion::from_matrix(&axes).into_inner(),
+        false => nalgebra_glm::Quat::identity(),
+    };
+
+    (position, rotation, scale)
 }
 
 pub struct TransformBuild(Transform);
@@ -63,220 +160,434 @@ impl TransformBuild {
     }
 
     pub fn with_position(mut self, position: nalgebra_glm::Vec3) -> Self {
-        self.0.position = position;
-        self.0.matrix = self.0.matrix * nalgebra_glm::translate(&self.0.matrix, &position);
-
+        self.0.set_position(&position);
         self
     }
 
     pub fn with_rotation(mut self, rotation: nalgebra_glm::Vec3) -> Self {
-        let rot_x = nalgebra_glm::quat_rotate(
-            &nalgebra_glm::Quat::identity(),
-            f32::to_radians(rotation.x),
-            &nalgebra_glm::Vec3::x(),
-        );
-        let rot_y = nalgebra_glm::quat_rotate(
-            &nalgebra_glm::Quat::identity(),
-            f32::to_radians(rotation.y),
-            &nalgebra_glm::Vec3::y(),
-        );
-        let rot_z = nalgebra_glm::quat_rotate(
-            &nalgebra_glm::Quat::identity(),
-            f32::to_radians(rotation.z),
-            &nalgebra_glm::Vec3::z(),
-        );
-
-        let rotation = (rot_x * rot_y * rot_z).normalize();
+        self.0.set_rotation_euler(&rotation);
+        self
+    }
 
-        self.0.q_rotation = rotation;
-        self.0.rotation = nalgebra_glm::quat_euler_angles(&rotation);
-        self.0.matrix = self.0.matrix * nalgebra_glm::quat_to_mat4(&self.0.q_rotation);
+    pub fn with_q_rotation(mut self, rotation: nalgebra_glm::Quat) -> Self {
+        self.0.set_rotation(&rotation);
+        self
+    }
 
+    pub fn with_scale(mut self, scale: nalgebra_glm::Vec3) -> Self {
+        self.0.set_scale(&scale);
         self
     }
 
-    pub fn with_parent(mut self, parent: std::sync::Arc<std::sync::Mutex<Transform>>) -> Self {
-        self.0.parent = Some(parent);
+    pub fn with_matrix(mut self, matrix: &nalgebra_glm::Mat4) -> Self {
+        self.0.set_matrix(matrix);
         self
     }
 
     pub fn with_buffer(mut self, device: &wgpu::Device) -> Self {
-        self.0.buffer = Some(std::sync::Arc::new(
+        self.0.create_buffer(device);
+        self
+    }
+
+    pub fn build(self) -> Transform {
+        self.0
+    }
+}
+
+impl Default for TransformBuild {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Transform {
+    pub fn from_matrix(matrix: &nalgebra_glm::Mat4) -> Self {
+        TransformBuild::new().with_matrix(matrix).build()
+    }
+
+    pub fn create_buffer(&mut self, device: &wgpu::Device) {
+        self.buffer = Some(std::sync::Arc::new(
             wgpu::util::DeviceExt::create_buffer_init(
                 device,
                 &wgpu::util::BufferInitDescriptor {
                     label: Some("Transform Buffer"),
-                    contents: bytemuck::cast_slice(&[self.0.to_raw()]),
+                    contents: bytemuck::cast_slice(&[self.to_raw()]),
                     usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
                 },
             ),
         ));
+        // The world matrix is uploaded by the engine after the next propagation
+        self.changed = true;
+    }
 
-        self
+    // Only the local matrix is known here, it matches the world matrix for roots
+    pub fn to_raw(&self) -> TransformRaw {
+        TransformRaw {
+            transform: self.matrix.into(),
+        }
     }
 
-    pub fn build(self) -> Transform {
-        self.0
+    fn rebuild_matrix(&mut self) {
+        self.matrix = nalgebra_glm::translate(&nalgebra_glm::Mat4::identity(), &self.position)
+            * nalgebra_glm::quat_to_mat4(&self.q_rotation)
+            * nalgebra_glm::scale(&nalgebra_glm::Mat4::identity(), &self.scale);
+        self.changed = true;
     }
-}
 
-impl Transform {
-    pub fn to_raw(&self) -> TransformRaw {
-        let transform = self.matrix;
-        let final_transform = self.get_parent_matrix() * transform;
+    pub fn is_changed(&self) -> bool {
+        self.changed
+    }
 
-        TransformRaw {
-            transform: final_transform.into(),
-        }
+    pub fn mark_changed(&mut self) {
+        self.changed = true;
     }
 
+    pub fn clear_changed(&mut self) {
+        self.changed = false;
+    }
+
+    // Local matrix, relative to the parent
     pub fn get_matrix(&self) -> nalgebra_glm::Mat4 {
         self.matrix
     }
 
-    pub fn get_parent_matrix(&self) -> nalgebra_glm::Mat4 {
-        let mut parent_matrix = nalgebra_glm::Mat4::identity();
-        if let Some(parent) = self.parent.as_ref() {
-            let _parent_matrix = parent.lock().unwrap().get_matrix();
-            let _parent_parent_matrix = parent.lock().unwrap().get_parent_matrix();
+    pub fn set_matrix(&mut self, matrix: &nalgebra_glm::Mat4) {
+        let (position, rotation, scale) = decompose(matrix);
 
-            parent_matrix = _parent_parent_matrix * _parent_matrix;
-        }
+        self.position = position;
+        self.q_rotation = rotation;
+        self.scale = scale;
+        self.rebuild_matrix();
+    }
+
+    // Without a buffer, the inverse is not meant to be rendered. A rotated non-uniform scale
+    // inverts to a shear, which is dropped, `inverse_transform_point` handles those
+    pub fn inverse(&self) -> Transform {
+        Transform::from_matrix(&nalgebra_glm::inverse(&self.matrix))
+    }
+
+    // Local space to the space of the parent and back
+    pub fn transform_point(&self, point: &nalgebra_glm::Vec3) -> nalgebra_glm::Vec3 {
+        (self.matrix * point.push(1.0)).xyz()
+    }
+
+    pub fn transform_vector(&self, vector: &nalgebra_glm::Vec3) -> nalgebra_glm::Vec3 {
+        (self.matrix * vector.push(0.0)).xyz()
+    }
+
+    pub fn inverse_transform_point(&self, point: &nalgebra_glm::Vec3) -> nalgebra_glm::Vec3 {
+        (nalgebra_glm::inverse(&self.matrix) * point.push(1.0)).xyz()
+    }
+
+    pub fn inverse_transform_vector(&self, vector: &nalgebra_glm::Vec3) -> nalgebra_glm::Vec3 {
+        (nalgebra_glm::inverse(&self.matrix) * vector.push(0.0)).xyz()
+    }
 
-        parent_matrix
+    // Position and scale are interpolated linearly, `lerp` normalizes the linearly
+    // interpolated rotation while `slerp` keeps a constant angular speed
+    pub fn lerp(&self, other: &Transform, t: f32) -> Transform {
+        let rotation = match self.q_rotation.dot(&other.q_rotation) < 0.0 {
+            true => -other.q_rotation,
+            false => other.q_rotation,
+        };
+
+        TransformBuild::new()
+            .with_position(nalgebra_glm::lerp(&self.position, &other.position, t))
+            .with_q_rotation(nalgebra_glm::quat_lerp(&self.q_rotation, &rotation, t))
+            .with_scale(nalgebra_glm::lerp(&self.scale, &other.scale, t))
+            .build()
+    }
+
+    pub fn slerp(&self, other: &Transform, t: f32) -> Transform {
+        let rotation = nalgebra::UnitQuaternion::new_normalize(self.q_rotation)
+            .slerp(
+                &nalgebra::UnitQuaternion::new_normalize(other.q_rotation),
+                t,
+            )
+            .into_inner();
+
+        TransformBuild::new()
+            .with_position(nalgebra_glm::lerp(&self.position, &other.position, t))
+            .with_q_rotation(rotation)
+            .with_scale(nalgebra_glm::lerp(&self.scale, &other.scale, t))
+            .build()
     }
 
     pub fn get_position(&self) -> nalgebra_glm::Vec3 {
         self.position
     }
 
-    pub fn set_position(&mut self, new_position: &nalgebra_glm::Vec3) {
-        let pos = new_position - self.position;
+    pub fn set_position(&mut self, position: &nalgebra_glm::Vec3) {
+        self.position = *position;
+        self.rebuild_matrix();
+    }
 
-        self.position = new_position.clone();
+    pub fn add_position(&mut self, offset: &nalgebra_glm::Vec3) {
+        self.position += offset;
+        self.rebuild_matrix();
+    }
 
-        self.matrix = self.matrix * nalgebra_glm::translate(&nalgebra_glm::Mat4::identity(), &pos);
+    pub fn get_scale(&self) -> nalgebra_glm::Vec3 {
+        self.scale
     }
 
-    pub fn add_position(&mut self, new_position: &nalgebra_glm::Vec3) {
-        self.position = self.position + new_position.clone();
+    pub fn set_scale(&mut self, scale: &nalgebra_glm::Vec3) {
+        self.scale = *scale;
+        self.rebuild_matrix();
+    }
 
-        self.matrix =
-            self.matrix * nalgebra_glm::translate(&nalgebra_glm::Mat4::identity(), &new_position);
+    pub fn get_rotation(&self) -> nalgebra_glm::Vec3 {
+        quat_to_euler(&self.q_rotation)
     }
 
+    pub fn get_q_rotation(&self) -> nalgebra_glm::Quat {
+        self.q_rotation
+    }
+
+    pub fn set_rotation(&mut self, rotation: &nalgebra_glm::Quat) {
+        self.q_rotation = rotation.normalize();
+        self.rebuild_matrix();
+    }
+
+    pub fn set_rotation_euler(&mut self, rotation: &nalgebra_glm::Vec3) {
+        self.set_rotation(&euler_to_quat(rotation));
+    }
+
+    // Local axes expressed in the space of the parent
     pub fn forward(&self) -> nalgebra_glm::Vec3 {
-        nalgebra_glm::row(&nalgebra_glm::quat_to_mat4(&self.q_rotation), 2).xyz()
+        nalgebra_glm::quat_rotate_vec3(&self.q_rotation, &nalgebra_glm::Vec3::z())
     }
 
     pub fn up(&self) -> nalgebra_glm::Vec3 {
-        nalgebra_glm::row(&nalgebra_glm::quat_to_mat4(&self.q_rotation), 1).xyz()
+        nalgebra_glm::quat_rotate_vec3(&self.q_rotation, &nalgebra_glm::Vec3::y())
     }
 
     pub fn right(&self) -> nalgebra_glm::Vec3 {
-        nalgebra_glm::row(&nalgebra_glm::quat_to_mat4(&self.q_rotation), 0).xyz()
+        nalgebra_glm::quat_rotate_vec3(&self.q_rotation, &nalgebra_glm::Vec3::x())
     }
 
-    pub fn get_rotation(&self) -> nalgebra_glm::Vec3 {
-        self.rotation
+    // `add_rotation_*` rotate around the local axes of the transform, the `global`
+    // variants around the axes of the parent space, which is world space for roots
+    pub fn add_rotation_axis(&mut self, angle: f32, axis: &nalgebra_glm::Vec3) {
+        let rot = nalgebra_glm::quat_angle_axis(angle.to_radians(), &nalgebra_glm::normalize(axis));
+
+        self.set_rotation(&(self.q_rotation * rot));
     }
 
-    pub fn get_q_rotation(&self) -> nalgebra_glm::Quat {
-        self.q_rotation
+    pub fn add_rotation_global_axis(&mut self, angle: f32, axis: &nalgebra_glm::Vec3) {
+        let rot = nalgebra_glm::quat_angle_axis(angle.to_radians(), &nalgebra_glm::normalize(axis));
+
+        self.set_rotation(&(rot * self.q_rotation));
     }
 
-    pub fn add_rotation_x(&mut self, angle: f32) {
-        let rot = nalgebra_glm::quat_rotate(
-            &nalgebra_glm::Quat::identity(),
-            angle.to_radians(),
-            &self.right(),
-        );
+    // Turns the forward axis towards `target`, both `target` and `up` are in the space
+    // of the parent
+    pub fn look_at(&mut self, target: &nalgebra_glm::Vec3, up: &nalgebra_glm::Vec3) {
+        let direction = target - self.position;
+        if nalgebra_glm::length2(&direction) < f32::EPSILON {
+            return;
+        }
 
-        self.q_rotation = (self.q_rotation * rot).normalize();
+        // Keep the current up axis when looking straight along `up`
+        let up = match nalgebra_glm::length2(&nalgebra_glm::cross(&direction, up)) < f32::EPSILON {
+            true => self.up(),
+            false => *up,
+        };
 
-        self.rotation = nalgebra_glm::degrees(&nalgebra_glm::quat_euler_angles(&self.q_rotation));
+        self.set_rotation(&nalgebra::UnitQuaternion::face_towards(&direction, &up).into_inner());
+    }
 
-        self.matrix = self.matrix * nalgebra_glm::quat_to_mat4(&rot);
+    // Orbits the transform around `point` in the space of the parent, the rotation
+    // follows so the same side keeps facing the point
+    pub fn rotate_around(
+        &mut self,
+        point: &nalgebra_glm::Vec3,
+        axis: &nalgebra_glm::Vec3,
+        angle: f32,
+    ) {
+        let rot = nalgebra_glm::quat_angle_axis(angle.to_radians(), &nalgebra_glm::normalize(axis));
+
+        self.position = point + nalgebra_glm::quat_rotate_vec3(&rot, &(self.position - point));
+        self.set_rotation(&(rot * self.get_q_rotation()));
+    }
+
+    pub fn add_rotation_x(&mut self, angle: f32) {
+        self.add_rotation_axis(angle, &nalgebra_glm::Vec3::x());
     }
 
     pub fn add_rotation_y(&mut self, angle: f32) {
-        let rot = nalgebra_glm::quat_rotate(
-            &nalgebra_glm::Quat::identity(),
-            angle.to_radians(),
-            &self.up(),
-        );
+        self.add_rotation_axis(angle, &nalgebra_glm::Vec3::y());
+    }
 
-        self.q_rotation = (self.q_rotation * rot).normalize();
+    pub fn add_rotation_z(&mut self, angle: f32) {
+        self.add_rotation_axis(angle, &nalgebra_glm::Vec3::z());
+    }
 
-        self.rotation = nalgebra_glm::degrees(&nalgebra_glm::quat_euler_angles(&self.q_rotation));
+    pub fn add_rotation_global_x(&mut self, angle: f32) {
+        self.add_rotation_global_axis(angle, &nalgebra_glm::Vec3::x());
+    }
 
-        self.matrix = self.matrix * nalgebra_glm::quat_to_mat4(&rot);
+    pub fn add_rotation_global_y(&mut self, angle: f32) {
+        self.add_rotation_global_axis(angle, &nalgebra_glm::Vec3::y());
     }
 
-    pub fn add_rotation_z(&mut self, angle: f32) {
-        let rot = nalgebra_glm::quat_rotate(
-            &nalgebra_glm::Quat::identity(),
-            angle.to_radians(),
-            &self.forward(),
-        );
+    pub fn add_rotation_global_z(&mut self, angle: f32) {
+        self.add_rotation_global_axis(angle, &nalgebra_glm::Vec3::z());
+    }
+}
 
-        self.q_rotation = (self.q_rotation * rot).normalize();
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-        self.rotation = nalgebra_glm::degrees(&nalgebra_glm::quat_euler_angles(&self.q_rotation));
+    const TOLERANCE: f32 = 1e-4;
 
-        self.matrix = self.matrix * nalgebra_glm::quat_to_mat4(&rot);
+    fn close(a: &nalgebra_glm::Vec3, b: &nalgebra_glm::Vec3) -> bool {
+        nalgebra_glm::distance(a, b) < TOLERANCE
     }
 
-    pub fn add_rotation_global_x(&mut self, angle: f32) {
-        let rot = nalgebra_glm::quat_rotate(
-            &nalgebra_glm::Quat::identity(),
-            angle.to_radians(),
-            &nalgebra_glm::Vec3::x(),
-        );
+    // `q` and `-q` are the same rotation
+    fn same_rotation(a: &nalgebra_glm::Quat, b: &nalgebra_glm::Quat) -> bool {
+        a.dot(b).abs() > 1.0 - TOLERANCE
+    }
 
-        self.q_rotation = (self.q_rotation * rot).normalize();
+    fn sample() -> Transform {
+        TransformBuild::new()
+            .with_position(nalgebra_glm::vec3(1.0, -2.0, 3.0))
+            .with_rotation(nalgebra_glm::vec3(30.0, 45.0, -60.0))
+            .with_scale(nalgebra_glm::vec3(2.0, 0.5, 1.5))
+            .build()
+    }
+
+    #[test]
+    fn matrices_decompose_into_the_same_trs() {
+        let transform = sample();
+        let (position, rotation, scale) = decompose(&transform.get_matrix());
 
-        self.rotation = nalgebra_glm::degrees(&nalgebra_glm::quat_euler_angles(&self.q_rotation));
+        assert!(close(&position, &transform.get_position()));
+        assert!(same_rotation(&rotation, &transform.get_q_rotation()));
+        assert!(close(&scale, &transform.get_scale()));
 
-        self.matrix = self.matrix * nalgebra_glm::quat_to_mat4(&rot);
+        let rebuilt = Transform::from_matrix(&transform.get_matrix());
+        assert!((rebuilt.get_matrix() - transform.get_matrix()).abs().max() < TOLERANCE);
     }
 
-    pub fn add_rotation_global_y(&mut self, angle: f32) {
-        let rot = nalgebra_glm::quat_rotate(
-            &nalgebra_glm::Quat::identity(),
-            angle.to_radians(),
-            &nalgebra_glm::Vec3::y(),
+    #[test]
+    fn mirrored_matrices_decompose_with_negative_x_scale() {
+        let matrix = nalgebra_glm::scale(
+            &nalgebra_glm::Mat4::identity(),
+            &nalgebra_glm::vec3(-2.0, 1.0, 1.0),
         );
+        let (_, rotation, scale) = decompose(&matrix);
 
-        self.q_rotation = (self.q_rotation * rot).normalize();
+        assert!(close(&scale, &nalgebra_glm::vec3(-2.0, 1.0, 1.0)));
+        assert!(same_rotation(&rotation, &nalgebra_glm::Quat::identity()));
+    }
 
-        self.rotation = nalgebra_glm::degrees(&nalgebra_glm::quat_euler_angles(&self.q_rotation));
+    #[test]
+    fn euler_angles_round_trip() {
+        let transform = sample();
 
-        self.matrix = self.matrix * nalgebra_glm::quat_to_mat4(&rot);
+        assert!(close(
+            &transform.get_rotation(),
+            &nalgebra_glm::vec3(30.0, 45.0, -60.0)
+        ));
     }
 
-    pub fn add_rotation_global_z(&mut self, angle: f32) {
-        let rot = nalgebra_glm::quat_rotate(
-            &nalgebra_glm::Quat::identity(),
-            angle.to_radians(),
-            &nalgebra_glm::Vec3::z(),
-        );
+    #[test]
+    fn inverse_undoes_the_transform() {
+        let point = nalgebra_glm::vec3(4.0, 5.0, -6.0);
 
-        self.q_rotation = (self.q_rotation * rot).normalize();
+        let transform = sample();
+        assert!(close(
+            &transform.inverse_transform_point(&transform.transform_point(&point)),
+            &point
+        ));
 
-        self.rotation = nalgebra_glm::degrees(&nalgebra_glm::quat_euler_angles(&self.q_rotation));
+        let uniform = TransformBuild::new()
+            .with_position(nalgebra_glm::vec3(1.0, -2.0, 3.0))
+            .with_rotation(nalgebra_glm::vec3(30.0, 45.0, -60.0))
+            .with_scale(nalgebra_glm::vec3(2.0, 2.0, 2.0))
+            .build();
+        assert!(close(
+            &uniform
+                .inverse()
+                .transform_point(&uniform.transform_point(&point)),
+            &point
+        ));
+    }
 
-        self.matrix = self.matrix * nalgebra_glm::quat_to_mat4(&rot);
+    #[test]
+    fn interpolation_ends_on_both_transforms() {
+        let from = Transform::default();
+        let to = sample();
+
+        [from.lerp(&to, 0.0), from.slerp(&to, 0.0)]
+            .iter()
+            .for_each(|start| {
+                assert!((start.get_matrix() - from.get_matrix()).abs().max() < TOLERANCE)
+            });
+        [from.lerp(&to, 1.0), from.slerp(&to, 1.0)]
+            .iter()
+            .for_each(|end| assert!((end.get_matrix() - to.get_matrix()).abs().max() < TOLERANCE));
     }
 
-    pub fn add_rotation_axis(&mut self, angle: f32, axis: &nalgebra_glm::Vec3) {
-        let rot =
-            nalgebra_glm::quat_rotate(&nalgebra_glm::Quat::identity(), angle.to_radians(), axis);
+    #[test]
+    fn slerp_turns_at_a_constant_rate() {
+        let from = Transform::default();
+        let to = TransformBuild::new()
+            .with_position(nalgebra_glm::vec3(2.0, 0.0, 0.0))
+            .with_rotation(nalgebra_glm::vec3(0.0, 90.0, 0.0))
+            .build();
+
+        let half = from.slerp(&to, 0.5);
+        assert!(close(
+            &half.get_position(),
+            &nalgebra_glm::vec3(1.0, 0.0, 0.0)
+        ));
+        assert!(close(
+            &half.get_rotation(),
+            &nalgebra_glm::vec3(0.0, 45.0, 0.0)
+        ));
 
-        self.q_rotation = (self.q_rotation * rot).normalize();
+        let quarter = from.slerp(&to, 0.25);
+        assert!(close(
+            &quarter.get_rotation(),
+            &nalgebra_glm::vec3(0.0, 22.5, 0.0)
+        ));
+    }
 
-        self.rotation = nalgebra_glm::degrees(&nalgebra_glm::quat_euler_angles(&self.q_rotation));
+    #[test]
+    fn lerp_takes_the_short_way_around() {
+        let from = TransformBuild::new()
+            .with_rotation(nalgebra_glm::vec3(0.0, 170.0, 0.0))
+            .build();
+        let to = TransformBuild::new()
+            .with_q_rotation(-euler_to_quat(&nalgebra_glm::vec3(0.0, -170.0, 0.0)))
+            .build();
+
+        // Halfway between 170 and 190 degrees, not back through 0
+        let half = from.lerp(&to, 0.5);
+        assert!(close(&half.forward(), &-nalgebra_glm::Vec3::z()));
+    }
+
+    #[test]
+    fn look_at_points_forward_at_the_target() {
+        let mut transform = TransformBuild::new()
+            .with_position(nalgebra_glm::vec3(1.0, 2.0, 3.0))
+            .build();
+        let target = nalgebra_glm::vec3(-3.0, 0.0, 7.0);
+        transform.look_at(&target, &nalgebra_glm::Vec3::y());
+
+        assert!(close(
+            &transform.forward(),
+            &nalgebra_glm::normalize(&(target - transform.get_position()))
+        ));
+        assert!(nalgebra_glm::dot(&transform.up(), &nalgebra_glm::Vec3::y()) > 0.0);
 
-        self.matrix = self.matrix * nalgebra_glm::quat_to_mat4(&rot);
+        // Straight up falls back on the current up axis instead of failing
+        let above = transform.get_position() + nalgebra_glm::Vec3::y();
+        transform.look_at(&above, &nalgebra_glm::Vec3::y());
+        assert!(close(&transform.forward(), &nalgebra_glm::Vec3::y()));
     }
 }
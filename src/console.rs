@@ -0,0 +1,871 @@
+use std::collections::{BTreeMap, VecDeque};
+
+// Lines kept by the console, the oldest ones are dropped first
+const MAX_LINES: usize = 1000;
+
+// Records logged since the console last read them
+static CAPTURED: std::sync::Mutex<Vec<LogLine>> = std::sync::Mutex::new(Vec::new());
+
+#[derive(Debug, Clone)]
+pub struct LogLine {
+    pub level: log::Level,
+    pub target: String,
+    pub message: String,
+}
+
+// Prints what env_logger lets through and keeps every record up to info for the console
+struct ConsoleLogger {
+    inner: env_logger::Logger,
+}
+
+impl log::Log for ConsoleLogger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        metadata.level() <= log::Level::Info || self.inner.enabled(metadata)
+    }
+
+    fn log(&self, record: &log::Record) {
+        let printed = self.inner.matches(record);
+        if printed {
+            self.inner.log(record);
+        }
+
+        if printed || record.level() <= log::Level::Info {
+            if let Ok(mut captured) = CAPTURED.lock() {
+                captured.push(LogLine {
+                    level: record.level(),
+                    target: record.target().to_owned(),
+                    message: record.args().to_string(),
+                });
+            }
+        }
+    }
+
+    fn flush(&self) {
+        self.inner.flush();
+    }
+}
+
+// Replaces `env_logger::init_from_env`, the console also sees the records that are not
+// printed
+pub fn init_logger(env: env_logger::Env) {
+    let inner = env_logger::Builder::from_env(env).build();
+    let max_level = inner.filter().max(log::LevelFilter::Info);
+
+    if log::set_boxed_logger(Box::new(ConsoleLogger { inner })).is_ok() {
+        log::set_max_level(max_level);
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CVarValue {
+    Bool(bool),
+    Int(i64),
+    Float(f32),
+    String(String),
+}
+
+impl std::fmt::Display for CVarValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CVarValue::Bool(value) => write!(f, "{}", if *value { "on" } else { "off" }),
+            CVarValue::Int(value) => write!(f, "{}", value),
+            CVarValue::Float(value) => write!(f, "{}", value),
+            CVarValue::String(value) => write!(f, "\"{}\"", value),
+        }
+    }
+}
+
+impl From<bool> for CVarValue {
+    fn from(value: bool) -> Self {
+        CVarValue::Bool(value)
+    }
+}
+
+impl From<i64> for CVarValue {
+    fn from(value: i64) -> Self {
+        CVarValue::Int(value)
+    }
+}
+
+impl From<f32> for CVarValue {
+    fn from(value: f32) -> Self {
+        CVarValue::Float(value)
+    }
+}
+
+impl From<&str> for CVarValue {
+    fn from(value: &str) -> Self {
+        CVarValue::String(value.to_owned())
+    }
+}
+
+impl CVarValue {
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            CVarValue::Bool(_) => "bool",
+            CVarValue::Int(_) => "int",
+            CVarValue::Float(_) => "float",
+            CVarValue::String(_) => "string",
+        }
+    }
+
+    // Reads `text` as a value of the same type
+    pub fn parse(&self, text: &str) -> Result<CVarValue, String> {
+        let error = || format!("\"{}\" is not a {}", text, self.type_name());
+
+        match self {
+            CVarValue::Bool(_) => match text.to_lowercase().as_str() {
+                "on" | "true" | "1" => Ok(CVarValue::Bool(true)),
+                "off" | "false" | "0" => Ok(CVarValue::Bool(false)),
+                _ => Err(error()),
+            },
+            CVarValue::Int(_) => text.parse().map(CVarValue::Int).map_err(|_| error()),
+            CVarValue::Float(_) => text.parse().map(CVarValue::Float).map_err(|_| error()),
+            CVarValue::String(_) => Ok(CVarValue::String(text.to_owned())),
+        }
+    }
+
+    pub fn as_bool(&self) -> bool {
+        match self {
+            CVarValue::Bool(value) => *value,
+            CVarValue::Int(value) => *value != 0,
+            CVarValue::Float(value) => *value != 0.0,
+            CVarValue::String(value) => !value.is_empty(),
+        }
+    }
+
+    pub fn as_int(&self) -> i64 {
+        match self {
+            CVarValue::Bool(value) => *value as i64,
+            CVarValue::Int(value) => *value,
+            CVarValue::Float(value) => *value as i64,
+            CVarValue::String(value) => value.parse().unwrap_or_default(),
+        }
+    }
+
+    pub fn as_float(&self) -> f32 {
+        match self {
+            CVarValue::Bool(value) => *value as i32 as f32,
+            CVarValue::Int(value) => *value as f32,
+            CVarValue::Float(value) => *value,
+            CVarValue::String(value) => value.parse().unwrap_or_default(),
+        }
+    }
+}
+
+// What commands and change callbacks can read and change
+pub struct ConsoleContext<'a> {
+    pub world: &'a mut hecs::World,
+    // The camera the window is seen through
+    pub camera: hecs::Entity,
+    pub editor: &'a mut crate::editor::Editor,
+    // Set during recordings and replays, commands and variables can't change anything then
+    pub locked: bool,
+}
+
+const LOCKED: &str = "can't change anything during a recording or a replay";
+
+// Arguments after the command name, returns the text to print
+pub type CommandHandler = Box<dyn FnMut(&[&str], &mut ConsoleContext) -> Result<String, String>>;
+
+pub type CVarCallback = Box<dyn FnMut(&CVarValue, &mut ConsoleContext)>;
+
+struct Command {
+    help: String,
+    // Offered for the first argument
+    completions: Vec<String>,
+    handler: CommandHandler,
+}
+
+pub struct CVar {
+    pub value: CVarValue,
+    pub default: CVarValue,
+    pub help: String,
+    callback: Option<CVarCallback>,
+}
+
+enum Line {
+    Log(LogLine),
+    Command(String),
+    Output(String),
+    Error(String),
+}
+
+pub struct Console {
+    open: bool,
+    // Set when the console opens or closes, until it is taken
+    toggled: bool,
+    focus_input: bool,
+    lines: VecDeque<Line>,
+    // Most verbose level shown
+    level: log::LevelFilter,
+    input: String,
+    history: Vec<String>,
+    history_index: Option<usize>,
+    commands: BTreeMap<String, Command>,
+    cvars: BTreeMap<String, CVar>,
+}
+
+impl Default for Console {
+    fn default() -> Self {
+        Self {
+            open: false,
+            toggled: false,
+            focus_input: false,
+            lines: VecDeque::new(),
+            level: log::LevelFilter::Info,
+            input: String::new(),
+            history: vec![],
+            history_index: None,
+            commands: BTreeMap::new(),
+            cvars: BTreeMap::new(),
+        }
+    }
+}
+
+impl Console {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+
+    pub fn set_open(&mut self, open: bool) {
+        if open != self.open {
+            self.open = open;
+            self.toggled = true;
+            self.focus_input = open;
+        }
+    }
+
+    pub fn toggle(&mut self) {
+        self.set_open(!self.open);
+    }
+
+    // Whether the console opened or closed since the last call
+    pub fn take_toggled(&mut self) -> bool {
+        std::mem::take(&mut self.toggled)
+    }
+
+    // A command with the same name is replaced
+    pub fn add_command(
+        &mut self,
+        name: &str,
+        help: &str,
+        completions: &[&str],
+        handler: impl FnMut(&[&str], &mut ConsoleContext) -> Result<String, String> + 'static,
+    ) {
+        self.commands.insert(
+            name.to_owned(),
+            Command {
+                help: help.to_owned(),
+                completions: completions.iter().map(|s| s.to_string()).collect(),
+                handler: Box::new(handler),
+            },
+        );
+    }
+
+    // The type of `default` is the type of the variable, a variable with the same name keeps
+    // its value if it has the same type
+    pub fn add_cvar(&mut self, name: &str, default: impl Into<CVarValue>, help: &str) {
+        let default = default.into();
+        let value = match self.cvars.remove(name) {
+            Some(cvar) if cvar.value.type_name() == default.type_name() => cvar.value,
+            _ => default.clone(),
+        };
+
+        self.cvars.insert(
+            name.to_owned(),
+            CVar {
+                value,
+                default,
+                help: help.to_owned(),
+                callback: None,
+            },
+        );
+    }
+
+    // Called after every change made from the console
+    pub fn on_change(
+        &mut self,
+        name: &str,
+        callback: impl FnMut(&CVarValue, &mut ConsoleContext) + 'static,
+    ) -> bool {
+        match self.cvars.get_mut(name) {
+            Some(cvar) => {
+                cvar.callback = Some(Box::new(callback));
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn cvar(&self, name: &str) -> Option<&CVarValue> {
+        self.cvars.get(name).map(|cvar| &cvar.value)
+    }
+
+    // Changes the value without running the callback, for variables mirroring state that
+    // changed elsewhere. Values of another type are ignored
+    pub fn sync_cvar(&mut self, name: &str, value: impl Into<CVarValue>) {
+        let value = value.into();
+        if let Some(cvar) = self.cvars.get_mut(name) {
+            if cvar.value.type_name() == value.type_name() {
+                cvar.value = value;
+            }
+        }
+    }
+
+    pub fn cvars(&self) -> impl Iterator<Item = (&str, &CVar)> {
+        self.cvars.iter().map(|(name, cvar)| (name.as_str(), cvar))
+    }
+
+    // Parses `text` as the type of the variable and runs its callback
+    pub fn set_cvar(
+        &mut self,
+        name: &str,
+        text: &str,
+        context: &mut ConsoleContext,
+    ) -> Result<CVarValue, String> {
+        if context.locked {
+            return Err(LOCKED.to_owned());
+        }
+
+        let cvar = self
+            .cvars
+            .get_mut(name)
+            .ok_or_else(|| format!("unknown variable \"{}\"", name))?;
+
+        cvar.value = cvar.value.parse(text)?;
+        if let Some(callback) = &mut cvar.callback {
+            callback(&cvar.value, context);
+        }
+
+        Ok(cvar.value.clone())
+    }
+
+    fn push_line(&mut self, line: Line) {
+        self.lines.push_back(line);
+        while self.lines.len() > MAX_LINES {
+            self.lines.pop_front();
+        }
+    }
+
+    // Runs a typed line, its output is added to the console
+    pub fn execute(&mut self, line: &str, context: &mut ConsoleContext) {
+        let line = line.trim();
+        if line.is_empty() {
+            return;
+        }
+
+        self.push_line(Line::Command(line.to_owned()));
+        if self.history.last().map(String::as_str) != Some(line) {
+            self.history.push(line.to_owned());
+        }
+
+        match self.run(line, context) {
+            Ok(output) if output.is_empty() => {}
+            Ok(output) => self.push_line(Line::Output(output)),
+            Err(e) => self.push_line(Line::Error(e)),
+        }
+    }
+
+    fn run(&mut self, line: &str, context: &mut ConsoleContext) -> Result<String, String> {
+        let words = line.split_whitespace().collect::<Vec<_>>();
+        let (name, args) = (words[0], &words[1..]);
+
+        match name {
+            "help" => Ok(self.help(args.first().copied())),
+            "clear" => {
+                self.lines.clear();
+                Ok(String::new())
+            }
+            "set" => match args {
+                [name, value @ ..] if !value.is_empty() => self
+                    .set_cvar(name, &value.join(" "), context)
+                    .map(|value| format!("{} = {}", name, value)),
+                _ => Err("usage: set <variable> <value>".to_owned()),
+            },
+            "get" => match args {
+                [name] => self
+                    .cvar(name)
+                    .map(|value| format!("{} = {}", name, value))
+                    .ok_or_else(|| format!("unknown variable \"{}\"", name)),
+                _ => Err("usage: get <variable>".to_owned()),
+            },
+            "reset" => match args {
+                [name] => {
+                    let default = self
+                        .cvars
+                        .get(*name)
+                        .map(|cvar| cvar.default.clone())
+                        .ok_or_else(|| format!("unknown variable \"{}\"", name))?;
+                    let text = match default {
+                        CVarValue::String(value) => value,
+                        value => value.to_string(),
+                    };
+                    self.set_cvar(name, &text, context)
+                        .map(|value| format!("{} = {}", name, value))
+                }
+                _ => Err("usage: reset <variable>".to_owned()),
+            },
+            // A variable name alone prints it, followed by a value sets it
+            name if self.cvars.contains_key(name) => match args {
+                [] => Ok(format!("{} = {}", name, self.cvars[name].value)),
+                value => self
+                    .set_cvar(name, &value.join(" "), context)
+                    .map(|value| format!("{} = {}", name, value)),
+            },
+            name if context.locked && self.commands.contains_key(name) => Err(LOCKED.to_owned()),
+            name => {
+                // Taken out while it runs, the handler may be replaced by then
+                let mut command = self
+                    .commands
+                    .remove(name)
+                    .ok_or_else(|| format!("unknown command \"{}\", try help", name))?;
+                let result = (command.handler)(args, context);
+                self.commands.entry(name.to_owned()).or_insert(command);
+
+                result
+            }
+        }
+    }
+
+    fn help(&self, name: Option<&str>) -> String {
+        match name {
+            Some(name) => match (self.commands.get(name), self.cvars.get(name)) {
+                (Some(command), _) => format!("{}: {}", name, command.help),
+                (None, Some(cvar)) => format!(
+                    "{} ({}, default {}): {}",
+                    name,
+                    cvar.value.type_name(),
+                    cvar.default,
+                    cvar.help
+                ),
+                (None, None) => format!("unknown command or variable \"{}\"", name),
+            },
+            None => {
+                let commands = ["clear", "get", "help", "reset", "set"]
+                    .into_iter()
+                    .chain(self.commands.keys().map(String::as_str))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                let cvars = self
+                    .cvars
+                    .iter()
+                    .map(|(name, cvar)| format!("{} = {}", name, cvar.value))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+
+                format!("commands: {}\nvariables: {}", commands, cvars)
+            }
+        }
+    }
+
+    // Candidates for the word being typed at the end of `line`
+    pub fn completions(&self, line: &str) -> Vec<String> {
+        let words = line.split_whitespace().collect::<Vec<_>>();
+        let typing_new = line.is_empty() || line.ends_with(char::is_whitespace);
+        let prefix = if typing_new {
+            ""
+        } else {
+            words.last().copied().unwrap_or("")
+        };
+        let index = if typing_new {
+            words.len()
+        } else {
+            words.len() - 1
+        };
+
+        let candidates = match index {
+            0 => ["clear", "get", "help", "reset", "set"]
+                .into_iter()
+                .map(str::to_owned)
+                .chain(self.commands.keys().cloned())
+                .chain(self.cvars.keys().cloned())
+                .collect::<Vec<_>>(),
+            1 => match words[0] {
+                "set" | "get" | "reset" => self.cvars.keys().cloned().collect(),
+                "help" => self
+                    .commands
+                    .keys()
+                    .chain(self.cvars.keys())
+                    .cloned()
+                    .collect(),
+                name => match (self.commands.get(name), self.cvars.get(name)) {
+                    (Some(command), _) => command.completions.clone(),
+                    (
+                        None,
+                        Some(CVar {
+                            value: CVarValue::Bool(_),
+                            ..
+                        }),
+                    ) => vec!["off".to_owned(), "on".to_owned()],
+                    _ => vec![],
+                },
+            },
+            _ => vec![],
+        };
+
+        let mut candidates = candidates
+            .into_iter()
+            .filter(|candidate| candidate.starts_with(prefix))
+            .collect::<Vec<_>>();
+        candidates.sort();
+        candidates.dedup();
+
+        candidates
+    }
+
+    // Completes the last word as far as all the candidates agree
+    fn complete(&mut self) {
+        let candidates = self.completions(&self.input);
+        let Some(first) = candidates.first() else {
+            return;
+        };
+
+        let common = candidates
+            .iter()
+            .skip(1)
+            .fold(first.clone(), |common, candidate| {
+                common
+                    .chars()
+                    .zip(candidate.chars())
+                    .take_while(|(a, b)| a == b)
+                    .map(|(a, _)| a)
+                    .collect()
+            });
+
+        let start = match self.input.ends_with(char::is_whitespace) {
+            true => self.input.len(),
+            false => self
+                .input
+                .rfind(char::is_whitespace)
+                .map_or(0, |index| index + 1),
+        };
+        self.input.truncate(start);
+        self.input.push_str(&common);
+        if candidates.len() == 1 {
+            self.input.push(' ');
+        }
+    }
+
+    fn read_log(&mut self) {
+        let captured = match CAPTURED.lock() {
+            Ok(mut captured) => std::mem::take(&mut *captured),
+            Err(_) => return,
+        };
+        captured
+            .into_iter()
+            .for_each(|line| self.push_line(Line::Log(line)));
+    }
+
+    pub fn show(&mut self, ctx: &egui::Context, context: &mut ConsoleContext) {
+        self.read_log();
+
+        if !self.open {
+            return;
+        }
+
+        egui::TopBottomPanel::top("console")
+            .resizable(true)
+            .default_height(300.0)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Level");
+                    [
+                        log::LevelFilter::Error,
+                        log::LevelFilter::Warn,
+                        log::LevelFilter::Info,
+                        log::LevelFilter::Debug,
+                        log::LevelFilter::Trace,
+                    ]
+                    .into_iter()
+                    .for_each(|level| {
+                        ui.selectable_value(&mut self.level, level, level.as_str());
+                    });
+                    if ui.button("Clear").clicked() {
+                        self.lines.clear();
+                    }
+                });
+
+                ui.separator();
+
+                let input_height = ui.spacing().interact_size.y * 2.5;
+                egui::ScrollArea::vertical()
+                    .auto_shrink([false, false])
+                    .max_height(ui.available_height() - input_height)
+                    .stick_to_bottom(true)
+                    .show(ui, |ui| {
+                        self.lines.iter().for_each(|line| match line {
+                            Line::Log(line) if line.level <= self.level => {
+                                let color = match line.level {
+                                    log::Level::Error => egui::Color32::LIGHT_RED,
+                                    log::Level::Warn => egui::Color32::YELLOW,
+                                    log::Level::Info => egui::Color32::LIGHT_GRAY,
+                                    log::Level::Debug | log::Level::Trace => egui::Color32::GRAY,
+                                };
+                                ui.label(
+                                    egui::RichText::new(format!(
+                                        "[{} {}] {}",
+                                        line.level, line.target, line.message
+                                    ))
+                                    .monospace()
+                                    .color(color),
+                                );
+                            }
+                            Line::Log(_) => {}
+                            Line::Command(command) => {
+                                ui.label(
+                                    egui::RichText::new(format!("> {}", command))
+                                        .monospace()
+                                        .strong(),
+                                );
+                            }
+                            Line::Output(output) => {
+                                ui.label(egui::RichText::new(output).monospace());
+                            }
+                            Line::Error(e) => {
+                                ui.label(
+                                    egui::RichText::new(e)
+                                        .monospace()
+                                        .color(egui::Color32::LIGHT_RED),
+                                );
+                            }
+                        });
+                    });
+
+                self.prompt(ui, context);
+            });
+    }
+
+    fn prompt(&mut self, ui: &mut egui::Ui, context: &mut ConsoleContext) {
+        let id = egui::Id::new("console_input");
+
+        // Tab completes instead of moving the focus
+        let response = ui.add(
+            egui::TextEdit::singleline(&mut self.input)
+                .id(id)
+                .font(egui::TextStyle::Monospace)
+                .desired_width(f32::INFINITY)
+                .lock_focus(true),
+        );
+        if std::mem::take(&mut self.focus_input) {
+            response.request_focus();
+        }
+
+        // The key that opens the console also closes it
+        if self.input.contains('`') {
+            self.input.retain(|c| c != '`');
+            self.set_open(false);
+        }
+
+        let (enter, tab, up, down, escape) = ui.input(|i| {
+            (
+                i.key_pressed(egui::Key::Enter),
+                i.key_pressed(egui::Key::Tab),
+                i.key_pressed(egui::Key::ArrowUp),
+                i.key_pressed(egui::Key::ArrowDown),
+                i.key_pressed(egui::Key::Escape),
+            )
+        });
+        // Enter and Escape take the focus away from the text
+        let focused = response.has_focus() || response.lost_focus();
+
+        let mut move_cursor = false;
+        if focused && enter {
+            let line = std::mem::take(&mut self.input);
+            self.execute(&line, context);
+            self.history_index = None;
+            response.request_focus();
+        }
+        if focused && escape {
+            self.set_open(false);
+        }
+        if focused && tab {
+            self.complete();
+            move_cursor = true;
+        }
+        if focused && (up || down) && !self.history.is_empty() {
+            let last = self.history.len() - 1;
+            self.history_index = match (self.history_index, up) {
+                (None, true) => Some(last),
+                (None, false) => None,
+                (Some(index), true) => Some(index.saturating_sub(1)),
+                (Some(index), false) if index < last => Some(index + 1),
+                (Some(_), false) => None,
+            };
+            self.input = self
+                .history_index
+                .map(|index| self.history[index].clone())
+                .unwrap_or_default();
+            move_cursor = true;
+        }
+
+        if move_cursor {
+            if let Some(mut state) = egui::TextEdit::load_state(ui.ctx(), id) {
+                state.set_ccursor_range(Some(egui::text_edit::CCursorRange::one(
+                    egui::text::CCursor::new(self.input.chars().count()),
+                )));
+                egui::TextEdit::store_state(ui.ctx(), id, state);
+            }
+        }
+
+        let candidates = self.completions(&self.input);
+        if !self.input.is_empty() && !candidates.is_empty() {
+            ui.label(
+                egui::RichText::new(candidates.join("  "))
+                    .monospace()
+                    .color(egui::Color32::GRAY),
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn console() -> (Console, std::rc::Rc<std::cell::Cell<f32>>) {
+        let changed = std::rc::Rc::new(std::cell::Cell::new(0.0));
+        let mut console = Console::new();
+
+        console.add_cvar("wireframe", false, "");
+        console.add_cvar("camera.fov", 45f32, "");
+        console.add_cvar("camera.speed", 3f32, "");
+        let seen = changed.clone();
+        console.on_change("camera.fov", move |value, _| seen.set(value.as_float()));
+        console.add_command("spawn", "", &["cube", "sphere", "spring"], |args, _| {
+            Ok(args.join(","))
+        });
+
+        (console, changed)
+    }
+
+    fn run(console: &mut Console, line: &str, locked: bool) -> Result<String, String> {
+        let mut world = hecs::World::new();
+        let camera = world.spawn(());
+        let mut editor = crate::editor::Editor::default();
+
+        console.run(
+            line,
+            &mut ConsoleContext {
+                world: &mut world,
+                camera,
+                editor: &mut editor,
+                locked,
+            },
+        )
+    }
+
+    #[test]
+    fn values_parse_as_the_type_of_the_variable() {
+        assert_eq!(
+            CVarValue::Bool(false).parse("ON"),
+            Ok(CVarValue::Bool(true))
+        );
+        assert_eq!(CVarValue::Bool(true).parse("0"), Ok(CVarValue::Bool(false)));
+        assert_eq!(CVarValue::Int(0).parse("-12"), Ok(CVarValue::Int(-12)));
+        assert_eq!(
+            CVarValue::Float(0.0).parse("2.5"),
+            Ok(CVarValue::Float(2.5))
+        );
+        assert_eq!(
+            CVarValue::String(String::new()).parse("a b"),
+            Ok(CVarValue::String("a b".to_owned()))
+        );
+
+        assert!(CVarValue::Bool(false).parse("maybe").is_err());
+        assert!(CVarValue::Int(0).parse("1.5").is_err());
+        assert!(CVarValue::Float(0.0).parse("fast").is_err());
+    }
+
+    #[test]
+    fn variables_are_set_read_and_reset() {
+        let (mut console, changed) = console();
+
+        assert_eq!(
+            run(&mut console, "camera.fov 60", false).unwrap(),
+            "camera.fov = 60"
+        );
+        assert_eq!(changed.get(), 60.0);
+        assert_eq!(
+            run(&mut console, "get camera.fov", false).unwrap(),
+            "camera.fov = 60"
+        );
+        assert_eq!(
+            run(&mut console, "set wireframe on", false).unwrap(),
+            "wireframe = on"
+        );
+
+        run(&mut console, "reset camera.fov", false).unwrap();
+        assert_eq!(console.cvar("camera.fov"), Some(&CVarValue::Float(45.0)));
+        assert_eq!(changed.get(), 45.0);
+
+        assert!(run(&mut console, "camera.fov wide", false).is_err());
+        assert!(run(&mut console, "get missing", false).is_err());
+        assert!(run(&mut console, "set wireframe", false).is_err());
+    }
+
+    #[test]
+    fn commands_get_the_words_after_their_name() {
+        let (mut console, _) = console();
+
+        assert_eq!(
+            run(&mut console, "spawn  cube   2", false).unwrap(),
+            "cube,2"
+        );
+        assert!(run(&mut console, "teleport", false).is_err());
+    }
+
+    #[test]
+    fn locked_consoles_only_read() {
+        let (mut console, changed) = console();
+
+        assert!(run(&mut console, "camera.fov 60", true).is_err());
+        assert!(run(&mut console, "spawn cube", true).is_err());
+        assert_eq!(changed.get(), 0.0);
+        assert_eq!(
+            run(&mut console, "camera.fov", true).unwrap(),
+            "camera.fov = 45"
+        );
+    }
+
+    #[test]
+    fn synced_variables_skip_the_callback() {
+        let (mut console, changed) = console();
+
+        console.sync_cvar("camera.fov", 70f32);
+        console.sync_cvar("camera.fov", true);
+        assert_eq!(console.cvar("camera.fov"), Some(&CVarValue::Float(70.0)));
+        assert_eq!(changed.get(), 0.0);
+    }
+
+    #[test]
+    fn completions_depend_on_the_word() {
+        let (console, _) = console();
+
+        assert_eq!(console.completions("sp"), ["spawn"]);
+        assert_eq!(
+            console.completions("set camera."),
+            ["camera.fov", "camera.speed"]
+        );
+        assert_eq!(console.completions("wireframe "), ["off", "on"]);
+        assert_eq!(console.completions("spawn sp"), ["sphere", "spring"]);
+        assert!(console.completions("spawn cube ").is_empty());
+    }
+
+    #[test]
+    fn completing_extends_the_common_prefix() {
+        let (mut console, _) = console();
+
+        console.input = "set camera.f".to_owned();
+        console.complete();
+        assert_eq!(console.input, "set camera.fov ");
+
+        console.input = "spawn s".to_owned();
+        console.complete();
+        assert_eq!(console.input, "spawn sp");
+    }
+}
@@ -0,0 +1,254 @@
+use crate::vertex_type::LineVertex;
+
+pub struct DebugDraw {
+    depth_test: bool,
+    vertices: Vec<LineVertex>,
+    overlay_vertices: Vec<LineVertex>,
+    buffer: wgpu::Buffer,
+    capacity: usize,
+}
+
+impl DebugDraw {
+    const INITIAL_CAPACITY: usize = 1024;
+    const CIRCLE_SEGMENTS: usize = 32;
+
+    pub fn new(device: &wgpu::Device) -> Self {
+        Self {
+            depth_test: true,
+            vertices: vec![],
+            overlay_vertices: vec![],
+            buffer: Self::create_buffer(device, Self::INITIAL_CAPACITY),
+            capacity: Self::INITIAL_CAPACITY,
+        }
+    }
+
+    fn create_buffer(device: &wgpu::Device, capacity: usize) -> wgpu::Buffer {
+        device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Debug Draw Buffer"),
+            size: (capacity * std::mem::size_of::<LineVertex>()) as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        })
+    }
+
+    // Primitives added after disabling the depth test are drawn on top of the scene
+    pub fn set_depth_test(&mut self, enabled: bool) {
+        self.depth_test = enabled;
+    }
+
+    pub fn line(&mut self, a: &nalgebra_glm::Vec3, b: &nalgebra_glm::Vec3, color: [f32; 3]) {
+        let vertices = if self.depth_test {
+            &mut self.vertices
+        } else {
+            &mut self.overlay_vertices
+        };
+
+        vertices.push(LineVertex {
+            position: (*a).into(),
+            color,
+        });
+        vertices.push(LineVertex {
+            position: (*b).into(),
+            color,
+        });
+    }
+
+    pub fn aabb(&mut self, aabb: &crate::math::Aabb, matrix: &nalgebra_glm::Mat4, color: [f32; 3]) {
+        let corners = [
+            nalgebra_glm::vec3(aabb.min.x, aabb.min.y, aabb.min.z),
+            nalgebra_glm::vec3(aabb.max.x, aabb.min.y, aabb.min.z),
+            nalgebra_glm::vec3(aabb.max.x, aabb.min.y, aabb.max.z),
+            nalgebra_glm::vec3(aabb.min.x, aabb.min.y, aabb.max.z),
+            nalgebra_glm::vec3(aabb.min.x, aabb.max.y, aabb.min.z),
+            nalgebra_glm::vec3(aabb.max.x, aabb.max.y, aabb.min.z),
+            nalgebra_glm::vec3(aabb.max.x, aabb.max.y, aabb.max.z),
+            nalgebra_glm::vec3(aabb.min.x, aabb.max.y, aabb.max.z),
+        ]
+        .map(|corner| (matrix * corner.push(1.0)).xyz());
+
+        self.box_edges(&corners, color);
+    }
+
+    pub fn sphere(&mut self, center: &nalgebra_glm::Vec3, radius: f32, color: [f32; 3]) {
+        self.circle(center, &nalgebra_glm::Vec3::x(), radius, color);
+        self.circle(center, &nalgebra_glm::Vec3::y(), radius, color);
+        self.circle(center, &nalgebra_glm::Vec3::z(), radius, color);
+    }
+
+    pub fn circle(
+        &mut self,
+        center: &nalgebra_glm::Vec3,
+        normal: &nalgebra_glm::Vec3,
+        radius: f32,
+        color: [f32; 3],
+    ) {
+        let normal = nalgebra_glm::normalize(normal);
+        let helper = if normal.x.abs() < 0.9 {
+            nalgebra_glm::Vec3::x()
+        } else {
+            nalgebra_glm::Vec3::y()
+        };
+        let u = nalgebra_glm::normalize(&nalgebra_glm::cross(&normal, &helper));
+        let v = nalgebra_glm::cross(&normal, &u);
+
+        let point = |i: usize| {
+            let angle = i as f32 / Self::CIRCLE_SEGMENTS as f32 * std::f32::consts::TAU;
+            center + (u * angle.cos() + v * angle.sin()) * radius
+        };
+
+        (0..Self::CIRCLE_SEGMENTS).for_each(|i| {
+            self.line(&point(i), &point(i + 1), color);
+        });
+    }
+
+    pub fn axes(&mut self, matrix: &nalgebra_glm::Mat4, size: f32) {
+        let origin = (matrix * nalgebra_glm::vec4(0.0, 0.0, 0.0, 1.0)).xyz();
+
+        [
+            (nalgebra_glm::Vec3::x(), [1.0, 0.0, 0.0]),
+            (nalgebra_glm::Vec3::y(), [0.0, 1.0, 0.0]),
+            (nalgebra_glm::Vec3::z(), [0.0, 0.0, 1.0]),
+        ]
+        .iter()
+        .for_each(|(axis, color)| {
+            let end = (matrix * (axis * size).push(1.0)).xyz();
+            self.line(&origin, &end, *color);
+        });
+    }
+
+    pub fn frustum(
+        &mut self,
+        camera: &crate::component::Camera,
+        transform: &crate::component::GlobalTransform,
+        color: [f32; 3],
+    ) {
+        let inverse = nalgebra_glm::inverse(&camera.view_projection(transform));
+
+        // The depth is remapped to [0, 1] like in the shaders
+        let corners = [
+            nalgebra_glm::vec4(-1.0, -1.0, 0.0, 1.0),
+            nalgebra_glm::vec4(1.0, -1.0, 0.0, 1.0),
+            nalgebra_glm::vec4(1.0, -1.0, 1.0, 1.0),
+            nalgebra_glm::vec4(-1.0, -1.0, 1.0, 1.0),
+            nalgebra_glm::vec4(-1.0, 1.0, 0.0, 1.0),
+            nalgebra_glm::vec4(1.0, 1.0, 0.0, 1.0),
+            nalgebra_glm::vec4(1.0, 1.0, 1.0, 1.0),
+            nalgebra_glm::vec4(-1.0, 1.0, 1.0, 1.0),
+        ]
+        .map(|corner| {
+            let corner = inverse * corner;
+            corner.xyz() / corner.w
+        });
+
+        self.box_edges(&corners, color);
+    }
+
+    pub fn arrow(&mut self, from: &nalgebra_glm::Vec3, to: &nalgebra_glm::Vec3, color: [f32; 3]) {
+        self.line(from, to, color);
+
+        let direction = to - from;
+        let length = nalgebra_glm::length(&direction);
+        if length <= f32::EPSILON {
+            return;
+        }
+
+        let direction = direction / length;
+        let helper = if direction.y.abs() < 0.9 {
+            nalgebra_glm::Vec3::y()
+        } else {
+            nalgebra_glm::Vec3::x()
+        };
+        let side = nalgebra_glm::normalize(&nalgebra_glm::cross(&direction, &helper));
+        let other_side = nalgebra_glm::cross(&direction, &side);
+
+        let head_length = length * 0.2;
+        let base = to - direction * head_length;
+
+        [side, -side, other_side, -other_side]
+            .iter()
+            .for_each(|offset| {
+                self.line(to, &(base + offset * head_length * 0.5), color);
+            });
+    }
+
+    // Corners 0..4 are the bottom face and 4..8 the top face, both in winding order
+    fn box_edges(&mut self, corners: &[nalgebra_glm::Vec3; 8], color: [f32; 3]) {
+        (0..4).for_each(|i| {
+            let next = (i + 1) % 4;
+            self.line(&corners[i], &corners[next], color);
+            self.line(&corners[i + 4], &corners[next + 4], color);
+            self.line(&corners[i], &corners[i + 4], color);
+        });
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.vertices.is_empty() && self.overlay_vertices.is_empty()
+    }
+
+    // Uploads both batches into a single buffer, the depth tested lines go first
+    pub fn upload(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) {
+        let count = self.vertices.len() + self.overlay_vertices.len();
+
+        if count > self.capacity {
+            self.capacity = count.next_power_of_two();
+            self.buffer = Self::create_buffer(device, self.capacity);
+        }
+
+        queue.write_buffer(&self.buffer, 0, bytemuck::cast_slice(&self.vertices));
+        queue.write_buffer(
+            &self.buffer,
+            (self.vertices.len() * std::mem::size_of::<LineVertex>()) as wgpu::BufferAddress,
+            bytemuck::cast_slice(&self.overlay_vertices),
+        );
+    }
+
+    pub fn draw<'a>(
+        &'a self,
+        render_pass: &mut wgpu::RenderPass<'a>,
+        pipeline: &'a wgpu::RenderPipeline,
+        overlay_pipeline: &'a wgpu::RenderPipeline,
+        bind_groups: Vec<(u32, &'a wgpu::BindGroup)>,
+        stats: &mut crate::profiler::RenderStats,
+    ) {
+        bind_groups.iter().for_each(|(id, bind_group)| {
+            render_pass.set_bind_group(*id, bind_group, &[]);
+        });
+
+        render_pass.set_vertex_buffer(0, self.buffer.slice(..));
+
+        let depth_count = self.vertices.len() as u32;
+        let overlay_count = self.overlay_vertices.len() as u32;
+
+        if depth_count > 0 {
+            render_pass.set_pipeline(pipeline);
+            render_pass.draw(0..depth_count, 0..1);
+            stats.add_draw(
+                wgpu::PrimitiveTopology::LineList,
+                depth_count as u64,
+                depth_count as u64,
+            );
+        }
+
+        if overlay_count > 0 {
+            render_pass.set_pipeline(overlay_pipeline);
+            render_pass.draw(depth_count..depth_count + overlay_count, 0..1);
+            stats.add_draw(
+                wgpu::PrimitiveTopology::LineList,
+                overlay_count as u64,
+                overlay_count as u64,
+            );
+        }
+    }
+
+    pub fn memory(&self) -> crate::profiler::GpuMemory {
+        let mut memory = crate::profiler::GpuMemory::default();
+        memory.add_buffer(&self.buffer);
+        memory
+    }
+
+    pub fn clear(&mut self) {
+        self.vertices.clear();
+        self.overlay_vertices.clear();
+        self.depth_test = true;
+    }
+}
@@ -0,0 +1,806 @@
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Primitive {
+    Cube,
+    Plane,
+    MarchingSquares,
+}
+
+impl Primitive {
+    pub const ALL: [Primitive; 3] = [
+        Primitive::Cube,
+        Primitive::Plane,
+        Primitive::MarchingSquares,
+    ];
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EditorAction {
+    Spawn(Primitive),
+    Delete(hecs::Entity),
+    SaveScene(String),
+    LoadScene(String),
+    CreatePrefab(hecs::Entity, String),
+    InstantiatePrefab(String),
+    ReloadPrefab(String),
+    RevertOverride(hecs::Entity, crate::prefab::OverrideField),
+    ToggleRecording(String),
+    ToggleReplay(String),
+}
+
+impl EditorAction {
+    // Recordings only keep the input, these would make their replays diverge
+    pub fn edits_world(&self) -> bool {
+        !matches!(
+            self,
+            EditorAction::SaveScene(_)
+                | EditorAction::ToggleRecording(_)
+                | EditorAction::ToggleReplay(_)
+        )
+    }
+}
+
+#[derive(Default)]
+pub struct Editor {
+    pub enabled: bool,
+    pub gizmo: crate::gizmo::Gizmo,
+    pub scene_path: String,
+    pub prefab_path: String,
+    pub bindings_path: String,
+    pub recording_path: String,
+    // Set by the engine before the editor is shown
+    pub recording: bool,
+    pub replaying: bool,
+    show_bindings: bool,
+    actions: Vec<EditorAction>,
+}
+
+impl Editor {
+    pub fn new() -> Self {
+        Self {
+            enabled: false,
+            gizmo: crate::gizmo::Gizmo::default(),
+            scene_path: "scene.ron".to_owned(),
+            prefab_path: "prefab.ron".to_owned(),
+            bindings_path: "bindings.ron".to_owned(),
+            recording_path: "recording.ron".to_owned(),
+            recording: false,
+            replaying: false,
+            show_bindings: false,
+            actions: vec![],
+        }
+    }
+
+    pub fn toggle(&mut self) {
+        self.enabled = !self.enabled;
+    }
+
+    // Nothing is edited while a recording or a replay runs
+    pub fn locked(&self) -> bool {
+        self.recording || self.replaying
+    }
+
+    // Queued like the buttons' actions, the engine runs them at its next update
+    pub fn push_action(&mut self, action: EditorAction) {
+        self.actions.push(action);
+    }
+
+    pub fn drain_actions(&mut self) -> std::vec::Drain<'_, EditorAction> {
+        self.actions.drain(..)
+    }
+
+    pub fn show(
+        &mut self,
+        ctx: &egui::Context,
+        world: &mut hecs::World,
+        picking: &mut crate::picking::Picking,
+        pipelines: &HashMap<String, crate::pipeline::Pipeline>,
+        materials: &HashMap<String, crate::material::Material>,
+        prefabs: &crate::prefab::PrefabLibrary,
+    ) {
+        if !self.enabled {
+            return;
+        }
+        let locked = self.locked();
+
+        egui::SidePanel::left("editor_hierarchy")
+            .resizable(true)
+            .show(ctx, |ui| {
+                ui.heading("Hierarchy");
+
+                ui.horizontal(|ui| {
+                    ui.text_edit_singleline(&mut self.scene_path);
+                    if ui.button("Save").clicked() {
+                        self.actions
+                            .push(EditorAction::SaveScene(self.scene_path.clone()));
+                    }
+                    if ui.add_enabled(!locked, egui::Button::new("Load")).clicked() {
+                        self.actions
+                            .push(EditorAction::LoadScene(self.scene_path.clone()));
+                    }
+                });
+
+                ui.horizontal(|ui| {
+                    ui.text_edit_singleline(&mut self.prefab_path);
+                    if ui
+                        .add_enabled(!locked, egui::Button::new("Instantiate"))
+                        .clicked()
+                    {
+                        self.actions
+                            .push(EditorAction::InstantiatePrefab(self.prefab_path.clone()));
+                    }
+                    if ui
+                        .add_enabled(!locked, egui::Button::new("Reload"))
+                        .clicked()
+                    {
+                        self.actions
+                            .push(EditorAction::ReloadPrefab(self.prefab_path.clone()));
+                    }
+                });
+
+                ui.horizontal(|ui| {
+                    ui.text_edit_singleline(&mut self.recording_path);
+                    let record = match self.recording {
+                        true => "Stop",
+                        false => "Record",
+                    };
+                    if ui
+                        .add_enabled(!self.replaying, egui::Button::new(record))
+                        .clicked()
+                    {
+                        self.actions
+                            .push(EditorAction::ToggleRecording(self.recording_path.clone()));
+                    }
+                    let replay = match self.replaying {
+                        true => "Stop",
+                        false => "Replay",
+                    };
+                    if ui
+                        .add_enabled(!self.recording, egui::Button::new(replay))
+                        .clicked()
+                    {
+                        self.actions
+                            .push(EditorAction::ToggleReplay(self.recording_path.clone()));
+                    }
+                });
+
+                ui.horizontal_wrapped(|ui| {
+                    Primitive::ALL.iter().for_each(|primitive| {
+                        if ui
+                            .add_enabled(!locked, egui::Button::new(format!("+ {:?}", primitive)))
+                            .clicked()
+                        {
+                            self.actions.push(EditorAction::Spawn(*primitive));
+                        }
+                    });
+                });
+
+                if ui.button("Input Bindings").clicked() {
+                    self.show_bindings = !self.show_bindings;
+                }
+
+                ui.separator();
+
+                self.gizmo_settings(ui);
+
+                ui.separator();
+
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    Self::hierarchy(ui, world, picking);
+                });
+            });
+
+        egui::SidePanel::right("editor_inspector")
+            .resizable(true)
+            .show(ctx, |ui| {
+                ui.heading("Inspector");
+                ui.separator();
+
+                match picking.selected() {
+                    Some(entity) if world.contains(entity) => {
+                        ui.add_enabled_ui(!locked, |ui| {
+                            self.inspector(ui, world, entity, pipelines, materials, prefabs);
+                        });
+                    }
+                    _ => {
+                        ui.label("No entity selected");
+                    }
+                }
+            });
+    }
+
+    pub fn show_bindings(
+        &mut self,
+        ctx: &egui::Context,
+        input: &mut crate::component::input::Input,
+    ) {
+        if !self.enabled || self.locked() {
+            input.cancel_rebind();
+        }
+        if !self.enabled {
+            return;
+        }
+        let locked = self.locked();
+
+        let mut open = self.show_bindings;
+        egui::Window::new("Input Bindings")
+            .open(&mut open)
+            .show(ctx, |ui| {
+                ui.add_enabled_ui(!locked, |ui| self.bindings(ui, input));
+            });
+        self.show_bindings = open;
+
+        // Closing the window gives up on a rebind in progress
+        if !self.show_bindings {
+            input.cancel_rebind();
+        }
+    }
+
+    // The custom types start from what the camera showed before the switch
+    fn projection(ui: &mut egui::Ui, camera: &mut crate::component::Camera) {
+        use crate::component::CameraType;
+
+        let size = camera.viewport_size();
+        let types = [
+            CameraType::Perspective,
+            CameraType::PerspectiveCustom {
+                aspect: size.x / size.y,
+                fovy: camera.fov.to_radians(),
+                near: camera.near,
+                far: camera.far,
+            },
+            CameraType::Orthographic,
+            CameraType::OrthographicCustom {
+                left: 0.0,
+                right: size.x,
+                bottom: 0.0,
+                top: size.y,
+                znear: camera.near,
+                zfar: camera.far,
+            },
+        ];
+
+        egui::ComboBox::from_label("Projection")
+            .selected_text(camera.camera_type.name())
+            .show_ui(ui, |ui| {
+                types.into_iter().for_each(|camera_type| {
+                    let selected = camera.camera_type.name() == camera_type.name();
+                    if ui.selectable_label(selected, camera_type.name()).clicked() && !selected {
+                        camera.camera_type = camera_type;
+                    }
+                });
+            });
+
+        let drag = |ui: &mut egui::Ui, label: &str, value: &mut f32, speed: f64| {
+            ui.horizontal(|ui| {
+                ui.label(label);
+                ui.add(egui::DragValue::new(value).speed(speed));
+            });
+        };
+
+        match &mut camera.camera_type {
+            CameraType::Perspective => {
+                ui.add(egui::Slider::new(&mut camera.fov, 1.0..=179.0).text("Field of view"));
+            }
+            CameraType::PerspectiveCustom {
+                aspect,
+                fovy,
+                near,
+                far,
+            } => {
+                let mut degrees = fovy.to_degrees();
+                if ui
+                    .add(egui::Slider::new(&mut degrees, 1.0..=179.0).text("Field of view"))
+                    .changed()
+                {
+                    *fovy = degrees.to_radians();
+                }
+                drag(ui, "Aspect", aspect, 0.01);
+                drag(ui, "Near", near, 0.01);
+                drag(ui, "Far", far, 1.0);
+            }
+            CameraType::Orthographic => {}
+            CameraType::OrthographicCustom {
+                left,
+                right,
+                bottom,
+                top,
+                znear,
+                zfar,
+            } => {
+                drag(ui, "Left", left, 0.1);
+                drag(ui, "Right", right, 0.1);
+                drag(ui, "Bottom", bottom, 0.1);
+                drag(ui, "Top", top, 0.1);
+                drag(ui, "Near", znear, 0.01);
+                drag(ui, "Far", zfar, 1.0);
+            }
+        }
+
+        if matches!(
+            camera.camera_type,
+            CameraType::Perspective | CameraType::Orthographic
+        ) {
+            drag(ui, "Near", &mut camera.near, 0.01);
+            drag(ui, "Far", &mut camera.far, 1.0);
+        }
+
+        // A plane at 0 or behind the near one breaks the depth range
+        camera.near = camera.near.max(0.001);
+        camera.far = camera.far.max(camera.near + 0.001);
+        camera.projection = camera.build_projection();
+    }
+
+    fn gizmo_settings(&mut self, ui: &mut egui::Ui) {
+        use crate::gizmo::{GizmoMode, GizmoSpace};
+
+        ui.horizontal(|ui| {
+            ui.selectable_value(&mut self.gizmo.mode, GizmoMode::Translate, "Translate");
+            ui.selectable_value(&mut self.gizmo.mode, GizmoMode::Rotate, "Rotate");
+            ui.selectable_value(&mut self.gizmo.mode, GizmoMode::Scale, "Scale");
+        });
+
+        ui.horizontal(|ui| {
+            ui.selectable_value(&mut self.gizmo.space, GizmoSpace::World, "World");
+            ui.selectable_value(&mut self.gizmo.space, GizmoSpace::Local, "Local");
+        });
+
+        ui.checkbox(&mut self.gizmo.snap, "Snap");
+        ui.add_enabled_ui(self.gizmo.snap, |ui| {
+            ui.horizontal(|ui| {
+                ui.add(
+                    egui::DragValue::new(&mut self.gizmo.translate_snap)
+                        .speed(0.05)
+                        .clamp_range(0.0..=100.0),
+                );
+                ui.add(
+                    egui::DragValue::new(&mut self.gizmo.rotate_snap)
+                        .speed(0.5)
+                        .clamp_range(0.0..=180.0)
+                        .suffix("°"),
+                );
+                ui.add(
+                    egui::DragValue::new(&mut self.gizmo.scale_snap)
+                        .speed(0.01)
+                        .clamp_range(0.0..=10.0),
+                );
+            });
+        });
+    }
+
+    fn bindings(&mut self, ui: &mut egui::Ui, input: &mut crate::component::input::Input) {
+        use crate::component::input::{AxisBinding, Bindings, Button, Rebind};
+
+        ui.horizontal(|ui| {
+            ui.text_edit_singleline(&mut self.bindings_path);
+            if ui.button("Save").clicked() {
+                if let Err(e) = input.bindings().save(&self.bindings_path) {
+                    log::error!("Can't save bindings {}: {}", self.bindings_path, e);
+                }
+            }
+            if ui.button("Load").clicked() {
+                match Bindings::load(&self.bindings_path) {
+                    Ok(bindings) => input.bindings_mut().merge(bindings),
+                    Err(e) => log::error!("Can't load bindings {}: {}", self.bindings_path, e),
+                }
+            }
+        });
+
+        let gamepad = &mut input.bindings_mut().gamepad;
+        ui.horizontal(|ui| {
+            ui.add(egui::Slider::new(&mut gamepad.dead_zone, 0.0..=0.9).text("Dead Zone"));
+            ui.add(egui::Slider::new(&mut gamepad.exponent, 0.5..=4.0).text("Curve"));
+        });
+
+        let conflicts = input.bindings().conflicts();
+        let rebinding = input.rebinding().cloned();
+        let actions = input.bindings().actions.clone();
+        let axes = input.bindings().axes.clone();
+
+        // Clicking a binding waits for the next button, conflicting ones are red
+        let mut binding_button = |ui: &mut egui::Ui, button: Option<Button>, rebind: Rebind| {
+            let text = match (&rebinding, button) {
+                (Some(waiting), _) if *waiting == rebind => egui::RichText::new("..."),
+                (_, Some(button)) if conflicts.iter().any(|(other, _)| *other == button) => {
+                    egui::RichText::new(button.to_string()).color(egui::Color32::RED)
+                }
+                (_, Some(button)) => egui::RichText::new(button.to_string()),
+                (_, None) => egui::RichText::new("+"),
+            };
+
+            if ui.button(text).clicked() {
+                input.start_rebind(rebind);
+            }
+        };
+
+        ui.heading("Actions");
+        let mut unbind = None;
+        egui::Grid::new("action_bindings").show(ui, |ui| {
+            actions.iter().for_each(|(action, buttons)| {
+                ui.label(action);
+                ui.horizontal(|ui| {
+                    buttons.iter().enumerate().for_each(|(index, button)| {
+                        binding_button(
+                            ui,
+                            Some(*button),
+                            Rebind::Action {
+                                action: action.clone(),
+                                index,
+                            },
+                        );
+                        if ui.small_button("x").clicked() {
+                            unbind = Some((action.clone(), *button));
+                        }
+                    });
+                    binding_button(
+                        ui,
+                        None,
+                        Rebind::Action {
+                            action: action.clone(),
+                            index: buttons.len(),
+                        },
+                    );
+                });
+                ui.end_row();
+            });
+        });
+
+        ui.heading("Axes");
+        let mut remove = None;
+        egui::Grid::new("axis_bindings").show(ui, |ui| {
+            axes.iter().for_each(|(axis, bindings)| {
+                ui.label(axis);
+                ui.horizontal(|ui| {
+                    bindings
+                        .iter()
+                        .enumerate()
+                        .for_each(|(index, (binding, _))| {
+                            match binding {
+                                AxisBinding::Buttons { negative, positive } => {
+                                    binding_button(
+                                        ui,
+                                        Some(*negative),
+                                        Rebind::AxisNegative {
+                                            axis: axis.clone(),
+                                            index,
+                                        },
+                                    );
+                                    binding_button(
+                                        ui,
+                                        Some(*positive),
+                                        Rebind::AxisPositive {
+                                            axis: axis.clone(),
+                                            index,
+                                        },
+                                    );
+                                }
+                                binding => {
+                                    ui.label(format!("{:?}", binding));
+                                }
+                            }
+                            if ui.small_button("x").clicked() {
+                                remove = Some((axis.clone(), index));
+                            }
+                        });
+                });
+                ui.end_row();
+            });
+        });
+
+        if let Some((action, button)) = unbind {
+            input.unbind_action(&action, button);
+        }
+        if let Some((axis, index)) = remove {
+            if let Some(bindings) = input.bindings_mut().axes.get_mut(&axis) {
+                bindings.remove(index);
+            }
+        }
+
+        if rebinding.is_some() {
+            ui.label("Press a key or a mouse button, Escape cancels");
+        }
+
+        conflicts.iter().for_each(|(button, users)| {
+            ui.colored_label(
+                egui::Color32::RED,
+                format!("{} is bound to {}", button, users.join(", ")),
+            );
+        });
+    }
+
+    fn label(world: &hecs::World, entity: hecs::Entity) -> String {
+        match world.get::<&crate::component::Name>(entity) {
+            Ok(name) => name.0.clone(),
+            Err(_) => format!("Entity {}", entity.id()),
+        }
+    }
+
+    fn hierarchy(ui: &mut egui::Ui, world: &hecs::World, picking: &mut crate::picking::Picking) {
+        let mut roots = world
+            .query::<Option<&crate::component::Parent>>()
+            .iter()
+            .filter(|(_, parent)| parent.is_none_or(|parent| !world.contains(parent.0)))
+            .map(|(entity, _)| entity)
+            .collect::<Vec<_>>();
+        roots.sort_by_key(|entity| entity.id());
+
+        roots.iter().for_each(|entity| {
+            Self::hierarchy_node(ui, world, picking, *entity);
+        });
+    }
+
+    fn hierarchy_node(
+        ui: &mut egui::Ui,
+        world: &hecs::World,
+        picking: &mut crate::picking::Picking,
+        entity: hecs::Entity,
+    ) {
+        let selected = picking.selected() == Some(entity);
+        let label = Self::label(world, entity);
+        let children = crate::hierarchy::children(world, entity);
+
+        if children.is_empty() {
+            if ui.selectable_label(selected, label).clicked() {
+                picking.select(Some(entity));
+            }
+            return;
+        }
+
+        egui::collapsing_header::CollapsingState::load_with_default_open(
+            ui.ctx(),
+            ui.make_persistent_id(entity),
+            true,
+        )
+        .show_header(ui, |ui| {
+            if ui.selectable_label(selected, label).clicked() {
+                picking.select(Some(entity));
+            }
+        })
+        .body(|ui| {
+            children.iter().for_each(|child| {
+                Self::hierarchy_node(ui, world, picking, *child);
+            });
+        });
+    }
+
+    fn inspector(
+        &mut self,
+        ui: &mut egui::Ui,
+        world: &mut hecs::World,
+        entity: hecs::Entity,
+        pipelines: &HashMap<String, crate::pipeline::Pipeline>,
+        materials: &HashMap<String, crate::material::Material>,
+        prefabs: &crate::prefab::PrefabLibrary,
+    ) {
+        let label = Self::label(world, entity);
+
+        if let Ok(name) = world.query_one_mut::<&mut crate::component::Name>(entity) {
+            ui.text_edit_singleline(&mut name.0);
+        } else {
+            ui.label(label);
+        }
+
+        if let Ok(transform) = world.query_one_mut::<&mut crate::component::Transform>(entity) {
+            ui.collapsing("Transform", |ui| {
+                let mut position = transform.get_position();
+                ui.horizontal(|ui| {
+                    ui.label("Position");
+                    let changed = Self::drag_vec3(ui, &mut position, 0.05);
+                    if changed {
+                        transform.set_position(&position);
+                    }
+                });
+
+                let mut rotation = transform.get_rotation();
+                ui.horizontal(|ui| {
+                    ui.label("Rotation");
+                    if Self::drag_vec3(ui, &mut rotation, 0.5) {
+                        transform.set_rotation_euler(&rotation);
+                    }
+                });
+
+                let mut scale = transform.get_scale();
+                ui.horizontal(|ui| {
+                    ui.label("Scale");
+                    if Self::drag_vec3(ui, &mut scale, 0.05) {
+                        transform.set_scale(&nalgebra_glm::max(&scale, 0.01));
+                    }
+                });
+            });
+        }
+
+        if let Ok(render) = world.query_one_mut::<&mut crate::component::Render>(entity) {
+            ui.collapsing("Render", |ui| {
+                let mut pipeline_names = pipelines
+                    .iter()
+                    .filter(|(_, pipeline)| pipeline.accepts(render))
+                    .map(|(name, _)| name.clone())
+                    .collect::<Vec<_>>();
+                pipeline_names.sort();
+
+                egui::ComboBox::from_label("Pipeline")
+                    .selected_text(render.pipeline.clone())
+                    .show_ui(ui, |ui| {
+                        pipeline_names.into_iter().for_each(|name| {
+                            ui.selectable_value(&mut render.pipeline, name.clone(), name);
+                        });
+                    });
+
+                let mut material_names = materials.keys().cloned().collect::<Vec<_>>();
+                material_names.sort();
+
+                egui::ComboBox::from_label("Material")
+                    .selected_text(render.material.clone())
+                    .show_ui(ui, |ui| {
+                        material_names.into_iter().for_each(|name| {
+                            ui.selectable_value(&mut render.material, name.clone(), name);
+                        });
+                    });
+
+                ui.label(format!("Indices: {}", render.index_count));
+            });
+        }
+
+        if let Ok(camera) = world.query_one_mut::<&mut crate::component::Camera>(entity) {
+            ui.collapsing("Camera", |ui| {
+                Self::projection(ui, camera);
+
+                match &camera.output.target {
+                    crate::component::CameraTarget::Window => ui.label("Target: Window"),
+                    crate::component::CameraTarget::Texture(name) => {
+                        ui.label(format!("Target: {}", name))
+                    }
+                };
+
+                ui.horizontal(|ui| {
+                    ui.label("Order");
+                    ui.add(egui::DragValue::new(&mut camera.output.order));
+                });
+
+                let viewport = &mut camera.output.viewport;
+                ui.horizontal(|ui| {
+                    ui.label("Viewport");
+                    [
+                        &mut viewport.x,
+                        &mut viewport.y,
+                        &mut viewport.width,
+                        &mut viewport.height,
+                    ]
+                    .into_iter()
+                    .for_each(|value| {
+                        ui.add(
+                            egui::DragValue::new(value)
+                                .speed(0.01)
+                                .clamp_range(0.0..=1.0),
+                        );
+                    });
+                });
+            });
+        }
+
+        if let Ok((controller, transform)) = world.query_one_mut::<(
+            &mut crate::component::CameraController,
+            &crate::component::Transform,
+        )>(entity)
+        {
+            ui.collapsing("Camera Controller", |ui| {
+                let position = transform.get_position();
+                let modes = [
+                    crate::component::ControllerMode::Fly,
+                    crate::component::ControllerMode::fps(),
+                    crate::component::ControllerMode::orbit(
+                        position + transform.forward() * 10.0,
+                        10.0,
+                    ),
+                    crate::component::ControllerMode::pan_zoom(10.0),
+                ];
+
+                egui::ComboBox::from_label("Mode")
+                    .selected_text(controller.mode.name())
+                    .show_ui(ui, |ui| {
+                        modes.into_iter().for_each(|mode| {
+                            let selected = controller.mode.name() == mode.name();
+                            if ui.selectable_label(selected, mode.name()).clicked() && !selected {
+                                controller.set_mode(mode);
+                            }
+                        });
+                    });
+
+                ui.add(egui::Slider::new(&mut controller.speed, 0.1..=50.0).text("Speed"));
+                ui.add(
+                    egui::Slider::new(&mut controller.sensitivity, 0.01..=2.0).text("Sensitivity"),
+                );
+                ui.add(egui::Slider::new(&mut controller.smoothing, 0.0..=1.0).text("Smoothing"));
+            });
+        }
+
+        if let Ok((instance, render)) = world.query_one_mut::<(
+            &mut crate::prefab::PrefabInstance,
+            Option<&mut crate::component::Render>,
+        )>(entity)
+        {
+            ui.collapsing("Prefab", |ui| {
+                ui.label(format!("{} [{}]", instance.prefab, instance.index));
+
+                if let Some(render) = render {
+                    let mut enabled = instance.overrides.color.is_some();
+                    let mut color = instance
+                        .overrides
+                        .color
+                        .or_else(|| {
+                            materials
+                                .get(&render.material)
+                                .map(|material| material.uniform.color)
+                        })
+                        .unwrap_or([1.0, 1.0, 1.0, 1.0]);
+
+                    ui.horizontal(|ui| {
+                        if ui.checkbox(&mut enabled, "Color").changed() {
+                            // The engine creates the per-instance material on the next update
+                            let base = instance.overrides.material.clone().or_else(|| {
+                                prefabs
+                                    .entity(instance)
+                                    .and_then(|data| data.render.as_ref())
+                                    .map(|data| data.material.clone())
+                            });
+
+                            if let Some(base) = base {
+                                if enabled {
+                                    instance.overrides.color = Some(color);
+                                    render.material = crate::prefab::color_material(&base, entity);
+                                } else {
+                                    instance.overrides.color = None;
+                                    render.material = base;
+                                }
+                            }
+                        }
+
+                        ui.add_enabled_ui(enabled, |ui| {
+                            if ui.color_edit_button_rgba_unmultiplied(&mut color).changed() {
+                                instance.overrides.color = Some(color);
+                            }
+                        });
+                    });
+                }
+
+                crate::prefab::OverrideField::ALL
+                    .iter()
+                    .filter(|field| instance.overrides.is_set(**field))
+                    .for_each(|field| {
+                        ui.horizontal(|ui| {
+                            ui.label(format!("{:?} overridden", field));
+                            if ui.small_button("Revert").clicked() {
+                                self.actions
+                                    .push(EditorAction::RevertOverride(entity, *field));
+                            }
+                        });
+                    });
+            });
+        }
+
+        ui.separator();
+
+        ui.horizontal(|ui| {
+            if ui.button("Create Prefab").clicked() {
+                self.actions
+                    .push(EditorAction::CreatePrefab(entity, self.prefab_path.clone()));
+            }
+            if ui.button("Delete").clicked() {
+                self.actions.push(EditorAction::Delete(entity));
+            }
+        });
+    }
+
+    fn drag_vec3(ui: &mut egui::Ui, value: &mut nalgebra_glm::Vec3, speed: f32) -> bool {
+        let mut changed = false;
+
+        (0..3).for_each(|i| {
+            changed |= ui
+                .add(egui::DragValue::new(&mut value[i]).speed(speed))
+                .changed();
+        });
+
+        changed
+    }
+}
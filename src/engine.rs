@@ -1,10 +1,7 @@
 use std::collections::HashMap;
-use std::{
-    rc::Rc,
-    sync::{Arc, Mutex},
-};
+use std::sync::Arc;
 
-struct EGUI {
+struct Egui {
     context: egui::Context,
     platform: egui_winit::State,
     renderer: egui_wgpu::Renderer,
@@ -17,13 +14,33 @@ pub struct Engine {
     queue: wgpu::Queue,
     config: wgpu::SurfaceConfiguration,
     scene: hecs::World,
-    render_pipelines: HashMap<String, Rc<wgpu::RenderPipeline>>,
-    bind_groups: HashMap<String, Rc<wgpu::BindGroup>>,
-    egui: EGUI,
-    input: (bool, bool, bool, bool, bool, bool, bool, bool),
-    pub mouse_delta: (f32, f32),
+    render_pipelines: HashMap<String, crate::pipeline::Pipeline>,
+    render_targets: HashMap<String, crate::texture::RenderTarget>,
+    white_texture: wgpu::BindGroup,
+    materials: HashMap<String, crate::material::Material>,
+    material_bind_group_layout: wgpu::BindGroupLayout,
+    skin_bind_group_layout: wgpu::BindGroupLayout,
+    debug_draw: crate::debug_draw::DebugDraw,
+    picking: crate::picking::Picking,
+    // Drained from the picking at every update, until the next one
+    picking_events: Vec<crate::picking::PickingEvent>,
+    editor: crate::editor::Editor,
+    prefabs: crate::prefab::PrefabLibrary,
+    egui: Egui,
+    input: crate::component::input::Input,
+    gilrs: Option<gilrs::Gilrs>,
+    // Hidden and locked to the window for mouse look
+    cursor_grabbed: bool,
+    ui: crate::ui::UiCallbacks,
+    console: crate::console::Console,
+    profiler: crate::profiler::Profiler,
+    recorder: Option<crate::replay::Recorder>,
+    // The live input is put aside while a replay drives `input`
+    replay: Option<(crate::replay::Replay, crate::component::input::Input)>,
     camera: hecs::Entity,
     depth_texture: crate::texture::Texture,
+    last_update: std::time::Instant,
+    time: crate::component::FrameTime,
 }
 
 impl Engine {
@@ -78,374 +95,274 @@ impl Engine {
             crate::texture::Texture::create_depth_texture(&device, &config, "depth_texture");
 
         // Camera
-        let (camera, camera_bind_group, camera_bind_group_layout) = crate::component::Camera::new(
+        let camera_bind_group_layout = crate::component::Camera::create_bind_group_layout(&device);
+        let camera = crate::component::Camera::new(
             crate::component::CameraType::Perspective,
             window.clone(),
             device.clone(),
         );
 
+        // Textures, draws without one sample plain white
+        let texture_bind_group_layout = crate::texture::Texture::create_bind_group_layout(&device);
+        let white_texture = crate::texture::Texture::create_solid_texture(
+            &device,
+            &queue,
+            [255, 255, 255, 255],
+            "white_texture",
+        )
+        .create_bind_group(&device, &texture_bind_group_layout);
+
+        let mut render_targets = HashMap::new();
+        render_targets.insert(
+            "Monitor".to_owned(),
+            crate::texture::RenderTarget::new(
+                &device,
+                &texture_bind_group_layout,
+                512,
+                288,
+                config.format,
+            ),
+        );
+
+        // Materials
+        let material_bind_group_layout =
+            crate::material::Material::create_bind_group_layout(&device);
+
+        let mut materials = HashMap::new();
+        materials.insert(
+            "Default".to_owned(),
+            crate::material::Material::new(
+                &device,
+                &material_bind_group_layout,
+                [1.0, 1.0, 1.0, 1.0],
+            ),
+        );
+        materials.insert(
+            "Water".to_owned(),
+            crate::material::Material::new(
+                &device,
+                &material_bind_group_layout,
+                [0.2, 0.5, 1.0, 0.5],
+            ),
+        );
+
         // Pipeline
-        let render_pipeline = Rc::new({
-            let render_pipeline_layout =
-                device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
-                    label: Some("Render Pipeline Layout Descriptor"),
-                    bind_group_layouts: &[&camera_bind_group_layout],
-                    push_constant_ranges: &[],
-                });
-            let shader = wgpu::ShaderModuleDescriptor {
-                label: Some("Render Pipeline Shader"),
-                source: wgpu::ShaderSource::Wgsl(include_str!("shaders/default.wgsl").into()),
-            };
+        let render_pipeline = crate::pipeline::create_default_pipeline(
+            &device,
+            config.format,
+            &[&camera_bind_group_layout, &material_bind_group_layout],
+            crate::pipeline::BlendMode::Opaque,
+        );
 
-            let shader = device.create_shader_module(shader);
-
-            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-                label: Some("Render Pipeline"),
-                layout: Some(&render_pipeline_layout),
-                vertex: wgpu::VertexState {
-                    module: &shader,
-                    entry_point: "v_main",
-                    buffers: &[
-                        wgpu::VertexBufferLayout {
-                            array_stride: std::mem::size_of::<crate::vertex_type::DefaultVertex>()
-                                as u64,
-                            step_mode: wgpu::VertexStepMode::Vertex,
-                            attributes: &[
-                                wgpu::VertexAttribute {
-                                    format: wgpu::VertexFormat::Float32x3,
-                                    offset: 0,
-                                    shader_location: 0,
-                                },
-                                wgpu::VertexAttribute {
-                                    format: wgpu::VertexFormat::Float32x3,
-                                    offset: std::mem::size_of::<[f32; 3]>() as wgpu::BufferAddress,
-                                    shader_location: 1,
-                                },
-                            ],
-                        },
-                        wgpu::VertexBufferLayout {
-                            array_stride: std::mem::size_of::<
-                                crate::component::transform::TransformRaw,
-                            >() as wgpu::BufferAddress,
-                            step_mode: wgpu::VertexStepMode::Instance,
-                            attributes: &[
-                                wgpu::VertexAttribute {
-                                    offset: 0,
-                                    shader_location: 5,
-                                    format: wgpu::VertexFormat::Float32x4,
-                                },
-                                wgpu::VertexAttribute {
-                                    offset: std::mem::size_of::<[f32; 4]>() as wgpu::BufferAddress,
-                                    shader_location: 6,
-                                    format: wgpu::VertexFormat::Float32x4,
-                                },
-                                wgpu::VertexAttribute {
-                                    offset: std::mem::size_of::<[f32; 8]>() as wgpu::BufferAddress,
-                                    shader_location: 7,
-                                    format: wgpu::VertexFormat::Float32x4,
-                                },
-                                wgpu::VertexAttribute {
-                                    offset: std::mem::size_of::<[f32; 12]>() as wgpu::BufferAddress,
-                                    shader_location: 8,
-                                    format: wgpu::VertexFormat::Float32x4,
-                                },
-                            ],
-                        },
-                    ],
-                },
-                fragment: Some(wgpu::FragmentState {
-                    module: &shader,
-                    entry_point: "f_main",
-                    targets: &[Some(wgpu::ColorTargetState {
-                        format: config.format,
-                        blend: Some(wgpu::BlendState::REPLACE),
-                        write_mask: wgpu::ColorWrites::ALL,
-                    })],
-                }),
-                primitive: wgpu::PrimitiveState {
-                    topology: wgpu::PrimitiveTopology::TriangleList,
-                    strip_index_format: None,
-                    front_face: wgpu::FrontFace::Ccw,
-                    cull_mode: None,
-                    // cull_mode: Some(wgpu::Face::Back),
-                    unclipped_depth: false,
-                    polygon_mode: wgpu::PolygonMode::Fill,
-                    conservative: false,
-                },
-                depth_stencil: Some(wgpu::TextureFormat::Depth32Float).map(|format| {
-                    wgpu::DepthStencilState {
-                        format,
-                        depth_write_enabled: true,
-                        depth_compare: wgpu::CompareFunction::Less,
-                        stencil: wgpu::StencilState::default(),
-                        bias: wgpu::DepthBiasState::default(),
-                    }
-                }),
-                multisample: wgpu::MultisampleState::default(),
-                multiview: None,
-            })
-        });
+        let transparent_render_pipeline = crate::pipeline::create_default_pipeline(
+            &device,
+            config.format,
+            &[&camera_bind_group_layout, &material_bind_group_layout],
+            crate::pipeline::BlendMode::AlphaBlend,
+        );
 
-        let line_render_pipeline = Rc::new({
-            let render_pipeline_layout =
-                device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
-                    label: Some("Render Pipeline Layout Descriptor"),
-                    bind_group_layouts: &[&camera_bind_group_layout],
-                    push_constant_ranges: &[],
-                });
-            let shader = wgpu::ShaderModuleDescriptor {
-                label: Some("Render Pipeline Shader"),
-                source: wgpu::ShaderSource::Wgsl(include_str!("shaders/line.wgsl").into()),
-            };
+        let skin_bind_group_layout = crate::component::Skin::create_bind_group_layout(&device);
 
-            let shader = device.create_shader_module(shader);
-
-            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-                label: Some("Render Pipeline"),
-                layout: Some(&render_pipeline_layout),
-                vertex: wgpu::VertexState {
-                    module: &shader,
-                    entry_point: "v_main",
-                    buffers: &[
-                        wgpu::VertexBufferLayout {
-                            array_stride: std::mem::size_of::<crate::vertex_type::LineVertex>()
-                                as u64,
-                            step_mode: wgpu::VertexStepMode::Vertex,
-                            attributes: &[
-                                wgpu::VertexAttribute {
-                                    format: wgpu::VertexFormat::Float32x3,
-                                    offset: 0,
-                                    shader_location: 0,
-                                },
-                                wgpu::VertexAttribute {
-                                    format: wgpu::VertexFormat::Float32x3,
-                                    offset: std::mem::size_of::<[f32; 3]>() as wgpu::BufferAddress,
-                                    shader_location: 1,
-                                },
-                            ],
-                        },
-                        wgpu::VertexBufferLayout {
-                            array_stride: std::mem::size_of::<
-                                crate::component::transform::TransformRaw,
-                            >() as wgpu::BufferAddress,
-                            step_mode: wgpu::VertexStepMode::Instance,
-                            attributes: &[
-                                wgpu::VertexAttribute {
-                                    offset: 0,
-                                    shader_location: 5,
-                                    format: wgpu::VertexFormat::Float32x4,
-                                },
-                                wgpu::VertexAttribute {
-                                    offset: std::mem::size_of::<[f32; 4]>() as wgpu::BufferAddress,
-                                    shader_location: 6,
-                                    format: wgpu::VertexFormat::Float32x4,
-                                },
-                                wgpu::VertexAttribute {
-                                    offset: std::mem::size_of::<[f32; 8]>() as wgpu::BufferAddress,
-                                    shader_location: 7,
-                                    format: wgpu::VertexFormat::Float32x4,
-                                },
-                                wgpu::VertexAttribute {
-                                    offset: std::mem::size_of::<[f32; 12]>() as wgpu::BufferAddress,
-                                    shader_location: 8,
-                                    format: wgpu::VertexFormat::Float32x4,
-                                },
-                            ],
-                        },
-                    ],
-                },
-                fragment: Some(wgpu::FragmentState {
-                    module: &shader,
-                    entry_point: "f_main",
-                    targets: &[Some(wgpu::ColorTargetState {
-                        format: config.format,
-                        blend: Some(wgpu::BlendState::REPLACE),
-                        write_mask: wgpu::ColorWrites::ALL,
-                    })],
-                }),
-                primitive: wgpu::PrimitiveState {
-                    topology: wgpu::PrimitiveTopology::LineList,
-                    strip_index_format: None,
-                    front_face: wgpu::FrontFace::Ccw,
-                    cull_mode: Some(wgpu::Face::Back),
-                    unclipped_depth: false,
-                    polygon_mode: wgpu::PolygonMode::Line,
-                    conservative: false,
-                },
-                depth_stencil: Some(wgpu::TextureFormat::Depth32Float).map(|format| {
-                    wgpu::DepthStencilState {
-                        format,
-                        depth_write_enabled: true,
-                        depth_compare: wgpu::CompareFunction::Less,
-                        stencil: wgpu::StencilState::default(),
-                        bias: wgpu::DepthBiasState::default(),
-                    }
-                }),
-                multisample: wgpu::MultisampleState::default(),
-                multiview: None,
-            })
-        });
+        let skinned_render_pipeline = crate::pipeline::create_skinned_pipeline(
+            &device,
+            config.format,
+            &[
+                &camera_bind_group_layout,
+                &material_bind_group_layout,
+                &skin_bind_group_layout,
+            ],
+            crate::pipeline::BlendMode::Opaque,
+        );
+
+        let textured_render_pipeline = crate::pipeline::create_textured_pipeline(
+            &device,
+            config.format,
+            &[
+                &camera_bind_group_layout,
+                &material_bind_group_layout,
+                &texture_bind_group_layout,
+            ],
+            crate::pipeline::BlendMode::Opaque,
+        );
+
+        let line_render_pipeline = crate::pipeline::create_line_pipeline(
+            &device,
+            config.format,
+            &[&camera_bind_group_layout, &material_bind_group_layout],
+            true,
+        );
+
+        let line_overlay_render_pipeline = crate::pipeline::create_line_pipeline(
+            &device,
+            config.format,
+            &[&camera_bind_group_layout, &material_bind_group_layout],
+            false,
+        );
 
         // Add pipeline to hashmap
         let mut render_pipelines = HashMap::new();
         render_pipelines.insert("Default".to_owned(), render_pipeline);
+        render_pipelines.insert("Transparent".to_owned(), transparent_render_pipeline);
+        render_pipelines.insert("Skinned".to_owned(), skinned_render_pipeline);
+        render_pipelines.insert("Textured".to_owned(), textured_render_pipeline);
         render_pipelines.insert("Line".to_owned(), line_render_pipeline);
+        render_pipelines.insert("LineOverlay".to_owned(), line_overlay_render_pipeline);
 
-        // Add bindgroups to hashmap
-        let mut bind_groups = HashMap::new();
-        bind_groups.insert("Camera".to_owned(), Rc::new(camera_bind_group));
-
-        // ECS
+        // ECS, the entities are spawned without a GPU and get their buffers, meshes and
+        // cameras here
         let mut scene = hecs::World::new();
+        let demo = spawn_default_scene(&mut scene);
 
-        let camera_transform = Arc::new(Mutex::new(
-            crate::component::TransformBuild::new()
-                .with_position(nalgebra_glm::vec3(0.0, 4.0, -6.0))
-                // .with_parent(triangle_transform_1.clone())
-                .build(),
-        ));
-
-        // Spawn triangle
-        let triangle_transform_1 = Arc::new(Mutex::new(
-            crate::component::TransformBuild::new()
-                .with_position(nalgebra_glm::vec3(0.0, 0.0, 4.0))
-                // .with_rotation(nalgebra_glm::vec3(-90.0, 0.0, 0.0))
-                .with_parent(camera_transform.clone())
-                .with_buffer(device.as_ref())
-                .build(),
-        ));
+        scene.insert_one(demo.camera, camera).unwrap();
+        scene
+            .insert_one(
+                demo.monitor_camera,
+                crate::component::Camera::new(
+                    crate::component::CameraType::Perspective,
+                    window.clone(),
+                    device.clone(),
+                )
+                .with_output(crate::component::CameraOutput {
+                    order: -1,
+                    target: crate::component::CameraTarget::Texture("Monitor".to_owned()),
+                    ..Default::default()
+                }),
+            )
+            .unwrap();
 
-        // Spawn camera
-        let camera = scene.spawn((camera_transform.clone(), camera));
+        let transform_buffer = |scene: &mut hecs::World, entity: hecs::Entity| {
+            let transform = scene
+                .query_one_mut::<&mut crate::component::Transform>(entity)
+                .unwrap();
+            transform.create_buffer(device.as_ref());
+            transform.buffer.clone()
+        };
 
-        scene.spawn((
-            triangle_transform_1.clone(),
-            crate::component::render::Render::new(
-                device.as_ref(),
-                (
-                    vec![
-                        crate::vertex_type::DefaultVertex {
-                            position: [0.0, 1.0, 0.0],
-                            color: [1.0, 0.0, 0.0],
-                        },
-                        crate::vertex_type::DefaultVertex {
-                            position: [1.0, -1.0, 0.0],
-                            color: [1.0, 0.0, 0.0],
-                        },
-                        crate::vertex_type::DefaultVertex {
-                            position: [-1.0, -1.0, 0.0],
-                            color: [1.0, 0.0, 0.0],
-                        },
-                    ],
-                    vec![0u16, 2, 1],
-                ),
-                "Default".to_string(),
-                triangle_transform_1.clone().lock().unwrap().buffer.clone(),
+        [
+            (
+                demo.triangle,
+                crate::shapes::MeshSource::Inline {
+                    positions: vec![[0.0, 1.0, 0.0], [1.0, -1.0, 0.0], [-1.0, -1.0, 0.0]],
+                    colors: vec![[1.0, 0.0, 0.0]; 3],
+                    indices: vec![0u16, 2, 1],
+                },
+                "Default",
+                "Default",
+                None,
             ),
-        ));
+            (
+                demo.marching_squares,
+                crate::shapes::MeshSource::MarchingSquares {
+                    width: 50,
+                    height: 50,
+                },
+                "Default",
+                "Default",
+                None,
+            ),
+            (
+                demo.water,
+                crate::shapes::MeshSource::Plane {
+                    width: 10.0,
+                    depth: 10.0,
+                    color: [1.0, 1.0, 1.0],
+                },
+                "Transparent",
+                "Water",
+                None,
+            ),
+            (
+                demo.monitor,
+                crate::shapes::MeshSource::TexturedQuad {
+                    width: 4.0,
+                    height: 2.25,
+                },
+                "Textured",
+                "Default",
+                Some("Monitor".to_owned()),
+            ),
+        ]
+        .into_iter()
+        .for_each(|(entity, mesh, pipeline, material, texture)| {
+            let buffer = transform_buffer(&mut scene, entity);
+            let render = mesh
+                .create_render(device.as_ref(), pipeline.to_owned(), buffer)
+                .unwrap()
+                .with_material(material.to_owned())
+                .with_texture(texture);
+            scene.insert_one(entity, render).unwrap();
+        });
 
-        // let triangle_transform_2 = Arc::new(Mutex::new(
-        //     crate::component::TransformBuild::new()
-        //         .with_position(nalgebra_glm::vec3(3.0, 1.0, 0.0))
-        //         .with_buffer(device.as_ref())
-        //         .build(),
-        // ));
-
-        // scene.spawn((
-        //     triangle_transform_2.clone(),
-        //     crate::component::render::Render::new(
-        //         device.as_ref(),
-        //         (
-        //             vec![
-        //                 crate::vertex_type::DefaultVertex {
-        //                     position: [0.0, 1.0, 0.0],
-        //                 },
-        //                 crate::vertex_type::DefaultVertex {
-        //                     position: [1.0, -1.0, 0.0],
-        //                 },
-        //                 crate::vertex_type::DefaultVertex {
-        //                     position: [-1.0, -1.0, 0.0],
-        //                 },
-        //             ],
-        //             vec![0u16, 2, 1],
-        //         ),
-        //         "Default".to_string(),
-        //         triangle_transform_2.clone().lock().unwrap().buffer.clone(),
-        //     ),
-        // ));
-
-        // let triangle_transform_3 = Arc::new(Mutex::new(
-        //     crate::component::TransformBuild::new()
-        //         .with_position(nalgebra_glm::vec3(-3.0, 1.0, 0.0))
-        //         .with_buffer(device.as_ref())
-        //         .build(),
-        // ));
-
-        // scene.spawn((
-        //     triangle_transform_3.clone(),
-        //     crate::component::render::Render::new(
-        //         device.as_ref(),
-        //         (
-        //             vec![
-        //                 crate::vertex_type::DefaultVertex {
-        //                     position: [0.0, 1.0, 0.0],
-        //                 },
-        //                 crate::vertex_type::DefaultVertex {
-        //                     position: [1.0, -1.0, 0.0],
-        //                 },
-        //                 crate::vertex_type::DefaultVertex {
-        //                     position: [-1.0, -1.0, 0.0],
-        //                 },
-        //             ],
-        //             vec![0u16, 2, 1],
-        //         ),
-        //         "Default".to_string(),
-        //         triangle_transform_3.clone().lock().unwrap().buffer.clone(),
-        //     ),
-        // ));
-
-        let quad = Arc::new(Mutex::new(
-            crate::component::TransformBuild::new()
-                .with_position(nalgebra_glm::vec3(3.5, 3.5, 11.0))
-                .with_rotation(nalgebra_glm::vec3(0.0, -90.0, 0.0))
-                .with_buffer(device.as_ref())
-                .build(),
-        ));
+        // Lines are drawn where they are, without a transform buffer
+        scene
+            .insert_one(
+                demo.grid,
+                crate::shapes::MeshSource::Grid {
+                    width: 100.0,
+                    depth: 100.0,
+                    m: 50,
+                    n: 50,
+                }
+                .create_render(device.as_ref(), "Line".to_owned(), None)
+                .unwrap(),
+            )
+            .unwrap();
 
-        quad.lock().unwrap().add_rotation_x(-90.0);
+        let skin = crate::component::Skin::new(
+            device.as_ref(),
+            &skin_bind_group_layout,
+            demo.joints.clone(),
+            crate::skinning::inverse_bind_matrices(&scene, demo.column, &demo.joints),
+        );
+        let column_buffer = transform_buffer(&mut scene, demo.column);
+        let column_render = crate::shapes::MeshSource::SkinnedColumn {
+            height: 3.0,
+            width: 0.5,
+            rings: 12,
+            joints: 3,
+            color: [0.9, 0.6, 0.3],
+        }
+        .create_render(device.as_ref(), "Skinned".to_owned(), column_buffer)
+        .unwrap()
+        .with_bind_group(skin.bind_group.clone());
+        scene.insert(demo.column, (skin, column_render)).unwrap();
 
-        scene.spawn((
-            quad.clone(),
-            crate::component::Render::new(
-                device.as_ref(),
-                crate::shapes::create_quad_marching_squares(50, 50),
-                "Default".to_string(),
-                quad.lock().unwrap().buffer.clone(),
-            ),
-        ));
+        let debug_draw = crate::debug_draw::DebugDraw::new(device.as_ref());
 
-        let lines = Arc::new(Mutex::new(
-            crate::component::TransformBuild::new()
-                // .with_buffer(device.as_ref())
-                .build(),
-        ));
+        let picking =
+            crate::picking::Picking::new(device.as_ref(), &config, &camera_bind_group_layout);
 
-        scene.spawn((
-            crate::component::Render::new(
-                device.as_ref(),
-                crate::shapes::create_grid(100.0, 100.0, 50, 50),
-                "Line".to_owned(),
-                lines.lock().unwrap().buffer.clone(),
-            ),
-            lines.clone(),
-        ));
+        // Saved bindings replace the defaults they name
+        let editor = crate::editor::Editor::new();
+        let mut input = default_input();
+        if std::path::Path::new(&editor.bindings_path).exists() {
+            match crate::component::input::Bindings::load(&editor.bindings_path) {
+                Ok(bindings) => input.bindings_mut().merge(bindings),
+                Err(e) => log::error!("Can't load bindings {}: {}", editor.bindings_path, e),
+            }
+        }
+
+        // Gamepads plugged in before the start don't send a connected event
+        let gilrs = match gilrs::Gilrs::new() {
+            Ok(gilrs) => {
+                gilrs.gamepads().for_each(|(id, gamepad)| {
+                    let player = input.connect_gamepad(id.into());
+                    log::info!("{} is used by player {}", gamepad.name(), player);
+                });
+                Some(gilrs)
+            }
+            Err(e) => {
+                log::warn!("Gamepads are not available: {}", e);
+                None
+            }
+        };
 
-        Self {
+        let mut engine = Self {
             surface,
             config,
             scene,
             render_pipelines,
-            egui: EGUI {
+            egui: Egui {
                 context: egui::Context::default(),
                 platform: egui_winit::State::new(event_loop),
                 renderer: egui_wgpu::Renderer::new(
@@ -458,12 +375,35 @@ impl Engine {
             device,
             queue,
             window,
-            input: (false, false, false, false, false, false, false, false),
-            bind_groups,
-            mouse_delta: (0.0, 0.0),
-            camera,
+            input,
+            gilrs,
+            cursor_grabbed: false,
+            ui: crate::ui::UiCallbacks::default(),
+            console: default_console(),
+            profiler: crate::profiler::Profiler::new(),
+            recorder: None,
+            replay: None,
+            render_targets,
+            white_texture,
+            materials,
+            material_bind_group_layout,
+            skin_bind_group_layout,
+            debug_draw,
+            picking,
+            picking_events: vec![],
+            editor,
+            prefabs: crate::prefab::PrefabLibrary::new(),
+            camera: demo.camera,
             depth_texture,
-        }
+            last_update: std::time::Instant::now(),
+            time: crate::component::FrameTime::default(),
+        };
+
+        // The game starts with mouse look, the editor releases the cursor
+        engine.set_cursor_grab(!engine.editor.enabled);
+        engine.add_ui("Debug Info", crate::ui::debug_info);
+
+        engine
     }
 
     pub fn render(&mut self) -> Result<(), wgpu::SurfaceError> {
@@ -478,71 +418,297 @@ impl Engine {
                 label: Some("Encoder"),
             });
 
-        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-            label: Some("Render Pass"),
-            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                view: &view,
-                resolve_target: None,
-                ops: wgpu::Operations {
-                    load: wgpu::LoadOp::Clear(wgpu::Color {
-                        r: 0.33,
-                        g: 0.33,
-                        b: 0.33,
-                        a: 1.0,
-                    }),
-                    store: true,
+        // Time spent recording each pass on the CPU
+        let mut pass = std::time::Instant::now();
+        let mut stats = crate::profiler::RenderStats::default();
+
+        if self.picking.mode == crate::picking::PickingMode::Exact {
+            let camera = self
+                .scene
+                .get::<&crate::component::Camera>(self.camera)
+                .unwrap();
+
+            // Hover and selection follow a frame behind the cursor
+            if let Some(hit) = self.picking.render_ids(
+                &self.device,
+                &self.queue,
+                &self.scene,
+                &self.render_pipelines,
+                &camera,
+                &mut stats,
+            ) {
+                self.picking.apply(hit);
+            }
+            pass = self.profiler.pass("Picking", pass);
+        }
+
+        // The UI runs first, its callbacks may add debug lines
+        // Name of the selection above its origin
+        let marker = self.picking.selected().and_then(|entity| {
+            let position = self
+                .scene
+                .get::<&crate::component::GlobalTransform>(entity)
+                .ok()?
+                .position();
+            let label = self
+                .scene
+                .get::<&crate::component::Name>(entity)
+                .map(|name| name.0.clone())
+                .unwrap_or_else(|_| format!("{:?}", entity));
+
+            let mut query = self
+                .scene
+                .query_one::<(
+                    &crate::component::Camera,
+                    &crate::component::GlobalTransform,
+                )>(self.camera)
+                .ok()?;
+            let (camera, transform) = query.get()?;
+            let screen = camera.world_to_screen(transform, &position)?;
+
+            (0.0..=1.0)
+                .contains(&screen.z)
+                .then_some((label, screen.xy()))
+        });
+
+        // EGUI
+        let input = self.egui.platform.take_egui_input(self.window.as_ref());
+        let output = self.egui.context.run(input, |ctx| {
+            if let Some((label, position)) = &marker {
+                let pixels_per_point = ctx.pixels_per_point();
+
+                ctx.debug_painter().text(
+                    egui::pos2(position.x / pixels_per_point, position.y / pixels_per_point),
+                    egui::Align2::CENTER_BOTTOM,
+                    label,
+                    egui::FontId::proportional(16.0),
+                    egui::Color32::WHITE,
+                );
+            }
+
+            self.editor.recording = self.recorder.is_some();
+            self.editor.replaying = self.replay.is_some();
+            self.editor.show(
+                ctx,
+                &mut self.scene,
+                &mut self.picking,
+                &self.render_pipelines,
+                &self.materials,
+                &self.prefabs,
+            );
+            self.editor.show_bindings(ctx, &mut self.input);
+
+            self.ui.show(
+                ctx,
+                &mut crate::ui::UiContext {
+                    world: &mut self.scene,
+                    input: &self.input,
+                    time: &self.time,
+                    camera: self.camera,
+                    editor_enabled: self.editor.enabled,
+                    debug_draw: &mut self.debug_draw,
+                    picking_events: &self.picking_events,
                 },
-            })],
-            depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
-                view: &self.depth_texture.view,
-                depth_ops: Some(wgpu::Operations {
-                    load: wgpu::LoadOp::Clear(1.0),
-                    store: true,
-                }),
-                stencil_ops: None,
-            }),
+            );
+
+            self.profiler.show(ctx);
+
+            self.console.show(
+                ctx,
+                &mut crate::console::ConsoleContext {
+                    world: &mut self.scene,
+                    camera: self.camera,
+                    locked: self.recorder.is_some() || self.replay.is_some(),
+                    editor: &mut self.editor,
+                },
+            );
         });
+        pass = self.profiler.pass("UI Layout", pass);
 
-        self.scene
-            .query_mut::<&mut crate::component::render::Render>()
+        self.debug_draw.upload(&self.device, &self.queue);
+        pass = self.profiler.pass("Upload", pass);
+
+        let mut cameras = self
+            .scene
+            .query_mut::<&crate::component::Camera>()
             .into_iter()
-            .for_each(|(_id, render)| {
-                render.draw(
-                    &mut render_pass,
-                    &self.render_pipelines[render.pipeline.as_str()],
-                    Some(vec![(0, &self.bind_groups["Camera"])]),
-                )
+            .map(|(entity, camera)| (entity, camera.output.order))
+            .collect::<Vec<_>>();
+        cameras.sort_by_key(|(entity, order)| (*order, entity.id()));
+
+        // The first camera drawing to a target clears it
+        let mut cleared = std::collections::HashSet::new();
+        let wireframe = self
+            .console
+            .cvar("wireframe")
+            .is_some_and(|wireframe| wireframe.as_bool());
+        let clear_color = wgpu::Color {
+            r: 0.33,
+            g: 0.33,
+            b: 0.33,
+            a: 1.0,
+        };
+
+        for (entity, _) in cameras {
+            let name = self
+                .scene
+                .get::<&crate::component::Name>(entity)
+                .map(|name| name.0.clone())
+                .unwrap_or_else(|_| format!("Camera {}", entity.id()));
+
+            let Ok((transform, camera)) = self.scene.query_one_mut::<(
+                &crate::component::GlobalTransform,
+                &crate::component::Camera,
+            )>(entity) else {
+                continue;
+            };
+
+            let view_matrix = camera.build_view(transform);
+            let frustum = camera.frustum(transform);
+            let output = camera.output.clone();
+            let camera_bind_group = camera.bind_group.clone();
+
+            let (color_view, depth_view, size) = match &output.target {
+                crate::component::CameraTarget::Window => (
+                    &view,
+                    &self.depth_texture.view,
+                    nalgebra_glm::vec2(self.config.width as f32, self.config.height as f32),
+                ),
+                crate::component::CameraTarget::Texture(name) => {
+                    match self.render_targets.get(name) {
+                        Some(target) => (
+                            &target.color.view,
+                            &target.depth.view,
+                            nalgebra_glm::vec2(target.width as f32, target.height as f32),
+                        ),
+                        None => continue,
+                    }
+                }
+            };
+
+            let load = match cleared.insert(output.target.clone()) {
+                true => wgpu::LoadOp::Clear(clear_color),
+                false => wgpu::LoadOp::Load,
+            };
+
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Render Pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: color_view,
+                    resolve_target: None,
+                    ops: wgpu::Operations { load, store: true },
+                })],
+                depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                    view: depth_view,
+                    depth_ops: Some(wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(1.0),
+                        store: true,
+                    }),
+                    stencil_ops: None,
+                }),
             });
 
-        drop(render_pass);
+            let (position, viewport_size) = output.viewport.to_pixels(&size);
+            render_pass.set_viewport(
+                position.x.min(size.x - 1.0),
+                position.y.min(size.y - 1.0),
+                viewport_size.x.max(1.0),
+                viewport_size.y.max(1.0),
+                0.0,
+                1.0,
+            );
 
-        // EGUI
-        let input = self.egui.platform.take_egui_input(self.window.as_ref());
-        let output = self.egui.context.run(input, |ctx| {
-            egui::Area::new("debug_info")
-                .fixed_pos(egui::pos2(0.0, 0.0))
-                .show(ctx, |ui| {
-                    let transform = self
-                        .scene
-                        .query_one_mut::<&crate::component::TransformType>(self.camera)
-                        .unwrap()
-                        .lock()
-                        .unwrap();
-
-                    ui.label(
-                        egui::RichText::new(format!("position: {:.4?}", transform.get_position()))
-                            .background_color(egui::Color32::from_rgba_premultiplied(0, 0, 0, 160))
-                            .color(egui::Color32::WHITE)
-                            .size(20.0),
-                    );
-                    ui.label(
-                        egui::RichText::new(format!("rotation: {:.4?}", transform.get_rotation()))
-                            .background_color(egui::Color32::from_rgba_premultiplied(0, 0, 0, 160))
-                            .color(egui::Color32::WHITE)
-                            .size(20.0),
+            let mut render_queue = crate::render_queue::RenderQueue::new();
+
+            self.scene
+                .query_mut::<(
+                    &crate::component::Render,
+                    Option<&crate::component::GlobalTransform>,
+                )>()
+                .into_iter()
+                // A texture can't be sampled while it is rendered to
+                .filter(|(_, (render, _))| match (&render.texture, &output.target) {
+                    (Some(texture), crate::component::CameraTarget::Texture(target)) => {
+                        texture != target
+                    }
+                    _ => true,
+                })
+                .for_each(|(_id, (render, transform))| {
+                    let world_matrix = transform
+                        .map(|transform| transform.matrix)
+                        .unwrap_or_else(nalgebra_glm::Mat4::identity);
+
+                    // Skinned meshes can leave the bounds of their bind pose
+                    if render.bind_group.is_none()
+                        && !frustum.intersects_aabb(&render.bounds.transform(&world_matrix))
+                    {
+                        return;
+                    }
+
+                    let center = render.bounds.center();
+                    let depth = (view_matrix * world_matrix * center.push(1.0)).z;
+
+                    render_queue.push(
+                        render,
+                        self.render_pipelines[render.pipeline.as_str()].blend_mode,
+                        depth,
                     );
                 });
-        });
+
+            render_queue.sort();
+            stats.batches += render_queue.batches();
+
+            let mut draw_state = crate::render_queue::DrawState::default();
+
+            render_queue.iter().for_each(|item| {
+                let texture = item
+                    .render
+                    .texture
+                    .as_ref()
+                    .and_then(|name| self.render_targets.get(name))
+                    .map_or(&self.white_texture, |target| &target.bind_group);
+
+                let pipeline = &self.render_pipelines[item.render.pipeline.as_str()];
+                let render_pipeline = match (wireframe, &pipeline.wireframe) {
+                    (true, Some(wireframe)) => wireframe,
+                    _ => &pipeline.render_pipeline,
+                };
+
+                item.render.draw(
+                    &mut render_pass,
+                    &mut draw_state,
+                    render_pipeline,
+                    Some(vec![
+                        (0, camera_bind_group.as_ref()),
+                        (1, &self.materials[item.render.material.as_str()].bind_group),
+                        (2, texture),
+                    ]),
+                    pipeline.topology,
+                    &mut stats,
+                )
+            });
+
+            if entity == self.camera && !self.debug_draw.is_empty() {
+                self.debug_draw.draw(
+                    &mut render_pass,
+                    &self.render_pipelines["Line"].render_pipeline,
+                    &self.render_pipelines["LineOverlay"].render_pipeline,
+                    vec![
+                        (0, camera_bind_group.as_ref()),
+                        (1, &self.materials["Default"].bind_group),
+                    ],
+                    &mut stats,
+                );
+            }
+
+            drop(render_pass);
+            pass = self.profiler.pass(&name, pass);
+        }
+
+        let window_load = match cleared.contains(&crate::component::CameraTarget::Window) {
+            true => wgpu::LoadOp::Load,
+            false => wgpu::LoadOp::Clear(clear_color),
+        };
 
         let paint_jobs = self.egui.context.tessellate(output.shapes.clone());
 
@@ -572,7 +738,7 @@ impl Engine {
                     view: &view,
                     resolve_target: None,
                     ops: wgpu::Operations {
-                        load: wgpu::LoadOp::Load,
+                        load: window_load,
                         store: true,
                     },
                 })],
@@ -589,176 +755,929 @@ impl Engine {
                 .renderer
                 .render(&mut render_pass, &paint_jobs, &screen_descriptor);
         }
+        pass = self.profiler.pass("UI", pass);
 
         self.queue.submit(std::iter::once(encoder.finish()));
         output_frame.present();
+        self.profiler.pass("Submit", pass);
+
+        *self.profiler.render_stats() = stats;
+        if self.profiler.enabled {
+            self.profiler.set_entities(self.scene.len());
+            self.profiler.set_memory(self.gpu_memory());
+        }
 
         Ok(())
     }
 
     pub fn update(&mut self) {
-        // self.scene
-        //     .query_mut::<(
-        //         &mut crate::component::TransformType,
-        //         &mut crate::component::Render,
-        //     )>()
-        //     .into_iter()
-        //     .enumerate()
-        //     .for_each(|(i, (_, (transform, _)))| {
-        //         if let Ok(mut transform) = transform.lock() {
-        //             // if i == 0 {
-        //         //         // transform.add_rotation_y(0.5);
-        //         //         if self.input.0 {
-        //         //             let position = transform.get_position();
-        //         //             let right = transform.right();
-        //         //             transform.set_position(&(position + right * 0.05));
-        //         //         }
-        //         //         if self.input.1 {
-        //         //             let position = transform.get_position();
-        //         //             let right = transform.right();
-        //         //             transform.set_position(&(position - right * 0.05));
-        //         //         }
-        //         //         if self.input.2 {
-        //         //             let position = transform.get_position();
-        //         //             let forward = transform.forward();
-        //         //             transform.set_position(&(position + forward * 0.05));
-        //         //         }
-        //         //         if self.input.3 {
-        //         //             let position = transform.get_position();
-        //         //             let forward = transform.forward();
-        //         //             transform.set_position(&(position - forward * 0.05));
-        //         //         }
-
-        //         //         transform.add_rotation_x(-self.mouse_delta.1 * 0.5);
-        //         //         // transform.add_rotation_y(self.mouse_delta.0 * 0.5);
-        //         //         let up = transform.up();
-        //         //         transform.add_rotation_global_y(self.mouse_delta.0 * 0.5 * up.y.signum());
-        //             // }
-
-        //             match transform.buffer.as_ref() {
-        //                 Some(buffer) => {
-        //                     self.queue.write_buffer(
-        //                         buffer,
-        //                         0,
-        //                         bytemuck::cast_slice(&[transform.to_raw()]),
-        //                     );
-        //                 }
-        //                 None => {}
-        //             }
-        //         }
-        //     });
-
-        // Provisional camera controller
+        let now = std::time::Instant::now();
+        let mut delta = (now - self.last_update).as_secs_f32();
+        self.last_update = now;
+
+        self.profiler.begin_frame();
+        let mut stage = now;
+
+        // Lines last for one frame, also when the previous one couldn't be presented
+        self.debug_draw.clear();
+
+        // Recordings step by the same time every frame, however long the frame took
+        if let Some(timestep) = self
+            .recorder
+            .as_ref()
+            .map(|recorder| recorder.timestep())
+            .or(self.replay.as_ref().map(|(replay, _)| replay.timestep()))
+        {
+            delta = timestep;
+        }
+
+        self.time.elapsed += delta;
+        self.time.frame = self.time.frame.wrapping_add(1);
+
+        if let Some(gilrs) = &mut self.gilrs {
+            while let Some(event) = gilrs.next_event() {
+                match event.event {
+                    gilrs::EventType::Connected => {
+                        log::info!("{} connected", gilrs.gamepad(event.id).name())
+                    }
+                    gilrs::EventType::Disconnected => {
+                        log::info!("Gamepad {} disconnected", event.id)
+                    }
+                    _ => {}
+                }
+                let input = match &mut self.replay {
+                    Some((_, live)) => live,
+                    None => &mut self.input,
+                };
+                input.handle_gamepad_event(event.id.into(), &event.event);
+            }
+        }
+
+        match &mut self.replay {
+            Some((replay, _)) => {
+                if !replay.next_frame(&mut self.input) {
+                    self.stop_replay();
+                }
+            }
+            None => {
+                // The UI gets the keyboard and the mouse it asks for, unless the mouse is grabbed
+                let ui = !self.cursor_grabbed;
+                self.input.set_blocked(
+                    ui && self.egui.context.wants_keyboard_input(),
+                    ui && (self.egui.context.wants_pointer_input()
+                        || self.egui.context.is_pointer_over_area()),
+                );
+            }
+        }
+
+        if self.input.pressed("Toggle Editor") {
+            self.editor.toggle();
+            self.set_cursor_grab(!self.editor.enabled);
+        }
+        if self.input.pressed("Grab Cursor") {
+            self.set_cursor_grab(!self.cursor_grabbed);
+        }
+        if self.input.pressed("Toggle Console") {
+            self.console.toggle();
+        }
+        // Typing needs the cursor, the console may also close itself
+        if self.console.take_toggled() {
+            self.set_cursor_grab(!self.console.is_open() && !self.editor.enabled);
+        }
+        if self.input.pressed("Toggle Stats") {
+            self.profiler.toggle();
+        }
+
+        // The console shows the field of view of the active camera, scenes and the inspector
+        // change it too
+        if let Ok(camera) = self.scene.get::<&crate::component::Camera>(self.camera) {
+            self.console.sync_cvar("camera.fov", camera.fov);
+        }
+        if let Some(crate::console::CVarValue::String(name)) =
+            self.console.cvar("picking.mode").cloned()
+        {
+            match crate::picking::PickingMode::from_name(&name) {
+                Some(mode) => self.picking.mode = mode,
+                None => {
+                    log::warn!(
+                        "Unknown picking mode \"{}\", it is one of bounds, mesh or exact",
+                        name
+                    );
+                    self.console
+                        .sync_cvar("picking.mode", self.picking.mode.name());
+                }
+            }
+        }
+        stage = self.profiler.stage("Input", stage);
+
+        // Prefab instances remember what was changed on them
+        self.prefabs.detect_overrides(&mut self.scene);
+
+        // Recordings and replays start and stop between frames
+        let mut playback = vec![];
+        if self.input.pressed("Record") && self.replay.is_none() {
+            playback.push(crate::editor::EditorAction::ToggleRecording(
+                self.editor.recording_path.clone(),
+            ));
+        }
+
+        // Editor actions
+        let playing = self.recorder.is_some() || self.replay.is_some();
+        let actions = self
+            .editor
+            .drain_actions()
+            .filter(|action| {
+                let refused = playing && action.edits_world();
+                if refused {
+                    log::warn!("Can't edit the scene during a recording or a replay");
+                }
+                !refused
+            })
+            .collect::<Vec<_>>();
+        actions.into_iter().for_each(|action| match action {
+            crate::editor::EditorAction::Spawn(primitive) => self.spawn_primitive(primitive),
+            crate::editor::EditorAction::Delete(entity) => {
+                if entity == self.camera
+                    || crate::hierarchy::is_ancestor(&self.scene, entity, self.camera)
+                {
+                    log::warn!("The active camera can't be deleted");
+                } else {
+                    if let Err(e) = crate::hierarchy::despawn_recursive(&mut self.scene, entity) {
+                        log::error!("Can't delete entity: {}", e);
+                    }
+                }
+            }
+            crate::editor::EditorAction::SaveScene(path) => match self.save_scene(&path) {
+                Ok(()) => log::info!("Scene saved to {}", path),
+                Err(e) => log::error!("Can't save scene {}: {}", path, e),
+            },
+            crate::editor::EditorAction::LoadScene(path) => match self.load_scene(&path) {
+                Ok(()) => log::info!("Scene loaded from {}", path),
+                Err(e) => log::error!("Can't load scene {}: {}", path, e),
+            },
+            crate::editor::EditorAction::CreatePrefab(entity, path) => {
+                match self
+                    .prefabs
+                    .create(&mut self.scene, entity, &self.materials, &path)
+                {
+                    Ok(()) => log::info!("Prefab saved to {}", path),
+                    Err(e) => log::error!("Can't create prefab {}: {}", path, e),
+                }
+            }
+            crate::editor::EditorAction::InstantiatePrefab(path) => {
+                match self.instantiate_prefab(&path) {
+                    Ok(entity) => self.picking.select(Some(entity)),
+                    Err(e) => log::error!("Can't instantiate prefab {}: {}", path, e),
+                }
+            }
+            crate::editor::EditorAction::ReloadPrefab(path) => {
+                match self
+                    .prefabs
+                    .reload(&path, &mut self.scene, self.device.as_ref())
+                {
+                    Ok(()) => self.add_prefab_materials(&path, true),
+                    Err(e) => log::error!("Can't reload prefab {}: {}", path, e),
+                }
+            }
+            crate::editor::EditorAction::RevertOverride(entity, field) => {
+                if let Ok(instance) = self
+                    .scene
+                    .query_one_mut::<&mut crate::prefab::PrefabInstance>(entity)
+                {
+                    instance.overrides.clear(field);
+                }
+                self.prefabs
+                    .apply(&mut self.scene, entity, self.device.as_ref())
+                    .unwrap_or_else(|e| log::error!("Can't revert override: {}", e));
+            }
+            crate::editor::EditorAction::ToggleRecording(_)
+            | crate::editor::EditorAction::ToggleReplay(_) => playback.push(action),
+        });
+
+        self.update_prefab_materials();
+        stage = self.profiler.stage("Editor", stage);
+
+        // World axes
+        self.debug_draw.set_depth_test(false);
+        self.debug_draw.axes(&nalgebra_glm::Mat4::identity(), 1.0);
+        self.debug_draw.set_depth_test(true);
+
+        crate::simulation::step(&mut self.scene, &self.input, delta);
+
+        if let Some(recorder) = &mut self.recorder {
+            recorder.record_frame(self.input.take_recorded().unwrap_or_default(), &self.scene);
+        }
+        if let Some((replay, _)) = &mut self.replay {
+            if let Err(e) = replay.verify(&self.scene) {
+                log::warn!("Replay: {}", e);
+            }
+        }
+        stage = self.profiler.stage("Simulation", stage);
+
         self.scene
             .query_mut::<(
-                &mut crate::component::TransformType,
+                &crate::component::GlobalTransform,
                 &mut crate::component::Camera,
             )>()
             .into_iter()
             .for_each(|(_, (transform, camera))| {
-                if let Ok(mut transform) = transform.lock() {
-                    if self.input.0 {
-                        let position = transform.get_position();
-                        let right = transform.right();
-                        transform.set_position(&(position + right * 0.05));
-                    }
-                    if self.input.1 {
-                        let position = transform.get_position();
-                        let right = transform.right();
-                        transform.set_position(&(position - right * 0.05));
-                    }
-                    if self.input.2 {
-                        let position = transform.get_position();
-                        let forward = transform.forward();
-                        transform.set_position(&(position + forward * 0.05));
+                camera.target_size = match &camera.output.target {
+                    crate::component::CameraTarget::Window => {
+                        nalgebra_glm::vec2(self.config.width as f32, self.config.height as f32)
                     }
-                    if self.input.3 {
-                        let position = transform.get_position();
-                        let forward = transform.forward();
-                        transform.set_position(&(position - forward * 0.05));
-                    }
-                    if self.input.4 {
-                        transform.add_rotation_z(-0.1);
-                    }
-                    if self.input.5 {
-                        transform.add_rotation_z(0.1);
-                    }
-                    if self.input.6 {
-                        let position = transform.get_position();
-                        transform.set_position(&(position - nalgebra_glm::Vec3::y() * 0.05));
-                    }
-                    if self.input.7 {
-                        let position = transform.get_position();
-                        transform.set_position(&(position + nalgebra_glm::Vec3::y() * 0.05));
+                    crate::component::CameraTarget::Texture(name) => {
+                        match self.render_targets.get(name) {
+                            Some(target) => {
+                                nalgebra_glm::vec2(target.width as f32, target.height as f32)
+                            }
+                            None => camera.target_size,
+                        }
                     }
+                };
+                camera.update(transform, &self.time, &self.queue);
+            });
+        stage = self.profiler.stage("Cameras", stage);
 
-                    transform.add_rotation_x(-self.mouse_delta.1 * 0.5);
-                    // transform.add_rotation_y(self.mouse_delta.0 * 0.5);
-                    let up = transform.up();
-                    transform.add_rotation_global_y(self.mouse_delta.0 * 0.5 * up.y.signum());
+        let (cursor_ray, camera_position) = {
+            let (transform, camera) = self
+                .scene
+                .query_one_mut::<(
+                    &crate::component::GlobalTransform,
+                    &crate::component::Camera,
+                )>(self.camera)
+                .unwrap();
 
-                    self.window
-                        .set_cursor_position(winit::dpi::PhysicalPosition { x: 640.0, y: 360.0 })
-                        .unwrap_or_default();
+            let cursor_ray = self
+                .picking
+                .cursor()
+                .filter(|cursor| camera.contains_pixel(cursor))
+                .map(|cursor| camera.screen_to_ray(transform, &cursor));
+
+            (cursor_ray, transform.position())
+        };
 
-                    self.mouse_delta = (0.0, 0.0);
+        if self.input.pressed("Select")
+            && !(self.editor.enabled && !playing && self.editor.gizmo.press())
+        {
+            self.picking.request_select();
+        }
+        if self.input.released("Select") {
+            self.editor.gizmo.release();
+        }
 
-                    camera.update(&transform, &self.queue);
-                }
+        // Picking
+        self.picking.retain(&self.scene);
+
+        if self.picking.mode != crate::picking::PickingMode::Exact {
+            let hit = cursor_ray.and_then(|ray| {
+                crate::picking::Picking::raycast(
+                    &self.scene,
+                    &self.render_pipelines,
+                    &ray,
+                    self.picking.mode,
+                )
+                .map(|(entity, _)| entity)
             });
+
+            self.picking.apply(hit);
+        }
+        stage = self.profiler.stage("Picking", stage);
+
+        // Gizmo, recordings don't keep what it does
+        if self.editor.enabled && !playing {
+            self.editor.gizmo.update(
+                &mut self.scene,
+                self.picking.selected(),
+                cursor_ray,
+                &camera_position,
+                &mut self.debug_draw,
+            );
+        } else {
+            self.editor.gizmo.release();
+        }
+        stage = self.profiler.stage("Gizmo", stage);
+
+        // The gizmo may have moved the selection
+        crate::hierarchy::propagate(&mut self.scene);
+        crate::skinning::update(&self.scene, &self.queue);
+        self.upload_transforms();
+        stage = self.profiler.stage("Transforms", stage);
+
+        self.picking_events = self.picking.drain_events().collect();
+        self.picking_events
+            .iter()
+            .for_each(|event| log::debug!("{:?}", event));
+
+        [
+            (self.picking.hovered(), [1.0, 1.0, 0.0]),
+            (self.picking.selected(), [1.0, 0.5, 0.0]),
+        ]
+        .into_iter()
+        .filter_map(|(entity, color)| entity.map(|entity| (entity, color)))
+        .for_each(|(entity, color)| {
+            if let Ok((render, transform)) = self.scene.query_one_mut::<(
+                &crate::component::Render,
+                Option<&crate::component::GlobalTransform>,
+            )>(entity)
+            {
+                let world_matrix = transform
+                    .map(|transform| transform.matrix)
+                    .unwrap_or_else(nalgebra_glm::Mat4::identity);
+
+                self.debug_draw.set_depth_test(false);
+                self.debug_draw.aabb(&render.bounds, &world_matrix, color);
+                self.debug_draw.set_depth_test(true);
+            }
+        });
+
+        // Frustum of the selected camera, unless the scene is seen through it
+        if let Some(entity) = self
+            .picking
+            .selected()
+            .filter(|entity| *entity != self.camera)
+        {
+            if let Ok((camera, transform)) = self.scene.query_one_mut::<(
+                &crate::component::Camera,
+                &crate::component::GlobalTransform,
+            )>(entity)
+            {
+                self.debug_draw.frustum(camera, transform, [0.0, 1.0, 1.0]);
+            }
+        }
+
+        self.profiler.stage("Debug Draw", stage);
+
+        self.input.end_frame();
+
+        playback.into_iter().for_each(|action| match action {
+            crate::editor::EditorAction::ToggleRecording(path) => match self.recorder.is_some() {
+                true => self.stop_recording(),
+                false => self.start_recording(&path),
+            },
+            crate::editor::EditorAction::ToggleReplay(path) => match self.replay.is_some() {
+                true => self.stop_replay(),
+                false => self.start_replay(&path),
+            },
+            _ => {}
+        });
     }
 
+    // Events used by the UI don't reach the game, the UI is skipped while the cursor is grabbed
     pub fn input(&mut self, event: &winit::event::WindowEvent) -> bool {
-        fn is_pressed(s: &winit::event::ElementState) -> bool {
-            matches!(s, winit::event::ElementState::Pressed)
+        if !self.cursor_grabbed
+            && self
+                .egui
+                .platform
+                .on_event(&self.egui.context, event)
+                .consumed
+        {
+            // Releases still count, otherwise the button would stay held
+            if matches!(
+                event,
+                winit::event::WindowEvent::KeyboardInput {
+                    input: winit::event::KeyboardInput {
+                        state: winit::event::ElementState::Released,
+                        ..
+                    },
+                    ..
+                } | winit::event::WindowEvent::MouseInput {
+                    state: winit::event::ElementState::Released,
+                    ..
+                }
+            ) {
+                self.live_input().handle_window_event(event);
+            }
+            return true;
         }
 
         match event {
-            winit::event::WindowEvent::KeyboardInput {
-                input:
-                    winit::event::KeyboardInput {
-                        state,
-                        virtual_keycode: Some(key),
-                        ..
-                    },
-                ..
-            } => match key {
-                winit::event::VirtualKeyCode::A => {
-                    self.input.0 = is_pressed(state);
-                    true
+            // The grab is lost with the focus
+            winit::event::WindowEvent::Focused(focused) => {
+                if *focused && self.cursor_grabbed {
+                    self.set_cursor_grab(true);
                 }
-                winit::event::VirtualKeyCode::D => {
-                    self.input.1 = is_pressed(state);
-                    true
+                self.live_input().handle_window_event(event)
+            }
+            winit::event::WindowEvent::CursorMoved { position, .. } => {
+                self.picking.set_cursor(Some(nalgebra_glm::vec2(
+                    position.x as f32,
+                    position.y as f32,
+                )));
+                true
+            }
+            winit::event::WindowEvent::CursorLeft { .. } => {
+                self.picking.set_cursor(None);
+                true
+            }
+            _ => self.live_input().handle_window_event(event),
+        }
+    }
+
+    // Where events go, replays keep the live input up to date for when they stop
+    fn live_input(&mut self) -> &mut crate::component::input::Input {
+        match &mut self.replay {
+            Some((_, live)) => live,
+            None => &mut self.input,
+        }
+    }
+
+    pub fn add_ui(
+        &mut self,
+        name: &str,
+        callback: impl FnMut(&egui::Context, &mut crate::ui::UiContext) + 'static,
+    ) {
+        self.ui.add(name, Box::new(callback));
+    }
+
+    pub fn remove_ui(&mut self, name: &str) -> bool {
+        self.ui.remove(name)
+    }
+
+    // Buffers and textures the engine created, the ones of egui are left out
+    fn gpu_memory(&self) -> crate::profiler::GpuMemory {
+        let mut memory = crate::profiler::GpuMemory::default();
+
+        memory.add_texture(&self.depth_texture.texture);
+        self.render_targets.values().for_each(|target| {
+            memory.add_texture(&target.color.texture);
+            memory.add_texture(&target.depth.texture);
+        });
+        self.materials
+            .values()
+            .for_each(|material| memory.add_buffer(&material.buffer));
+        memory.add(self.debug_draw.memory());
+        memory.add(self.picking.memory());
+
+        // Transform buffers are shared with the renders drawing them
+        self.scene
+            .query::<(
+                Option<&crate::component::Render>,
+                Option<&crate::component::Transform>,
+                Option<&crate::component::Camera>,
+                Option<&crate::component::Skin>,
+            )>()
+            .iter()
+            .for_each(|(_, (render, transform, camera, skin))| {
+                if let Some(render) = render {
+                    memory.add_buffer(&render.vertex_buffer);
+                    memory.add_buffer(&render.index_buffer);
                 }
-                winit::event::VirtualKeyCode::W => {
-                    self.input.2 = is_pressed(state);
-                    true
+                if let Some(buffer) = transform.and_then(|transform| transform.buffer.as_ref()) {
+                    memory.add_buffer(buffer);
                 }
-                winit::event::VirtualKeyCode::S => {
-                    self.input.3 = is_pressed(state);
-                    true
+                if let Some(camera) = camera {
+                    memory.add_buffer(&camera.buffer);
                 }
-                winit::event::VirtualKeyCode::Q => {
-                    self.input.4 = is_pressed(state);
-                    true
+                if let Some(skin) = skin {
+                    memory.add_buffer(&skin.buffer);
                 }
-                winit::event::VirtualKeyCode::E => {
-                    self.input.5 = is_pressed(state);
-                    true
+            });
+
+        memory
+    }
+
+    // Lines drawn until the end of the frame, from the game or from UI callbacks
+    pub fn debug_draw(&mut self) -> &mut crate::debug_draw::DebugDraw {
+        &mut self.debug_draw
+    }
+
+    // For adding commands and variables
+    pub fn console(&mut self) -> &mut crate::console::Console {
+        &mut self.console
+    }
+
+    pub fn picking_mode(&self) -> crate::picking::PickingMode {
+        self.picking.mode
+    }
+
+    // Also shown by the "picking.mode" console variable
+    pub fn set_picking_mode(&mut self, mode: crate::picking::PickingMode) {
+        self.picking.mode = mode;
+        self.console.sync_cvar("picking.mode", mode.name());
+    }
+
+    // Hover and selection changes of the last update
+    pub fn picking_events(&self) -> &[crate::picking::PickingEvent] {
+        &self.picking_events
+    }
+
+    // Locked keeps the cursor in place but is missing on X11 and Windows, confined is
+    // missing on macOS. The motion is read from device events in both cases
+    fn set_cursor_grab(&mut self, grab: bool) {
+        let result = match grab {
+            true => self
+                .window
+                .set_cursor_grab(winit::window::CursorGrabMode::Locked)
+                .or_else(|_| {
+                    self.window
+                        .set_cursor_grab(winit::window::CursorGrabMode::Confined)
+                }),
+            false => self
+                .window
+                .set_cursor_grab(winit::window::CursorGrabMode::None),
+        };
+
+        match result {
+            Ok(()) => {
+                self.window.set_cursor_visible(!grab);
+                self.cursor_grabbed = grab;
+            }
+            Err(e) => log::warn!("Can't grab the cursor: {}", e),
+        }
+    }
+
+    // The mouse only moves the game while the cursor is grabbed
+    pub fn device_input(&mut self, event: &winit::event::DeviceEvent) {
+        if self.cursor_grabbed {
+            self.live_input().handle_device_event(event);
+        }
+    }
+
+    // Starts from the scene saved next to the recording. The world stays as it is, so the
+    // recording is refused when the file can't bring it back the way a replay needs it
+    fn start_recording(&mut self, path: &str) {
+        if self.replay.is_some() {
+            log::warn!("Can't record during a replay");
+            return;
+        }
+
+        let scene = format!("{}.scene.ron", path.trim_end_matches(".ron"));
+        if let Err(e) = self.save_scene(&scene) {
+            log::error!("Can't save the scene of the recording {}: {}", scene, e);
+            return;
+        }
+        if let Err(e) = crate::replay::verify_scene(&self.scene, &scene) {
+            log::error!("Can't record {}, {}", path, e);
+            return;
+        }
+
+        // What the controllers picked up so far isn't saved, they start over like the ones
+        // a replay loads
+        self.scene
+            .query_mut::<&mut crate::component::CameraController>()
+            .into_iter()
+            .for_each(|(_, controller)| controller.set_mode(controller.mode));
+
+        self.recorder = Some(crate::replay::Recorder::new(
+            path,
+            1.0 / 60.0,
+            self.input.bindings().clone(),
+            Some(scene),
+        ));
+        self.input.start_recording();
+        log::info!("Recording to {}", path);
+    }
+
+    fn stop_recording(&mut self) {
+        let Some(recorder) = self.recorder.take() else {
+            return;
+        };
+        self.input.stop_recording();
+
+        let path = recorder.path().to_owned();
+        let recording = recorder.finish(&self.scene);
+        match recording.save(&path) {
+            Ok(()) => log::info!("Recorded {} frames to {}", recording.length, path),
+            Err(e) => log::error!("Can't save recording {}: {}", path, e),
+        }
+    }
+
+    fn start_replay(&mut self, path: &str) {
+        if self.recorder.is_some() {
+            log::warn!("Can't replay during a recording");
+            return;
+        }
+
+        let recording = match crate::replay::Recording::load(path) {
+            Ok(recording) => recording,
+            Err(e) => {
+                log::error!("Can't load recording {}: {}", path, e);
+                return;
+            }
+        };
+        if let Some(scene) = &recording.scene {
+            if let Err(e) = self.load_scene(scene) {
+                log::error!("Can't load the scene of the recording {}: {}", scene, e);
+                return;
+            }
+        }
+
+        let input = std::mem::replace(&mut self.input, recording.create_input());
+        self.replay = Some((crate::replay::Replay::new(recording), input));
+        log::info!("Replaying {}", path);
+    }
+
+    fn stop_replay(&mut self) {
+        let Some((replay, mut input)) = self.replay.take() else {
+            return;
+        };
+
+        // Presses made during the replay are stale by now
+        input.end_frame();
+        self.input = input;
+        log::info!(
+            "Replay stopped after {} of {} frames",
+            replay.frame(),
+            replay.recording().length
+        );
+    }
+
+    fn spawn_primitive(&mut self, primitive: crate::editor::Primitive) {
+        let position = {
+            let transform = self
+                .scene
+                .query_one_mut::<&crate::component::GlobalTransform>(self.camera)
+                .unwrap();
+
+            transform.position() + transform.forward() * 5.0
+        };
+
+        let transform = crate::component::TransformBuild::new()
+            .with_position(position)
+            .with_buffer(self.device.as_ref())
+            .build();
+        let transform_buffer = transform.buffer.clone();
+
+        let mesh = match primitive {
+            crate::editor::Primitive::Cube => crate::shapes::MeshSource::Cube {
+                size: 1.0,
+                color: [0.8, 0.8, 0.8],
+            },
+            crate::editor::Primitive::Plane => crate::shapes::MeshSource::Plane {
+                width: 2.0,
+                depth: 2.0,
+                color: [0.8, 0.8, 0.8],
+            },
+            crate::editor::Primitive::MarchingSquares => {
+                crate::shapes::MeshSource::MarchingSquares {
+                    width: 10,
+                    height: 10,
                 }
-                winit::event::VirtualKeyCode::LControl => {
-                    self.input.6 = is_pressed(state);
-                    true
+            }
+        };
+        let render = mesh
+            .create_render(self.device.as_ref(), "Default".to_owned(), transform_buffer)
+            .unwrap();
+
+        let entity = self.scene.spawn((
+            crate::component::Name(format!("{:?}", primitive)),
+            transform,
+            render,
+        ));
+
+        self.picking.select(Some(entity));
+    }
+
+    pub fn save_scene(&self, path: &str) -> Result<(), crate::scene::SceneError> {
+        crate::scene::SceneFile::from_world(&self.scene, &self.materials).save(path)
+    }
+
+    // The current world is only replaced once the whole scene was loaded successfully
+    pub fn load_scene(&mut self, path: &str) -> Result<(), crate::scene::SceneError> {
+        let file = crate::scene::SceneFile::load(path)?;
+
+        let mut scene = hecs::World::new();
+        let spawned = file.instantiate(
+            &mut scene,
+            &self.device,
+            &self.window,
+            &self.skin_bind_group_layout,
+        )?;
+
+        let mut materials = file
+            .materials
+            .iter()
+            .map(|(name, color)| {
+                (
+                    name.clone(),
+                    crate::material::Material::new(
+                        self.device.as_ref(),
+                        &self.material_bind_group_layout,
+                        *color,
+                    ),
+                )
+            })
+            .collect::<HashMap<_, _>>();
+        materials.entry("Default".to_owned()).or_insert_with(|| {
+            crate::material::Material::new(
+                self.device.as_ref(),
+                &self.material_bind_group_layout,
+                [1.0, 1.0, 1.0, 1.0],
+            )
+        });
+
+        // The first camera with a transform drawing to the window becomes the active one
+        let camera = spawned.cameras.into_iter().find(|entity| {
+            scene
+                .query_one_mut::<(&crate::component::Transform, &crate::component::Camera)>(*entity)
+                .is_ok_and(|(_, camera)| {
+                    camera.output.target == crate::component::CameraTarget::Window
+                })
+        });
+
+        let camera = match camera {
+            Some(camera) => camera,
+            None => {
+                log::warn!("The scene has no camera, a default one is added");
+
+                scene.spawn((
+                    crate::component::Name::new("Camera"),
+                    crate::component::TransformBuild::new().build(),
+                    crate::component::Camera::new(
+                        crate::component::CameraType::Perspective,
+                        self.window.clone(),
+                        self.device.clone(),
+                    ),
+                ))
+            }
+        };
+
+        // Scenes saved without a controller get the default one on the active camera
+        if !scene
+            .satisfies::<&crate::component::CameraController>(camera)
+            .unwrap_or_default()
+        {
+            scene
+                .insert_one(
+                    camera,
+                    crate::component::CameraController::new(crate::component::ControllerMode::Fly),
+                )
+                .unwrap();
+        }
+
+        // Instances pick up the changes made to their prefabs since the scene was saved
+        let instances = scene
+            .query_mut::<&crate::prefab::PrefabInstance>()
+            .into_iter()
+            .map(|(entity, instance)| (entity, instance.prefab.clone()))
+            .collect::<Vec<_>>();
+
+        for (entity, path) in instances {
+            match self.prefabs.load(&path) {
+                Ok(_) => self
+                    .prefabs
+                    .apply(&mut scene, entity, self.device.as_ref())?,
+                Err(e) => log::warn!("Can't load prefab {}, the saved copy is kept: {}", path, e),
+            }
+        }
+
+        // Prefabs may have changed the pipelines, a mesh drawn with the wrong vertex layout
+        // or without its bind groups fails the validation of wgpu
+        for (entity, (render, name)) in
+            scene.query_mut::<(&crate::component::Render, Option<&crate::component::Name>)>()
+        {
+            match self.render_pipelines.get(&render.pipeline) {
+                None => {
+                    return Err(crate::scene::SceneError::UnknownPipeline(
+                        render.pipeline.clone(),
+                    ))
                 }
-                winit::event::VirtualKeyCode::Space => {
-                    self.input.7 = is_pressed(state);
-                    true
+                Some(pipeline) if !pipeline.accepts(render) => {
+                    return Err(crate::scene::SceneError::IncompatiblePipeline {
+                        pipeline: render.pipeline.clone(),
+                        entity: name
+                            .map(|name| format!("\"{}\"", name.0))
+                            .unwrap_or_else(|| format!("entity {}", entity.id())),
+                    })
                 }
-                _ => false,
-            },
-            _ => false,
+                Some(_) => {}
+            }
+            if !materials.contains_key(&render.material) {
+                return Err(crate::scene::SceneError::UnknownMaterial(
+                    render.material.clone(),
+                ));
+            }
         }
+
+        self.scene = scene;
+        self.materials = materials;
+        self.camera = camera;
+        self.picking.select(None);
+        self.picking.retain(&self.scene);
+        self.editor.gizmo.release();
+
+        let paths = self
+            .scene
+            .query_mut::<&crate::prefab::PrefabInstance>()
+            .into_iter()
+            .map(|(_, instance)| instance.prefab.clone())
+            .collect::<std::collections::HashSet<_>>();
+        paths
+            .iter()
+            .for_each(|path| self.add_prefab_materials(path, false));
+        self.update_prefab_materials();
+
+        Ok(())
+    }
+
+    // The root of the new instance is placed in front of the camera
+    fn instantiate_prefab(&mut self, path: &str) -> Result<hecs::Entity, crate::scene::SceneError> {
+        let position = {
+            let transform = self
+                .scene
+                .query_one_mut::<&crate::component::GlobalTransform>(self.camera)
+                .unwrap();
+
+            transform.position() + transform.forward() * 5.0
+        };
+
+        let spawned = self.prefabs.instantiate(
+            path,
+            &mut self.scene,
+            &self.device,
+            &self.window,
+            &self.skin_bind_group_layout,
+            crate::prefab::Overrides {
+                position: Some(position.into()),
+                ..Default::default()
+            },
+        )?;
+
+        self.add_prefab_materials(path, false);
+        self.update_prefab_materials();
+
+        spawned
+            .entities
+            .first()
+            .copied()
+            .ok_or_else(|| crate::scene::SceneError::Prefab("the prefab is empty".to_owned()))
+    }
+
+    // Materials are shared by name, `overwrite` makes the prefab colors win
+    fn add_prefab_materials(&mut self, path: &str, overwrite: bool) {
+        let materials = match self.prefabs.get(path) {
+            Some(prefab) => prefab.materials.clone(),
+            None => return,
+        };
+
+        materials
+            .into_iter()
+            .for_each(|(name, color)| match self.materials.get_mut(&name) {
+                Some(material) => {
+                    if overwrite && material.uniform.color != color {
+                        material.set_color(color, &self.queue);
+                    }
+                }
+                None => {
+                    self.materials.insert(
+                        name,
+                        crate::material::Material::new(
+                            self.device.as_ref(),
+                            &self.material_bind_group_layout,
+                            color,
+                        ),
+                    );
+                }
+            });
+    }
+
+    // Writes the world matrix of every transform changed by the last propagation
+    // into its buffer
+    fn upload_transforms(&mut self) {
+        self.scene
+            .query_mut::<(
+                &crate::component::Transform,
+                &mut crate::component::GlobalTransform,
+            )>()
+            .into_iter()
+            .filter(|(_, (_, global))| global.changed)
+            .for_each(|(_, (transform, global))| {
+                if let Some(buffer) = transform.buffer.as_ref() {
+                    self.queue
+                        .write_buffer(buffer, 0, bytemuck::cast_slice(&[global.to_raw()]));
+                }
+                global.changed = false;
+            });
+    }
+
+    fn update_prefab_materials(&mut self) {
+        let overrides = self.prefabs.color_overrides(&mut self.scene);
+
+        // Materials of despawned instances and of reverted colors
+        self.materials.retain(|name, _| {
+            !crate::prefab::is_color_material(name)
+                || overrides.iter().any(|(material, _)| material == name)
+        });
+
+        overrides
+            .into_iter()
+            .for_each(|(name, color)| match self.materials.get_mut(&name) {
+                Some(material) => {
+                    if material.uniform.color != color {
+                        material.set_color(color, &self.queue);
+                    }
+                }
+                None => {
+                    self.materials.insert(
+                        name,
+                        crate::material::Material::new(
+                            self.device.as_ref(),
+                            &self.material_bind_group_layout,
+                            color,
+                        ),
+                    );
+                }
+            });
     }
 
     pub fn resize(&mut self, new_size: winit::dpi::PhysicalSize<u32>) {
@@ -771,7 +1690,372 @@ impl Engine {
                 &self.config,
                 "depth_texture",
             );
+            self.picking.resize(self.device.as_ref(), &self.config);
             println!("New window size: {:?}", self.window.inner_size());
         }
     }
 }
+
+// Entities of the scene the engine starts with that the GPU parts are added to
+pub(crate) struct DefaultScene {
+    pub camera: hecs::Entity,
+    pub triangle: hecs::Entity,
+    pub marching_squares: hecs::Entity,
+    pub water: hecs::Entity,
+    pub grid: hecs::Entity,
+    pub monitor_camera: hecs::Entity,
+    pub monitor: hecs::Entity,
+    pub column: hecs::Entity,
+    pub joints: Vec<hecs::Entity>,
+}
+
+// Everything the game logic reads, so the scene can also be run without a GPU
+pub(crate) fn spawn_default_scene(scene: &mut hecs::World) -> DefaultScene {
+    let camera = scene.spawn((
+        crate::component::Name::new("Camera"),
+        crate::component::TransformBuild::new()
+            .with_position(nalgebra_glm::vec3(0.0, 4.0, -6.0))
+            .build(),
+        crate::component::CameraController::new(crate::component::ControllerMode::Fly),
+    ));
+
+    let triangle = scene.spawn((
+        crate::component::Name::new("Triangle"),
+        crate::component::TransformBuild::new()
+            .with_position(nalgebra_glm::vec3(0.0, 0.0, 4.0))
+            .build(),
+    ));
+    crate::hierarchy::set_parent(scene, triangle, Some(camera)).unwrap();
+
+    let mut quad = crate::component::TransformBuild::new()
+        .with_position(nalgebra_glm::vec3(3.5, 3.5, 11.0))
+        .with_rotation(nalgebra_glm::vec3(0.0, -90.0, 0.0))
+        .build();
+    quad.add_rotation_global_x(-90.0);
+    let marching_squares = scene.spawn((crate::component::Name::new("Marching Squares"), quad));
+
+    let water = scene.spawn((
+        crate::component::Name::new("Water"),
+        crate::component::TransformBuild::new()
+            .with_position(nalgebra_glm::vec3(0.0, 0.5, 8.0))
+            .build(),
+    ));
+
+    let grid = scene.spawn((
+        crate::component::Name::new("Grid"),
+        crate::component::TransformBuild::new().build(),
+    ));
+
+    scene.spawn((
+        crate::component::Name::new("Sun"),
+        crate::component::TransformBuild::new()
+            .with_position(nalgebra_glm::vec3(0.0, 10.0, 0.0))
+            .with_rotation(nalgebra_glm::vec3(-45.0, 30.0, 0.0))
+            .build(),
+        crate::component::Light::new(
+            crate::component::LightType::Directional,
+            [1.0, 1.0, 1.0],
+            1.0,
+        ),
+    ));
+
+    // Second camera rendering into the "Monitor" target, shown on a quad
+    let mut monitor_camera_transform = crate::component::TransformBuild::new()
+        .with_position(nalgebra_glm::vec3(0.0, 12.0, 0.0))
+        .build();
+    monitor_camera_transform.look_at(&nalgebra_glm::vec3(0.0, 0.0, 8.0), &nalgebra_glm::Vec3::y());
+    let monitor_camera = scene.spawn((
+        crate::component::Name::new("Monitor Camera"),
+        monitor_camera_transform,
+    ));
+
+    let monitor = scene.spawn((
+        crate::component::Name::new("Monitor"),
+        crate::component::TransformBuild::new()
+            .with_position(nalgebra_glm::vec3(6.0, 4.0, 16.0))
+            .build(),
+    ));
+
+    // Skinned column swaying on three joints
+    let column = scene.spawn((
+        crate::component::Name::new("Column"),
+        crate::component::TransformBuild::new()
+            .with_position(nalgebra_glm::vec3(-4.0, 0.0, 8.0))
+            .build(),
+    ));
+
+    let joints = (0..3)
+        .scan(column, |parent, i| {
+            let joint = scene.spawn((
+                crate::component::Name(format!("Joint {}", i)),
+                crate::component::TransformBuild::new()
+                    .with_position(nalgebra_glm::vec3(0.0, if i == 0 { 0.0 } else { 1.0 }, 0.0))
+                    .build(),
+            ));
+            crate::hierarchy::set_parent(scene, joint, Some(*parent)).unwrap();
+            *parent = joint;
+
+            Some(joint)
+        })
+        .collect::<Vec<_>>();
+
+    // The joints are still in their bind pose after the propagation, the players only move
+    // them from the first update on
+    let sway = joints.iter().enumerate().fold(
+        crate::skinning::SkeletalClip::new("Sway"),
+        |clip, (i, _)| {
+            let angle = |degrees: f32| {
+                nalgebra_glm::quat_angle_axis(degrees.to_radians(), &nalgebra_glm::Vec3::z())
+            };
+
+            clip.with_joint(
+                &format!("Joint {}", i),
+                crate::animation::AnimationClip::new("Sway").with_rotation(
+                    crate::animation::Track::new(crate::animation::Interpolation::Cubic)
+                        .with_keyframe(0.0, angle(-15.0))
+                        .with_keyframe(1.0, angle(15.0))
+                        .with_keyframe(2.0, angle(-15.0)),
+                ),
+            )
+        },
+    );
+    sway.play(scene, column, 0.0);
+
+    crate::hierarchy::propagate(scene);
+
+    DefaultScene {
+        camera,
+        triangle,
+        marching_squares,
+        water,
+        grid,
+        monitor_camera,
+        monitor,
+        column,
+        joints,
+    }
+}
+
+fn default_input() -> crate::component::input::Input {
+    use crate::component::input::{AxisBinding, Button};
+    use winit::event::{MouseButton, VirtualKeyCode};
+
+    let keys = |negative, positive| AxisBinding::Buttons {
+        negative: Button::Key(negative),
+        positive: Button::Key(positive),
+    };
+    let pad = |negative, positive| AxisBinding::Buttons {
+        negative: Button::Gamepad(negative),
+        positive: Button::Gamepad(positive),
+    };
+
+    crate::component::input::Input::new()
+        .with_axis("Move X", keys(VirtualKeyCode::A, VirtualKeyCode::D), 1.0)
+        .with_axis(
+            "Move Y",
+            keys(VirtualKeyCode::LControl, VirtualKeyCode::Space),
+            1.0,
+        )
+        .with_axis("Move Z", keys(VirtualKeyCode::S, VirtualKeyCode::W), 1.0)
+        .with_axis("Roll", keys(VirtualKeyCode::Q, VirtualKeyCode::E), 1.0)
+        .with_axis("Look X", AxisBinding::MouseX, 1.0)
+        .with_axis("Look Y", AxisBinding::MouseY, 1.0)
+        .with_axis("Zoom", AxisBinding::Scroll, 1.0)
+        .with_axis(
+            "Move X",
+            AxisBinding::GamepadAxis(gilrs::Axis::LeftStickX),
+            1.0,
+        )
+        .with_axis(
+            "Move Y",
+            AxisBinding::GamepadTrigger(gilrs::Button::LeftTrigger2),
+            -1.0,
+        )
+        .with_axis(
+            "Move Y",
+            AxisBinding::GamepadTrigger(gilrs::Button::RightTrigger2),
+            1.0,
+        )
+        .with_axis(
+            "Move Z",
+            AxisBinding::GamepadAxis(gilrs::Axis::LeftStickY),
+            1.0,
+        )
+        .with_axis(
+            "Roll",
+            pad(gilrs::Button::LeftTrigger, gilrs::Button::RightTrigger),
+            1.0,
+        )
+        // Pixels per second at full tilt, the mouse moves down where the stick goes up
+        .with_axis(
+            "Turn X",
+            AxisBinding::GamepadAxis(gilrs::Axis::RightStickX),
+            300.0,
+        )
+        .with_axis(
+            "Turn Y",
+            AxisBinding::GamepadAxis(gilrs::Axis::RightStickY),
+            -300.0,
+        )
+        .with_axis(
+            "Zoom",
+            pad(gilrs::Button::DPadDown, gilrs::Button::DPadUp),
+            0.1,
+        )
+        .with_action(
+            "Toggle Editor",
+            &[
+                Button::Key(VirtualKeyCode::F1),
+                Button::Gamepad(gilrs::Button::Select),
+            ],
+        )
+        .with_action("Select", &[Button::Mouse(MouseButton::Left)])
+        .with_action("Grab Cursor", &[Button::Key(VirtualKeyCode::Tab)])
+        .with_action("Record", &[Button::Key(VirtualKeyCode::F9)])
+        .with_action("Toggle Console", &[Button::Key(VirtualKeyCode::Grave)])
+        .with_action("Toggle Stats", &[Button::Key(VirtualKeyCode::F3)])
+}
+
+fn default_console() -> crate::console::Console {
+    let mut console = crate::console::Console::new();
+
+    let primitives = crate::editor::Primitive::ALL
+        .iter()
+        .map(|primitive| format!("{:?}", primitive).to_lowercase())
+        .collect::<Vec<_>>();
+    console.add_command(
+        "spawn",
+        "spawn <primitive>, in front of the camera",
+        &primitives.iter().map(String::as_str).collect::<Vec<_>>(),
+        |args, context| {
+            let primitive = args
+                .first()
+                .and_then(|name| {
+                    crate::editor::Primitive::ALL
+                        .into_iter()
+                        .find(|primitive| format!("{:?}", primitive).eq_ignore_ascii_case(name))
+                })
+                .ok_or_else(|| "usage: spawn <primitive>".to_owned())?;
+
+            context
+                .editor
+                .push_action(crate::editor::EditorAction::Spawn(primitive));
+            Ok(String::new())
+        },
+    );
+
+    console.add_command(
+        "tp",
+        "tp <x> <y> <z>, moves the camera",
+        &[],
+        |args, context| {
+            let position = match args
+                .iter()
+                .map(|arg| arg.parse::<f32>())
+                .collect::<Result<Vec<_>, _>>()
+                .as_deref()
+            {
+                Ok([x, y, z]) => nalgebra_glm::vec3(*x, *y, *z),
+                _ => return Err("usage: tp <x> <y> <z>".to_owned()),
+            };
+
+            context
+                .world
+                .query_one_mut::<&mut crate::component::Transform>(context.camera)
+                .map_err(|_| "the camera has no transform".to_owned())?
+                .set_position(&position);
+            Ok(String::new())
+        },
+    );
+
+    // Read by the renderer every frame
+    console.add_cvar("wireframe", false, "draws the edges of the meshes");
+
+    // Read by the engine every frame, see `crate::engine::Engine::set_picking_mode`
+    console.add_cvar(
+        "picking.mode",
+        crate::picking::PickingMode::Mesh.name(),
+        "bounds, mesh or exact, exact reads the entity under the cursor back from the GPU",
+    );
+
+    console.add_cvar(
+        "camera.fov",
+        45f32,
+        "vertical field of view of the camera in degrees",
+    );
+    console.on_change("camera.fov", |value, context| {
+        if let Ok(camera) = context
+            .world
+            .query_one_mut::<&mut crate::component::Camera>(context.camera)
+        {
+            camera.fov = value.as_float();
+        }
+    });
+
+    console
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_scene_can_be_recorded() {
+        let directory = std::env::temp_dir();
+        let name = format!("engine_{}", std::process::id());
+        let scene = directory.join(format!("{}.scene.ron", name));
+        let scene = scene.to_str().unwrap().to_owned();
+        let path = directory.join(format!("{}.ron", name));
+        let path = path.to_str().unwrap().to_owned();
+
+        let mut world = hecs::World::new();
+        spawn_default_scene(&mut world);
+
+        // Cameras need a window, the engine saves the one it looks through with its type
+        let mut file = crate::scene::SceneFile::from_world(&world, &HashMap::new());
+        file.entities
+            .iter_mut()
+            .filter(|data| data.name.as_deref() == Some("Camera"))
+            .for_each(|data| data.camera = Some(crate::component::CameraType::Perspective));
+        file.save(&scene).unwrap();
+        let verified = crate::replay::verify_scene(&world, &scene);
+
+        // Walks forward while the column sways
+        let mut input = default_input();
+        let mut recorder = crate::replay::Recorder::new(
+            &path,
+            1.0 / 60.0,
+            input.bindings().clone(),
+            Some(scene.clone()),
+        );
+        input.start_recording();
+        (0..120).for_each(|frame| {
+            match frame {
+                0 => input.press(crate::component::input::Button::Key(
+                    winit::event::VirtualKeyCode::W,
+                )),
+                60 => input.release(crate::component::input::Button::Key(
+                    winit::event::VirtualKeyCode::W,
+                )),
+                _ => {}
+            }
+
+            crate::simulation::step(&mut world, &input, 1.0 / 60.0);
+            recorder.record_frame(input.take_recorded().unwrap_or_default(), &world);
+            input.end_frame();
+        });
+        let recording = recorder.finish(&world);
+        recording.save(&path).unwrap();
+        let replayed = crate::replay::verify_recording(&path);
+
+        std::fs::remove_file(&scene).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        verified.unwrap();
+        assert_eq!(replayed.unwrap(), 120);
+        assert!(recording
+            .snapshots
+            .iter()
+            .all(|snapshot| snapshot.transforms.contains_key("Camera")));
+    }
+}
@@ -0,0 +1,351 @@
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GizmoMode {
+    Translate,
+    Rotate,
+    Scale,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GizmoSpace {
+    Local,
+    World,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct DragStart {
+    center: nalgebra_glm::Vec3,
+    size: f32,
+    position: nalgebra_glm::Vec3,
+    scale: nalgebra_glm::Vec3,
+    // Distance along the axis for translate and scale, angle in degrees for rotate
+    value: f32,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Drag {
+    axis: usize,
+    start: Option<DragStart>,
+    applied_rotation: f32,
+}
+
+pub struct Gizmo {
+    pub mode: GizmoMode,
+    pub space: GizmoSpace,
+    pub snap: bool,
+    pub translate_snap: f32,
+    pub rotate_snap: f32,
+    pub scale_snap: f32,
+    hovered: Option<usize>,
+    drag: Option<Drag>,
+}
+
+const AXIS_COLORS: [[f32; 3]; 3] = [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]];
+const ACTIVE_COLOR: [f32; 3] = [1.0, 1.0, 0.0];
+
+impl Default for Gizmo {
+    fn default() -> Self {
+        Self {
+            mode: GizmoMode::Translate,
+            space: GizmoSpace::World,
+            snap: false,
+            translate_snap: 0.5,
+            rotate_snap: 15.0,
+            scale_snap: 0.1,
+            hovered: None,
+            drag: None,
+        }
+    }
+}
+
+impl Gizmo {
+    // Returns true when a handle was grabbed, the click must not reach picking then
+    pub fn press(&mut self) -> bool {
+        match self.hovered {
+            Some(axis) => {
+                self.drag = Some(Drag {
+                    axis,
+                    start: None,
+                    applied_rotation: 0.0,
+                });
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn release(&mut self) {
+        self.drag = None;
+    }
+
+    pub fn update(
+        &mut self,
+        world: &mut hecs::World,
+        entity: Option<hecs::Entity>,
+        ray: Option<crate::math::Ray>,
+        camera_position: &nalgebra_glm::Vec3,
+        debug_draw: &mut crate::debug_draw::DebugDraw,
+    ) {
+        let (transform, global) = match entity.and_then(|entity| {
+            world
+                .query_one_mut::<(
+                    &mut crate::component::Transform,
+                    &crate::component::GlobalTransform,
+                )>(entity)
+                .ok()
+        }) {
+            Some(components) => components,
+            None => {
+                self.hovered = None;
+                self.drag = None;
+                return;
+            }
+        };
+
+        let world_matrix = global.matrix;
+        let parent_matrix = world_matrix * nalgebra_glm::inverse(&transform.get_matrix());
+        let center = (world_matrix * nalgebra_glm::vec4(0.0, 0.0, 0.0, 1.0)).xyz();
+        let size = nalgebra_glm::distance(&center, camera_position) * 0.15;
+
+        // Scaling is always done along the local axes
+        let axes = match (self.space, self.mode) {
+            (GizmoSpace::World, GizmoMode::Translate | GizmoMode::Rotate) => [
+                nalgebra_glm::Vec3::x(),
+                nalgebra_glm::Vec3::y(),
+                nalgebra_glm::Vec3::z(),
+            ],
+            _ => [0, 1, 2]
+                .map(|i| nalgebra_glm::normalize(&nalgebra_glm::column(&world_matrix, i).xyz())),
+        };
+
+        if let Some(ray) = ray.as_ref() {
+            match self.drag.as_mut() {
+                Some(drag) => {
+                    Self::drag(
+                        drag,
+                        self.mode,
+                        self.snap,
+                        [self.translate_snap, self.rotate_snap, self.scale_snap],
+                        transform,
+                        &parent_matrix,
+                        &center,
+                        &axes,
+                        size,
+                        ray,
+                    );
+                }
+                None => {
+                    self.hovered = Self::hit(self.mode, &center, &axes, size, ray);
+                }
+            }
+        }
+
+        let active = self.drag.map(|drag| drag.axis).or(self.hovered);
+
+        debug_draw.set_depth_test(false);
+        axes.iter().enumerate().for_each(|(i, axis)| {
+            let color = if active == Some(i) {
+                ACTIVE_COLOR
+            } else {
+                AXIS_COLORS[i]
+            };
+
+            match self.mode {
+                GizmoMode::Translate => {
+                    debug_draw.arrow(&center, &(center + axis * size), color);
+                }
+                GizmoMode::Rotate => {
+                    debug_draw.circle(&center, axis, size, color);
+                }
+                GizmoMode::Scale => {
+                    let end = center + axis * size;
+                    let half = size * 0.05;
+                    debug_draw.line(&center, &end, color);
+                    debug_draw.aabb(
+                        &crate::math::Aabb {
+                            min: nalgebra_glm::vec3(-half, -half, -half),
+                            max: nalgebra_glm::vec3(half, half, half),
+                        },
+                        &nalgebra_glm::translate(&nalgebra_glm::Mat4::identity(), &end),
+                        color,
+                    );
+                }
+            }
+        });
+        debug_draw.set_depth_test(true);
+    }
+
+    fn hit(
+        mode: GizmoMode,
+        center: &nalgebra_glm::Vec3,
+        axes: &[nalgebra_glm::Vec3; 3],
+        size: f32,
+        ray: &crate::math::Ray,
+    ) -> Option<usize> {
+        let tolerance = size * 0.08;
+
+        axes.iter()
+            .enumerate()
+            .filter_map(|(i, axis)| match mode {
+                GizmoMode::Translate | GizmoMode::Scale => {
+                    let (ray_t, axis_t, distance) = closest_to_axis(ray, center, axis)?;
+                    (ray_t >= 0.0 && (0.0..=size).contains(&axis_t) && distance < tolerance)
+                        .then_some((i, ray_t))
+                }
+                GizmoMode::Rotate => {
+                    let ray_t = intersect_plane(ray, center, axis)?;
+                    let distance = nalgebra_glm::distance(&ray.at(ray_t), center);
+                    ((distance - size).abs() < tolerance).then_some((i, ray_t))
+                }
+            })
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(i, _)| i)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn drag(
+        drag: &mut Drag,
+        mode: GizmoMode,
+        snap: bool,
+        snaps: [f32; 3],
+        transform: &mut crate::component::Transform,
+        parent_matrix: &nalgebra_glm::Mat4,
+        center: &nalgebra_glm::Vec3,
+        axes: &[nalgebra_glm::Vec3; 3],
+        size: f32,
+        ray: &crate::math::Ray,
+    ) {
+        let axis = axes[drag.axis];
+
+        // Measure against where the drag started, the entity moves while dragging
+        let (center, size) = drag
+            .start
+            .map(|start| (start.center, start.size))
+            .unwrap_or((*center, size));
+        let center = &center;
+
+        let value = match mode {
+            GizmoMode::Translate | GizmoMode::Scale => match closest_to_axis(ray, center, &axis) {
+                Some((_, axis_t, _)) => axis_t,
+                None => return,
+            },
+            GizmoMode::Rotate => match intersect_plane(ray, center, &axis) {
+                Some(ray_t) => {
+                    let direction = ray.at(ray_t) - center;
+                    let (u, v) = plane_basis(&axis);
+                    nalgebra_glm::dot(&direction, &v)
+                        .atan2(nalgebra_glm::dot(&direction, &u))
+                        .to_degrees()
+                }
+                None => return,
+            },
+        };
+
+        let start = *drag.start.get_or_insert(DragStart {
+            center: *center,
+            size,
+            position: transform.get_position(),
+            scale: transform.get_scale(),
+            value,
+        });
+
+        let snap_value = |value: f32, increment: f32| {
+            if snap && increment > 0.0 {
+                (value / increment).round() * increment
+            } else {
+                value
+            }
+        };
+
+        match mode {
+            GizmoMode::Translate => {
+                let distance = snap_value(value - start.value, snaps[0]);
+
+                // The position is relative to the parent
+                let parent_inverse = nalgebra_glm::inverse(parent_matrix);
+                let delta = (parent_inverse * (axis * distance).push(0.0)).xyz();
+
+                transform.set_position(&(start.position + delta));
+            }
+            GizmoMode::Rotate => {
+                let mut angle = value - start.value;
+                if angle > 180.0 {
+                    angle -= 360.0;
+                } else if angle < -180.0 {
+                    angle += 360.0;
+                }
+                let angle = snap_value(angle, snaps[1]);
+
+                // `add_rotation_global_axis` rotates around an axis in parent space
+                let parent_axis = nalgebra_glm::normalize(
+                    &(nalgebra_glm::inverse(parent_matrix) * axis.push(0.0)).xyz(),
+                );
+
+                let delta = angle - drag.applied_rotation;
+                if delta != 0.0 {
+                    transform.add_rotation_global_axis(delta, &parent_axis);
+                    drag.applied_rotation = angle;
+                }
+            }
+            GizmoMode::Scale => {
+                let factor = snap_value(1.0 + (value - start.value) / size, snaps[2]).max(0.01);
+
+                let mut scale = start.scale;
+                scale[drag.axis] *= factor;
+
+                transform.set_scale(&scale);
+            }
+        }
+    }
+}
+
+// Returns the distance along the ray, the distance along the axis and the gap between both
+fn closest_to_axis(
+    ray: &crate::math::Ray,
+    origin: &nalgebra_glm::Vec3,
+    axis: &nalgebra_glm::Vec3,
+) -> Option<(f32, f32, f32)> {
+    let w = ray.origin - origin;
+    let b = nalgebra_glm::dot(&ray.direction, axis);
+    let denominator = 1.0 - b * b;
+
+    if denominator.abs() < 1e-6 {
+        return None;
+    }
+
+    let d = nalgebra_glm::dot(&ray.direction, &w);
+    let e = nalgebra_glm::dot(axis, &w);
+
+    let ray_t = (b * e - d) / denominator;
+    let axis_t = (e - b * d) / denominator;
+    let distance = nalgebra_glm::distance(&ray.at(ray_t), &(origin + axis * axis_t));
+
+    Some((ray_t, axis_t, distance))
+}
+
+fn intersect_plane(
+    ray: &crate::math::Ray,
+    point: &nalgebra_glm::Vec3,
+    normal: &nalgebra_glm::Vec3,
+) -> Option<f32> {
+    let denominator = nalgebra_glm::dot(&ray.direction, normal);
+
+    if denominator.abs() < 1e-6 {
+        return None;
+    }
+
+    let t = nalgebra_glm::dot(&(point - ray.origin), normal) / denominator;
+
+    (t >= 0.0).then_some(t)
+}
+
+fn plane_basis(normal: &nalgebra_glm::Vec3) -> (nalgebra_glm::Vec3, nalgebra_glm::Vec3) {
+    let helper = if normal.x.abs() < 0.9 {
+        nalgebra_glm::Vec3::x()
+    } else {
+        nalgebra_glm::Vec3::y()
+    };
+    let u = nalgebra_glm::normalize(&nalgebra_glm::cross(normal, &helper));
+    let v = nalgebra_glm::cross(normal, &u);
+
+    (u, v)
+}
@@ -0,0 +1,324 @@
+use crate::component::{Children, GlobalTransform, Parent, Transform};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HierarchyError {
+    NoSuchEntity(hecs::Entity),
+    Cycle {
+        child: hecs::Entity,
+        parent: hecs::Entity,
+    },
+}
+
+impl std::fmt::Display for HierarchyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HierarchyError::NoSuchEntity(entity) => {
+                write!(f, "entity {} does not exist", entity.id())
+            }
+            HierarchyError::Cycle { child, parent } => write!(
+                f,
+                "entity {} can't be parented to its descendant {}",
+                child.id(),
+                parent.id()
+            ),
+        }
+    }
+}
+
+impl std::error::Error for HierarchyError {}
+
+pub fn parent(world: &hecs::World, entity: hecs::Entity) -> Option<hecs::Entity> {
+    world.get::<&Parent>(entity).ok().map(|parent| parent.0)
+}
+
+pub fn children(world: &hecs::World, entity: hecs::Entity) -> Vec<hecs::Entity> {
+    world
+        .get::<&Children>(entity)
+        .map(|children| children.0.clone())
+        .unwrap_or_default()
+}
+
+// Depth first, `entity` itself is not included
+pub fn descendants(world: &hecs::World, entity: hecs::Entity) -> Vec<hecs::Entity> {
+    let mut descendants = vec![];
+    let mut stack = children(world, entity);
+    stack.reverse();
+
+    while let Some(current) = stack.pop() {
+        descendants.push(current);
+
+        let mut children = children(world, current);
+        children.reverse();
+        stack.extend(children);
+    }
+
+    descendants
+}
+
+pub fn is_ancestor(world: &hecs::World, ancestor: hecs::Entity, entity: hecs::Entity) -> bool {
+    let mut current = parent(world, entity);
+
+    while let Some(entity) = current {
+        if entity == ancestor {
+            return true;
+        }
+        current = parent(world, entity);
+    }
+
+    false
+}
+
+// Keeps `Parent` and `Children` in sync, `None` turns the entity into a root
+pub fn set_parent(
+    world: &mut hecs::World,
+    child: hecs::Entity,
+    parent: Option<hecs::Entity>,
+) -> Result<(), HierarchyError> {
+    if !world.contains(child) {
+        return Err(HierarchyError::NoSuchEntity(child));
+    }
+
+    if let Some(parent) = parent {
+        if !world.contains(parent) {
+            return Err(HierarchyError::NoSuchEntity(parent));
+        }
+        if parent == child || is_ancestor(world, child, parent) {
+            return Err(HierarchyError::Cycle { child, parent });
+        }
+    }
+
+    if let Ok(old) = world.remove_one::<Parent>(child) {
+        if let Ok(mut children) = world.get::<&mut Children>(old.0) {
+            children.0.retain(|entity| *entity != child);
+        }
+    }
+
+    if let Some(parent) = parent {
+        world.insert_one(child, Parent(parent)).unwrap();
+
+        let added = match world.get::<&mut Children>(parent) {
+            Ok(mut children) => {
+                children.0.push(child);
+                true
+            }
+            Err(_) => false,
+        };
+        if !added {
+            world.insert_one(parent, Children(vec![child])).unwrap();
+        }
+    }
+
+    if let Ok(transform) = world.query_one_mut::<&mut Transform>(child) {
+        transform.mark_changed();
+    }
+
+    Ok(())
+}
+
+// Despawns the entity together with all of its descendants
+pub fn despawn_recursive(
+    world: &mut hecs::World,
+    entity: hecs::Entity,
+) -> Result<Vec<hecs::Entity>, HierarchyError> {
+    if !world.contains(entity) {
+        return Err(HierarchyError::NoSuchEntity(entity));
+    }
+
+    set_parent(world, entity, None)?;
+
+    let despawned = std::iter::once(entity)
+        .chain(descendants(world, entity))
+        .collect::<Vec<_>>();
+
+    despawned.iter().for_each(|entity| {
+        world.despawn(*entity).unwrap_or_default();
+    });
+
+    Ok(despawned)
+}
+
+// Updates `GlobalTransform` from the roots down, only the subtrees below a changed
+// `Transform` are recomputed
+pub fn propagate(world: &mut hecs::World) {
+    let missing = world
+        .query_mut::<()>()
+        .with::<&Transform>()
+        .without::<&GlobalTransform>()
+        .into_iter()
+        .map(|(entity, _)| entity)
+        .collect::<Vec<_>>();
+
+    missing.into_iter().for_each(|entity| {
+        world
+            .insert_one(entity, GlobalTransform::default())
+            .unwrap();
+        if let Ok(transform) = world.query_one_mut::<&mut Transform>(entity) {
+            transform.mark_changed();
+        }
+    });
+
+    let changed = world
+        .query_mut::<&Transform>()
+        .into_iter()
+        .filter(|(_, transform)| transform.is_changed())
+        .map(|(entity, _)| entity)
+        .collect::<Vec<_>>();
+
+    // Clean entities with a changed descendant, every other clean subtree is skipped
+    let mut dirty_ancestors = std::collections::HashSet::new();
+    changed.iter().for_each(|entity| {
+        let mut current = parent(world, *entity);
+        while let Some(ancestor) = current {
+            if !dirty_ancestors.insert(ancestor) {
+                break;
+            }
+            current = parent(world, ancestor);
+        }
+    });
+
+    // Entities whose parent has no transform are treated as roots
+    let is_root = |entity: &hecs::Entity| {
+        world.satisfies::<&Transform>(*entity).unwrap_or_default()
+            && parent(world, *entity)
+                .is_none_or(|parent| !world.satisfies::<&Transform>(parent).unwrap_or_default())
+    };
+    let mut roots = changed
+        .iter()
+        .chain(dirty_ancestors.iter())
+        .copied()
+        .filter(is_root)
+        .collect::<Vec<_>>();
+    roots.sort();
+    roots.dedup();
+
+    // An explicit stack, deep chains like ropes or generated skeletons would overflow the
+    // call stack
+    let mut stack = roots
+        .into_iter()
+        .map(|root| (root, nalgebra_glm::Mat4::identity(), false))
+        .collect::<Vec<_>>();
+
+    while let Some((entity, parent_matrix, parent_changed)) = stack.pop() {
+        let (matrix, changed) =
+            match world.query_one_mut::<(&mut Transform, &mut GlobalTransform)>(entity) {
+                Ok((transform, global)) => {
+                    let changed = parent_changed || transform.is_changed();
+
+                    if changed {
+                        global.matrix = parent_matrix * transform.get_matrix();
+                        global.changed = true;
+                        transform.clear_changed();
+                    }
+
+                    (global.matrix, changed)
+                }
+                Err(_) => continue,
+            };
+
+        if !changed && !dirty_ancestors.contains(&entity) {
+            continue;
+        }
+
+        if let Ok(children) = world.get::<&Children>(entity) {
+            stack.extend(
+                children
+                    .0
+                    .iter()
+                    .rev()
+                    .map(|child| (*child, matrix, changed)),
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spawn(world: &mut hecs::World, x: f32) -> hecs::Entity {
+        world.spawn((crate::component::TransformBuild::new()
+            .with_position(nalgebra_glm::vec3(x, 0.0, 0.0))
+            .build(),))
+    }
+
+    #[test]
+    fn propagate_composes_parent_matrices() {
+        let mut world = hecs::World::new();
+        let parent = spawn(&mut world, 1.0);
+        let child = spawn(&mut world, 2.0);
+        set_parent(&mut world, child, Some(parent)).unwrap();
+
+        propagate(&mut world);
+
+        let global = *world.get::<&GlobalTransform>(child).unwrap();
+        assert!((global.position() - nalgebra_glm::vec3(3.0, 0.0, 0.0)).norm() < 1e-5);
+    }
+
+    #[test]
+    fn propagate_handles_deep_chains() {
+        let mut world = hecs::World::new();
+        let root = spawn(&mut world, 0.0);
+        // `set_parent` walks up the chain to look for cycles, so it's built directly
+        world.insert_one(root, Children(vec![])).unwrap();
+        let last = (0..50_000).fold(root, |parent, _| {
+            let child = world.spawn((
+                crate::component::TransformBuild::new()
+                    .with_position(nalgebra_glm::vec3(1.0, 0.0, 0.0))
+                    .build(),
+                Parent(parent),
+                Children(vec![]),
+            ));
+            world.get::<&mut Children>(parent).unwrap().0.push(child);
+            child
+        });
+
+        propagate(&mut world);
+
+        let global = *world.get::<&GlobalTransform>(last).unwrap();
+        assert!((global.position().x - 50_000.0).abs() < 1.0);
+    }
+
+    #[test]
+    fn propagate_skips_clean_subtrees() {
+        let mut world = hecs::World::new();
+        let root = spawn(&mut world, 1.0);
+        let moved = spawn(&mut world, 0.0);
+        let still = spawn(&mut world, 0.0);
+        set_parent(&mut world, moved, Some(root)).unwrap();
+        set_parent(&mut world, still, Some(root)).unwrap();
+        propagate(&mut world);
+
+        world
+            .query_mut::<&mut GlobalTransform>()
+            .into_iter()
+            .for_each(|(_, global)| global.changed = false);
+        world
+            .get::<&mut Transform>(moved)
+            .unwrap()
+            .set_position(&nalgebra_glm::vec3(2.0, 0.0, 0.0));
+
+        propagate(&mut world);
+
+        assert!(!world.get::<&GlobalTransform>(root).unwrap().changed);
+        assert!(!world.get::<&GlobalTransform>(still).unwrap().changed);
+        let global = *world.get::<&GlobalTransform>(moved).unwrap();
+        assert!(global.changed);
+        assert!((global.position() - nalgebra_glm::vec3(3.0, 0.0, 0.0)).norm() < 1e-5);
+    }
+
+    #[test]
+    fn set_parent_rejects_cycles() {
+        let mut world = hecs::World::new();
+        let parent = spawn(&mut world, 0.0);
+        let child = spawn(&mut world, 0.0);
+        set_parent(&mut world, child, Some(parent)).unwrap();
+
+        assert_eq!(
+            set_parent(&mut world, parent, Some(child)),
+            Err(HierarchyError::Cycle {
+                child: parent,
+                parent: child
+            })
+        );
+    }
+}
@@ -1,12 +1,52 @@
+mod animation;
 mod component;
+mod console;
+mod debug_draw;
+mod editor;
 mod engine;
+mod gizmo;
+mod hierarchy;
+mod material;
+mod math;
+mod picking;
+mod pipeline;
+mod prefab;
+mod profiler;
+mod render_queue;
+mod replay;
+mod scene;
 mod shapes;
+mod simulation;
+mod skinning;
 mod texture;
+mod ui;
 mod vertex_type;
 
+pub use animation::{Animatable, AnimationClip, Easing, Interpolation, Keyframe, Pose, Track};
+pub use component::input::Input;
+pub use component::{
+    AnimationPlayer, Camera, CameraController, CameraInput, CameraOutput, CameraTarget, CameraType,
+    Children, ControllerMode, FrameTime, GlobalTransform, Light, LightType, Name, Parent, Render,
+    Skin, Transform, TransformBuild, Viewport,
+};
+pub use console::{CVarValue, Console, ConsoleContext};
+pub use debug_draw::DebugDraw;
+pub use engine::Engine;
+pub use math::Aabb;
+pub use picking::{PickingEvent, PickingMode};
+pub use replay::{verify_recording, ReplayError};
+pub use skinning::SkeletalClip;
+pub use ui::UiContext;
+pub use vertex_type::VertexType;
+
 pub async fn run() {
+    run_with(|_| {}).await;
+}
+
+// `setup` can add UI callbacks before the first frame
+pub async fn run_with(setup: impl FnOnce(&mut crate::engine::Engine)) {
     // Logs
-    env_logger::init_from_env(
+    crate::console::init_logger(
         env_logger::Env::default()
             .filter_or("MY_LOG_LEVEL", "warn")
             .write_style_or("MY_LOG_STYLE", "always"),
@@ -24,6 +64,7 @@ pub async fn run() {
     );
 
     let mut engine = crate::engine::Engine::new(window.clone(), &event_loop).await;
+    setup(&mut engine);
 
     event_loop.run(move |event, _, control_flow| match event {
         winit::event::Event::WindowEvent {
@@ -55,10 +96,7 @@ pub async fn run() {
                 _ => {}
             }
         }
-        winit::event::Event::DeviceEvent {
-            event: winit::event::DeviceEvent::MouseMotion { delta },
-            ..
-        } => engine.mouse_delta = (delta.0 as f32, delta.1 as f32),
+        winit::event::Event::DeviceEvent { ref event, .. } => engine.device_input(event),
         winit::event::Event::RedrawRequested(window_id) if window_id == window.id() => {
             engine.update();
 
@@ -1,3 +1,20 @@
 fn main() {
+    // `--replay <recording>` checks a recording without opening a window
+    let args = std::env::args().collect::<Vec<_>>();
+    if let Some(path) = args
+        .iter()
+        .position(|arg| arg == "--replay")
+        .and_then(|index| args.get(index + 1))
+    {
+        match idkwiad::verify_recording(path) {
+            Ok(frames) => println!("{}: {} frames replayed", path, frames),
+            Err(e) => {
+                eprintln!("{}: {}", path, e);
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+
     pollster::block_on(idkwiad::run());
 }
@@ -0,0 +1,428 @@
+#[derive(Debug, Clone, Copy)]
+pub struct Aabb {
+    pub min: nalgebra_glm::Vec3,
+    pub max: nalgebra_glm::Vec3,
+}
+
+impl Aabb {
+    pub fn from_points<'a>(points: impl Iterator<Item = &'a nalgebra_glm::Vec3>) -> Self {
+        let mut min = nalgebra_glm::vec3(f32::MAX, f32::MAX, f32::MAX);
+        let mut max = nalgebra_glm::vec3(f32::MIN, f32::MIN, f32::MIN);
+
+        points.for_each(|point| {
+            min = nalgebra_glm::min2(&min, point);
+            max = nalgebra_glm::max2(&max, point);
+        });
+
+        if min.x > max.x {
+            return Self {
+                min: nalgebra_glm::zero(),
+                max: nalgebra_glm::zero(),
+            };
+        }
+
+        Self { min, max }
+    }
+
+    pub fn center(&self) -> nalgebra_glm::Vec3 {
+        (self.min + self.max) * 0.5
+    }
+
+    pub fn corners(&self) -> [nalgebra_glm::Vec3; 8] {
+        [0, 1, 2, 3, 4, 5, 6, 7].map(|i| {
+            nalgebra_glm::vec3(
+                if i & 1 == 0 { self.min.x } else { self.max.x },
+                if i & 2 == 0 { self.min.y } else { self.max.y },
+                if i & 4 == 0 { self.min.z } else { self.max.z },
+            )
+        })
+    }
+
+    // Box around the transformed corners
+    pub fn transform(&self, matrix: &nalgebra_glm::Mat4) -> Self {
+        let corners = self
+            .corners()
+            .map(|corner| (matrix * corner.push(1.0)).xyz());
+
+        Self::from_points(corners.iter())
+    }
+}
+
+// Points with `dot(normal, point) + distance >= 0` are in front of the plane
+#[derive(Debug, Clone, Copy)]
+pub struct Plane {
+    pub normal: nalgebra_glm::Vec3,
+    pub distance: f32,
+}
+
+impl Plane {
+    // From the `a, b, c, d` coefficients of `ax + by + cz + d = 0`
+    pub fn from_coefficients(coefficients: &nalgebra_glm::Vec4) -> Self {
+        let length = nalgebra_glm::length(&coefficients.xyz()).max(f32::EPSILON);
+
+        Self {
+            normal: coefficients.xyz() / length,
+            distance: coefficients.w / length,
+        }
+    }
+
+    pub fn signed_distance(&self, point: &nalgebra_glm::Vec3) -> f32 {
+        nalgebra_glm::dot(&self.normal, point) + self.distance
+    }
+}
+
+// Planes face inwards, in the order left, right, bottom, top, near, far
+#[derive(Debug, Clone, Copy)]
+pub struct Frustum {
+    pub planes: [Plane; 6],
+}
+
+impl Frustum {
+    // `matrix` maps to clip space with the depth in [0, 1], as the shaders output it
+    pub fn from_matrix(matrix: &nalgebra_glm::Mat4) -> Self {
+        let row = |i: usize| nalgebra_glm::row(matrix, i);
+
+        Self {
+            planes: [
+                row(3) + row(0),
+                row(3) - row(0),
+                row(3) + row(1),
+                row(3) - row(1),
+                row(2),
+                row(3) - row(2),
+            ]
+            .map(|coefficients| Plane::from_coefficients(&coefficients)),
+        }
+    }
+
+    pub fn contains_point(&self, point: &nalgebra_glm::Vec3) -> bool {
+        self.planes
+            .iter()
+            .all(|plane| plane.signed_distance(point) >= 0.0)
+    }
+
+    pub fn intersects_sphere(&self, center: &nalgebra_glm::Vec3, radius: f32) -> bool {
+        self.planes
+            .iter()
+            .all(|plane| plane.signed_distance(center) >= -radius)
+    }
+
+    // Conservative, boxes near the corners of the frustum can pass while outside
+    pub fn intersects_aabb(&self, aabb: &Aabb) -> bool {
+        self.planes.iter().all(|plane| {
+            // The corner furthest along the normal
+            let corner = nalgebra_glm::vec3(
+                if plane.normal.x >= 0.0 {
+                    aabb.max.x
+                } else {
+                    aabb.min.x
+                },
+                if plane.normal.y >= 0.0 {
+                    aabb.max.y
+                } else {
+                    aabb.min.y
+                },
+                if plane.normal.z >= 0.0 {
+                    aabb.max.z
+                } else {
+                    aabb.min.z
+                },
+            );
+
+            plane.signed_distance(&corner) >= 0.0
+        })
+    }
+}
+
+// `view_projection` outputs the depth in [0, 1], the result is in pixels of the target from
+// the top left corner for a viewport at `offset` of `size` pixels. `z` is the depth, `None`
+// for points behind a perspective projection
+pub fn project(
+    view_projection: &nalgebra_glm::Mat4,
+    point: &nalgebra_glm::Vec3,
+    offset: &nalgebra_glm::Vec2,
+    size: &nalgebra_glm::Vec2,
+) -> Option<nalgebra_glm::Vec3> {
+    let clip = view_projection * point.push(1.0);
+    if clip.w <= f32::EPSILON {
+        return None;
+    }
+
+    let ndc = clip.xyz() / clip.w;
+
+    Some(nalgebra_glm::vec3(
+        offset.x + (ndc.x * 0.5 + 0.5) * size.x,
+        offset.y + (0.5 - ndc.y * 0.5) * size.y,
+        ndc.z,
+    ))
+}
+
+// Inverse of `project`
+pub fn unproject(
+    view_projection: &nalgebra_glm::Mat4,
+    position: &nalgebra_glm::Vec2,
+    depth: f32,
+    offset: &nalgebra_glm::Vec2,
+    size: &nalgebra_glm::Vec2,
+) -> nalgebra_glm::Vec3 {
+    let ndc = nalgebra_glm::vec4(
+        (position.x - offset.x) / size.x * 2.0 - 1.0,
+        1.0 - (position.y - offset.y) / size.y * 2.0,
+        depth,
+        1.0,
+    );
+
+    let world = nalgebra_glm::inverse(view_projection) * ndc;
+
+    world.xyz() / world.w
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Ray {
+    pub origin: nalgebra_glm::Vec3,
+    pub direction: nalgebra_glm::Vec3,
+}
+
+impl Ray {
+    pub fn new(origin: nalgebra_glm::Vec3, direction: nalgebra_glm::Vec3) -> Self {
+        Self { origin, direction }
+    }
+
+    pub fn at(&self, t: f32) -> nalgebra_glm::Vec3 {
+        self.origin + self.direction * t
+    }
+
+    // The direction is not renormalized, so `t` values stay comparable with the original ray
+    pub fn transform(&self, matrix: &nalgebra_glm::Mat4) -> Self {
+        Self {
+            origin: (matrix * self.origin.push(1.0)).xyz(),
+            direction: (matrix * self.direction.push(0.0)).xyz(),
+        }
+    }
+
+    // Slab test, returns the distance to the entry point
+    pub fn intersect_aabb(&self, aabb: &Aabb) -> Option<f32> {
+        let mut t_min = f32::MIN;
+        let mut t_max = f32::MAX;
+
+        for i in 0..3 {
+            if self.direction[i].abs() < f32::EPSILON {
+                if self.origin[i] < aabb.min[i] || self.origin[i] > aabb.max[i] {
+                    return None;
+                }
+                continue;
+            }
+
+            let inverse = 1.0 / self.direction[i];
+            let mut t0 = (aabb.min[i] - self.origin[i]) * inverse;
+            let mut t1 = (aabb.max[i] - self.origin[i]) * inverse;
+            if t0 > t1 {
+                std::mem::swap(&mut t0, &mut t1);
+            }
+
+            t_min = t_min.max(t0);
+            t_max = t_max.min(t1);
+
+            if t_min > t_max {
+                return None;
+            }
+        }
+
+        if t_max < 0.0 {
+            return None;
+        }
+
+        Some(t_min.max(0.0))
+    }
+
+    // Möller–Trumbore, both faces are hit
+    pub fn intersect_triangle(
+        &self,
+        a: &nalgebra_glm::Vec3,
+        b: &nalgebra_glm::Vec3,
+        c: &nalgebra_glm::Vec3,
+    ) -> Option<f32> {
+        let edge_1 = b - a;
+        let edge_2 = c - a;
+
+        let p = nalgebra_glm::cross(&self.direction, &edge_2);
+        let determinant = nalgebra_glm::dot(&edge_1, &p);
+        if determinant.abs() < f32::EPSILON {
+            return None;
+        }
+
+        let inverse = 1.0 / determinant;
+        let s = self.origin - a;
+        let u = nalgebra_glm::dot(&s, &p) * inverse;
+        if !(0.0..=1.0).contains(&u) {
+            return None;
+        }
+
+        let q = nalgebra_glm::cross(&s, &edge_1);
+        let v = nalgebra_glm::dot(&self.direction, &q) * inverse;
+        if v < 0.0 || u + v > 1.0 {
+            return None;
+        }
+
+        let t = nalgebra_glm::dot(&edge_2, &q) * inverse;
+
+        (t >= 0.0).then_some(t)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn unit_box() -> Aabb {
+        Aabb {
+            min: nalgebra_glm::vec3(-1.0, -1.0, -1.0),
+            max: nalgebra_glm::vec3(1.0, 1.0, 1.0),
+        }
+    }
+
+    // Looks from (0, 0, 5) towards the origin, 90 degrees vertically on a 200x100 viewport
+    fn view_projection() -> nalgebra_glm::Mat4 {
+        crate::component::camera::OPENGL_TO_WGPU_MATRIX
+            * nalgebra_glm::perspective_rh(2.0, 90f32.to_radians(), 0.1, 100.0)
+            * nalgebra_glm::look_at_rh(
+                &nalgebra_glm::vec3(0.0, 0.0, 5.0),
+                &nalgebra_glm::zero(),
+                &nalgebra_glm::Vec3::y(),
+            )
+    }
+
+    #[test]
+    fn ray_enters_aabb() {
+        let ray = Ray::new(nalgebra_glm::vec3(-5.0, 0.5, 0.0), nalgebra_glm::Vec3::x());
+
+        assert_eq!(ray.intersect_aabb(&unit_box()), Some(4.0));
+    }
+
+    #[test]
+    fn ray_starting_inside_aabb_hits_at_zero() {
+        let ray = Ray::new(nalgebra_glm::zero(), nalgebra_glm::vec3(0.0, 0.0, -1.0));
+
+        assert_eq!(ray.intersect_aabb(&unit_box()), Some(0.0));
+    }
+
+    #[test]
+    fn ray_misses_aabb() {
+        // Beside, behind and parallel outside a slab
+        [
+            Ray::new(nalgebra_glm::vec3(-5.0, 2.0, 0.0), nalgebra_glm::Vec3::x()),
+            Ray::new(nalgebra_glm::vec3(5.0, 0.0, 0.0), nalgebra_glm::Vec3::x()),
+            Ray::new(nalgebra_glm::vec3(0.0, 3.0, -5.0), nalgebra_glm::Vec3::z()),
+        ]
+        .iter()
+        .for_each(|ray| assert_eq!(ray.intersect_aabb(&unit_box()), None));
+    }
+
+    #[test]
+    fn ray_hits_both_faces_of_triangle() {
+        let (a, b, c) = (
+            nalgebra_glm::vec3(-1.0, -1.0, 0.0),
+            nalgebra_glm::vec3(1.0, -1.0, 0.0),
+            nalgebra_glm::vec3(0.0, 1.0, 0.0),
+        );
+
+        let front = Ray::new(
+            nalgebra_glm::vec3(0.0, 0.0, 2.0),
+            nalgebra_glm::vec3(0.0, 0.0, -1.0),
+        );
+        let back = Ray::new(nalgebra_glm::vec3(0.0, 0.0, -3.0), nalgebra_glm::Vec3::z());
+
+        assert_eq!(front.intersect_triangle(&a, &b, &c), Some(2.0));
+        assert_eq!(back.intersect_triangle(&a, &b, &c), Some(3.0));
+    }
+
+    #[test]
+    fn ray_misses_triangle() {
+        let (a, b, c) = (
+            nalgebra_glm::vec3(-1.0, -1.0, 0.0),
+            nalgebra_glm::vec3(1.0, -1.0, 0.0),
+            nalgebra_glm::vec3(0.0, 1.0, 0.0),
+        );
+
+        // Outside the edges, pointing away and parallel to the plane
+        [
+            Ray::new(
+                nalgebra_glm::vec3(1.0, 1.0, 2.0),
+                nalgebra_glm::vec3(0.0, 0.0, -1.0),
+            ),
+            Ray::new(nalgebra_glm::vec3(0.0, 0.0, 2.0), nalgebra_glm::Vec3::z()),
+            Ray::new(nalgebra_glm::vec3(-5.0, 0.0, 0.0), nalgebra_glm::Vec3::x()),
+        ]
+        .iter()
+        .for_each(|ray| assert_eq!(ray.intersect_triangle(&a, &b, &c), None));
+    }
+
+    #[test]
+    fn transformed_ray_keeps_distances() {
+        let matrix = nalgebra_glm::scale(
+            &nalgebra_glm::translation(&nalgebra_glm::vec3(10.0, 0.0, 0.0)),
+            &nalgebra_glm::vec3(2.0, 2.0, 2.0),
+        );
+        let ray = Ray::new(nalgebra_glm::vec3(0.0, 1.0, 0.0), nalgebra_glm::Vec3::x());
+        let local = ray.transform(&nalgebra_glm::inverse(&matrix));
+
+        // The world box spans x in [8, 12]
+        let t = local.intersect_aabb(&unit_box()).unwrap();
+        assert!((t - 8.0).abs() < 1e-5);
+        assert!(nalgebra_glm::distance(&ray.at(t), &nalgebra_glm::vec3(8.0, 1.0, 0.0)) < 1e-5);
+    }
+
+    #[test]
+    fn projection_and_unprojection_round_trip() {
+        let matrix = view_projection();
+        let offset = nalgebra_glm::vec2(50.0, 20.0);
+        let size = nalgebra_glm::vec2(200.0, 100.0);
+
+        let center = project(&matrix, &nalgebra_glm::zero(), &offset, &size).unwrap();
+        assert!(nalgebra_glm::distance(&center.xy(), &nalgebra_glm::vec2(150.0, 70.0)) < 1e-4);
+        assert!((0.0..=1.0).contains(&center.z));
+
+        [
+            nalgebra_glm::vec3(1.0, 2.0, -3.0),
+            nalgebra_glm::vec3(-4.0, -1.0, 2.0),
+            nalgebra_glm::vec3(0.5, 0.0, 4.0),
+        ]
+        .iter()
+        .for_each(|point| {
+            let screen = project(&matrix, point, &offset, &size).unwrap();
+            let back = unproject(&matrix, &screen.xy(), screen.z, &offset, &size);
+
+            assert!(nalgebra_glm::distance(point, &back) < 1e-3, "{:?}", back);
+        });
+
+        // Behind the camera
+        assert!(project(&matrix, &nalgebra_glm::vec3(0.0, 0.0, 10.0), &offset, &size).is_none());
+    }
+
+    #[test]
+    fn frustum_contains_what_is_in_view() {
+        let frustum = Frustum::from_matrix(&view_projection());
+
+        assert!(frustum.contains_point(&nalgebra_glm::zero()));
+        assert!(frustum.contains_point(&nalgebra_glm::vec3(0.0, 0.0, -90.0)));
+        // Behind, past the far plane and beside the 90 degree cone
+        assert!(!frustum.contains_point(&nalgebra_glm::vec3(0.0, 0.0, 6.0)));
+        assert!(!frustum.contains_point(&nalgebra_glm::vec3(0.0, 0.0, -100.0)));
+        assert!(!frustum.contains_point(&nalgebra_glm::vec3(0.0, 6.0, 0.0)));
+
+        assert!(frustum.intersects_aabb(&unit_box()));
+        assert!(
+            frustum.intersects_aabb(&unit_box().transform(&nalgebra_glm::translation(
+                &nalgebra_glm::vec3(0.0, 5.5, 0.0)
+            )))
+        );
+        assert!(
+            !frustum.intersects_aabb(&unit_box().transform(&nalgebra_glm::translation(
+                &nalgebra_glm::vec3(0.0, 0.0, 10.0)
+            )))
+        );
+
+        assert!(frustum.intersects_sphere(&nalgebra_glm::vec3(0.0, 0.0, 6.0), 1.5));
+        assert!(!frustum.intersects_sphere(&nalgebra_glm::vec3(0.0, 0.0, 7.0), 1.5));
+    }
+}
@@ -0,0 +1,516 @@
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PickingMode {
+    Bounds,
+    Mesh,
+    Exact,
+}
+
+impl PickingMode {
+    pub const ALL: [PickingMode; 3] = [PickingMode::Bounds, PickingMode::Mesh, PickingMode::Exact];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            PickingMode::Bounds => "bounds",
+            PickingMode::Mesh => "mesh",
+            PickingMode::Exact => "exact",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|mode| mode.name() == name)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PickingEvent {
+    HoverStart(hecs::Entity),
+    HoverEnd(hecs::Entity),
+    Selected(hecs::Entity),
+    Deselected(hecs::Entity),
+}
+
+pub struct Picking {
+    pub mode: PickingMode,
+    cursor: Option<nalgebra_glm::Vec2>,
+    select_requested: bool,
+    hovered: Option<hecs::Entity>,
+    selected: Option<hecs::Entity>,
+    events: Vec<PickingEvent>,
+    pipeline: wgpu::RenderPipeline,
+    id_texture: wgpu::Texture,
+    id_view: wgpu::TextureView,
+    // The main depth buffer holds the scene by the time the ids are drawn
+    depth_texture: crate::texture::Texture,
+    id_buffer: wgpu::Buffer,
+    id_bind_group: wgpu::BindGroup,
+    id_bind_group_layout: wgpu::BindGroupLayout,
+    id_capacity: usize,
+    id_alignment: usize,
+    readback_buffer: wgpu::Buffer,
+    // Entities of the draws copied to the readback buffer, until it is mapped
+    readback: Option<(Vec<hecs::Entity>, Readback)>,
+}
+
+type Readback = std::sync::mpsc::Receiver<Result<(), wgpu::BufferAsyncError>>;
+
+impl Picking {
+    pub const ID_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::R32Uint;
+    const INITIAL_CAPACITY: usize = 64;
+
+    pub fn new(
+        device: &wgpu::Device,
+        config: &wgpu::SurfaceConfiguration,
+        camera_bind_group_layout: &wgpu::BindGroupLayout,
+    ) -> Self {
+        let id_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                entries: &[wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: true,
+                        min_binding_size: wgpu::BufferSize::new(4),
+                    },
+                    count: None,
+                }],
+                label: Some("Picking Bind Group Layout"),
+            });
+
+        let pipeline = crate::pipeline::create_picking_pipeline(
+            device,
+            &[camera_bind_group_layout, &id_bind_group_layout],
+        );
+
+        let (id_texture, id_view) = Self::create_id_texture(device, config);
+        let depth_texture =
+            crate::texture::Texture::create_depth_texture(device, config, "Picking Depth Texture");
+
+        let id_alignment = device.limits().min_uniform_buffer_offset_alignment as usize;
+        let (id_buffer, id_bind_group) = Self::create_id_buffer(
+            device,
+            &id_bind_group_layout,
+            Self::INITIAL_CAPACITY,
+            id_alignment,
+        );
+
+        let readback_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Picking Readback Buffer"),
+            size: wgpu::COPY_BYTES_PER_ROW_ALIGNMENT as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        Self {
+            mode: PickingMode::Mesh,
+            cursor: None,
+            select_requested: false,
+            hovered: None,
+            selected: None,
+            events: vec![],
+            pipeline,
+            id_texture,
+            id_view,
+            depth_texture,
+            id_buffer,
+            id_bind_group,
+            id_bind_group_layout,
+            id_capacity: Self::INITIAL_CAPACITY,
+            id_alignment,
+            readback_buffer,
+            readback: None,
+        }
+    }
+
+    fn create_id_texture(
+        device: &wgpu::Device,
+        config: &wgpu::SurfaceConfiguration,
+    ) -> (wgpu::Texture, wgpu::TextureView) {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Picking Id Texture"),
+            size: wgpu::Extent3d {
+                width: config.width,
+                height: config.height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: Self::ID_FORMAT,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        });
+
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+        (texture, view)
+    }
+
+    fn create_id_buffer(
+        device: &wgpu::Device,
+        layout: &wgpu::BindGroupLayout,
+        capacity: usize,
+        alignment: usize,
+    ) -> (wgpu::Buffer, wgpu::BindGroup) {
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Picking Id Buffer"),
+            size: (capacity * alignment) as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+                    buffer: &buffer,
+                    offset: 0,
+                    size: wgpu::BufferSize::new(4),
+                }),
+            }],
+            label: Some("Picking Bind Group"),
+        });
+
+        (buffer, bind_group)
+    }
+
+    pub fn resize(&mut self, device: &wgpu::Device, config: &wgpu::SurfaceConfiguration) {
+        (self.id_texture, self.id_view) = Self::create_id_texture(device, config);
+        self.depth_texture =
+            crate::texture::Texture::create_depth_texture(device, config, "Picking Depth Texture");
+    }
+
+    pub fn set_cursor(&mut self, cursor: Option<nalgebra_glm::Vec2>) {
+        self.cursor = cursor;
+    }
+
+    pub fn cursor(&self) -> Option<nalgebra_glm::Vec2> {
+        self.cursor
+    }
+
+    // The selection is resolved the next time the entity under the cursor is computed
+    pub fn request_select(&mut self) {
+        self.select_requested = true;
+    }
+
+    pub fn hovered(&self) -> Option<hecs::Entity> {
+        self.hovered
+    }
+
+    pub fn selected(&self) -> Option<hecs::Entity> {
+        self.selected
+    }
+
+    pub fn select(&mut self, entity: Option<hecs::Entity>) {
+        if self.selected == entity {
+            return;
+        }
+
+        if let Some(old) = self.selected {
+            self.events.push(PickingEvent::Deselected(old));
+        }
+        if let Some(new) = entity {
+            self.events.push(PickingEvent::Selected(new));
+        }
+
+        self.selected = entity;
+    }
+
+    pub fn drain_events(&mut self) -> std::vec::Drain<'_, PickingEvent> {
+        self.events.drain(..)
+    }
+
+    // Updates hover and selection from the entity currently under the cursor
+    pub fn apply(&mut self, hit: Option<hecs::Entity>) {
+        if self.hovered != hit {
+            if let Some(old) = self.hovered {
+                self.events.push(PickingEvent::HoverEnd(old));
+            }
+            if let Some(new) = hit {
+                self.events.push(PickingEvent::HoverStart(new));
+            }
+
+            self.hovered = hit;
+        }
+
+        if self.select_requested {
+            self.select_requested = false;
+            self.select(hit);
+        }
+    }
+
+    // Forgets entities that no longer exist
+    pub fn retain(&mut self, world: &hecs::World) {
+        if self.hovered.is_some_and(|entity| !world.contains(entity)) {
+            self.hovered = None;
+        }
+        if self.selected.is_some_and(|entity| !world.contains(entity)) {
+            self.selected = None;
+        }
+    }
+
+    pub fn raycast(
+        world: &hecs::World,
+        pipelines: &HashMap<String, crate::pipeline::Pipeline>,
+        ray: &crate::math::Ray,
+        mode: PickingMode,
+    ) -> Option<(hecs::Entity, f32)> {
+        world
+            .query::<(
+                &crate::component::Render,
+                Option<&crate::component::GlobalTransform>,
+            )>()
+            .iter()
+            .filter(|(_, (render, _))| {
+                pipelines[render.pipeline.as_str()].topology
+                    == wgpu::PrimitiveTopology::TriangleList
+            })
+            .filter_map(|(entity, (render, transform))| {
+                let world_matrix = transform
+                    .map(|transform| transform.matrix)
+                    .unwrap_or_else(nalgebra_glm::Mat4::identity);
+
+                let local_ray = ray.transform(&nalgebra_glm::inverse(&world_matrix));
+
+                let t = local_ray.intersect_aabb(&render.bounds)?;
+
+                if mode == PickingMode::Bounds {
+                    return Some((entity, t));
+                }
+
+                render
+                    .indices
+                    .chunks_exact(3)
+                    .filter_map(|triangle| {
+                        local_ray.intersect_triangle(
+                            &render.positions[triangle[0] as usize],
+                            &render.positions[triangle[1] as usize],
+                            &render.positions[triangle[2] as usize],
+                        )
+                    })
+                    .min_by(f32::total_cmp)
+                    .map(|t| (entity, t))
+            })
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+    }
+
+    pub fn memory(&self) -> crate::profiler::GpuMemory {
+        let mut memory = crate::profiler::GpuMemory::default();
+        memory.add_texture(&self.id_texture);
+        memory.add_texture(&self.depth_texture.texture);
+        memory.add_buffer(&self.id_buffer);
+        memory.add_buffer(&self.readback_buffer);
+        memory
+    }
+
+    // Renders the entities of pickable pipelines with their draw id and copies the pixel under
+    // the cursor. The copy is read a frame later, `None` means no result arrived this frame
+    #[allow(clippy::too_many_arguments)]
+    pub fn render_ids(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        world: &hecs::World,
+        pipelines: &HashMap<String, crate::pipeline::Pipeline>,
+        camera: &crate::component::Camera,
+        stats: &mut crate::profiler::RenderStats,
+    ) -> Option<Option<hecs::Entity>> {
+        device.poll(wgpu::Maintain::Poll);
+        let hit = self.read_id(world);
+
+        // A single readback buffer, the next copy waits for the previous one to be read.
+        // Without a cursor over the camera nothing is hit
+        if self.readback.is_none() && !self.copy_id(device, queue, world, pipelines, camera, stats)
+        {
+            return Some(hit.flatten());
+        }
+
+        hit
+    }
+
+    fn read_id(&mut self, world: &hecs::World) -> Option<Option<hecs::Entity>> {
+        let (draws, receiver) = self.readback.as_ref()?;
+
+        let mapped = match receiver.try_recv() {
+            Err(std::sync::mpsc::TryRecvError::Empty) => return None,
+            Ok(result) => result.is_ok(),
+            Err(std::sync::mpsc::TryRecvError::Disconnected) => false,
+        };
+
+        let id = mapped.then(|| {
+            let id = u32::from_ne_bytes(
+                self.readback_buffer.slice(..4).get_mapped_range()[..4]
+                    .try_into()
+                    .unwrap(),
+            );
+            self.readback_buffer.unmap();
+            id
+        });
+
+        // Entities despawned since the copy can't be picked anymore
+        let hit = id
+            .and_then(|id| id.checked_sub(1))
+            .and_then(|index| draws.get(index as usize).copied())
+            .filter(|entity| world.contains(*entity));
+        self.readback = None;
+
+        Some(hit)
+    }
+
+    fn copy_id(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        world: &hecs::World,
+        pipelines: &HashMap<String, crate::pipeline::Pipeline>,
+        camera: &crate::component::Camera,
+        stats: &mut crate::profiler::RenderStats,
+    ) -> bool {
+        let Some(cursor) = self.cursor else {
+            return false;
+        };
+        let size = self.id_texture.size();
+
+        // The ids are drawn like the window, through the viewport of the camera
+        if camera.output.target != crate::component::CameraTarget::Window
+            || !camera.contains_pixel(&cursor)
+            || cursor.x < 0.0
+            || cursor.y < 0.0
+            || cursor.x >= size.width as f32
+            || cursor.y >= size.height as f32
+        {
+            return false;
+        }
+
+        let mut query = world.query::<&crate::component::Render>();
+        let draws = query
+            .iter()
+            .filter(|(_, render)| {
+                pipelines[render.pipeline.as_str()].pickable && render.transform_buffer.is_some()
+            })
+            .collect::<Vec<_>>();
+
+        if draws.len() > self.id_capacity {
+            self.id_capacity = draws.len().next_power_of_two();
+            (self.id_buffer, self.id_bind_group) = Self::create_id_buffer(
+                device,
+                &self.id_bind_group_layout,
+                self.id_capacity,
+                self.id_alignment,
+            );
+        }
+
+        // Id 0 is kept for the background
+        let mut ids = vec![0u8; draws.len() * self.id_alignment];
+        (0..draws.len()).for_each(|i| {
+            let id = i as u32 + 1;
+            ids[i * self.id_alignment..i * self.id_alignment + 4]
+                .copy_from_slice(&id.to_ne_bytes());
+        });
+        queue.write_buffer(&self.id_buffer, 0, &ids);
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Picking Encoder"),
+        });
+
+        {
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Picking Render Pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: &self.id_view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+                        store: true,
+                    },
+                })],
+                depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                    view: &self.depth_texture.view,
+                    depth_ops: Some(wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(1.0),
+                        store: false,
+                    }),
+                    stencil_ops: None,
+                }),
+            });
+
+            let target = nalgebra_glm::vec2(size.width as f32, size.height as f32);
+            let (position, viewport_size) = camera.output.viewport.to_pixels(&target);
+            render_pass.set_viewport(
+                position.x.min(target.x - 1.0),
+                position.y.min(target.y - 1.0),
+                viewport_size.x.max(1.0),
+                viewport_size.y.max(1.0),
+                0.0,
+                1.0,
+            );
+
+            render_pass.set_pipeline(&self.pipeline);
+            render_pass.set_bind_group(0, &camera.bind_group, &[]);
+
+            draws.iter().enumerate().for_each(|(i, (_, render))| {
+                render_pass.set_bind_group(
+                    1,
+                    &self.id_bind_group,
+                    &[(i * self.id_alignment) as wgpu::DynamicOffset],
+                );
+                render_pass.set_vertex_buffer(0, render.vertex_buffer.slice(..));
+                if let Some(buffer) = render.transform_buffer.as_ref() {
+                    render_pass.set_vertex_buffer(1, buffer.slice(..));
+                }
+                render_pass
+                    .set_index_buffer(render.index_buffer.slice(..), wgpu::IndexFormat::Uint16);
+                render_pass.draw_indexed(0..render.index_count, 0, 0..1);
+                stats.add_draw(
+                    wgpu::PrimitiveTopology::TriangleList,
+                    render.positions.len() as u64,
+                    render.index_count as u64,
+                );
+            });
+        }
+
+        encoder.copy_texture_to_buffer(
+            wgpu::ImageCopyTexture {
+                texture: &self.id_texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d {
+                    x: cursor.x as u32,
+                    y: cursor.y as u32,
+                    z: 0,
+                },
+                aspect: wgpu::TextureAspect::All,
+            },
+            wgpu::ImageCopyBuffer {
+                buffer: &self.readback_buffer,
+                layout: wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT),
+                    rows_per_image: None,
+                },
+            },
+            wgpu::Extent3d {
+                width: 1,
+                height: 1,
+                depth_or_array_layers: 1,
+            },
+        );
+
+        queue.submit(std::iter::once(encoder.finish()));
+
+        let (sender, receiver) = std::sync::mpsc::channel();
+        self.readback_buffer
+            .slice(..4)
+            .map_async(wgpu::MapMode::Read, move |result| {
+                sender.send(result).ok();
+            });
+        self.readback = Some((
+            draws.into_iter().map(|(entity, _)| entity).collect(),
+            receiver,
+        ));
+
+        true
+    }
+}
@@ -0,0 +1,440 @@
+use std::rc::Rc;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlendMode {
+    Opaque,
+    AlphaBlend,
+}
+
+// What a pipeline binds at group 2, after the camera and the material
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExtraBindGroup {
+    None,
+    // The render target named by the mesh, or plain white
+    Texture,
+    // The joint matrices in `crate::component::Render::bind_group`
+    Skin,
+}
+
+pub struct Pipeline {
+    pub render_pipeline: Rc<wgpu::RenderPipeline>,
+    // Same pipeline drawing the edges of the triangles, for meshes
+    pub wireframe: Option<Rc<wgpu::RenderPipeline>>,
+    pub blend_mode: BlendMode,
+    pub topology: wgpu::PrimitiveTopology,
+    pub vertex_type: crate::vertex_type::VertexType,
+    pub extra_bind_group: ExtraBindGroup,
+    // Drawn with the ids of exact picking, the vertices have to be `DefaultVertex`
+    pub pickable: bool,
+}
+
+impl Pipeline {
+    // Drawing a mesh with another vertex layout or without the bind groups the pipeline
+    // needs fails the validation of wgpu
+    pub fn accepts(&self, render: &crate::component::Render) -> bool {
+        self.vertex_type == render.vertex_type
+            && (self.extra_bind_group != ExtraBindGroup::Skin || render.bind_group.is_some())
+    }
+}
+
+fn default_vertex_layout() -> wgpu::VertexBufferLayout<'static> {
+    wgpu::VertexBufferLayout {
+        array_stride: std::mem::size_of::<crate::vertex_type::DefaultVertex>() as u64,
+        step_mode: wgpu::VertexStepMode::Vertex,
+        attributes: &[
+            wgpu::VertexAttribute {
+                format: wgpu::VertexFormat::Float32x3,
+                offset: 0,
+                shader_location: 0,
+            },
+            wgpu::VertexAttribute {
+                format: wgpu::VertexFormat::Float32x3,
+                offset: std::mem::size_of::<[f32; 3]>() as wgpu::BufferAddress,
+                shader_location: 1,
+            },
+        ],
+    }
+}
+
+fn line_vertex_layout() -> wgpu::VertexBufferLayout<'static> {
+    wgpu::VertexBufferLayout {
+        array_stride: std::mem::size_of::<crate::vertex_type::LineVertex>() as u64,
+        step_mode: wgpu::VertexStepMode::Vertex,
+        attributes: &[
+            wgpu::VertexAttribute {
+                format: wgpu::VertexFormat::Float32x3,
+                offset: 0,
+                shader_location: 0,
+            },
+            wgpu::VertexAttribute {
+                format: wgpu::VertexFormat::Float32x3,
+                offset: std::mem::size_of::<[f32; 3]>() as wgpu::BufferAddress,
+                shader_location: 1,
+            },
+        ],
+    }
+}
+
+fn skinned_vertex_layout() -> wgpu::VertexBufferLayout<'static> {
+    wgpu::VertexBufferLayout {
+        array_stride: std::mem::size_of::<crate::vertex_type::SkinnedVertex>() as u64,
+        step_mode: wgpu::VertexStepMode::Vertex,
+        attributes: &[
+            wgpu::VertexAttribute {
+                format: wgpu::VertexFormat::Float32x3,
+                offset: 0,
+                shader_location: 0,
+            },
+            wgpu::VertexAttribute {
+                format: wgpu::VertexFormat::Float32x3,
+                offset: std::mem::size_of::<[f32; 3]>() as wgpu::BufferAddress,
+                shader_location: 1,
+            },
+            wgpu::VertexAttribute {
+                format: wgpu::VertexFormat::Uint32x4,
+                offset: std::mem::size_of::<[f32; 6]>() as wgpu::BufferAddress,
+                shader_location: 2,
+            },
+            wgpu::VertexAttribute {
+                format: wgpu::VertexFormat::Float32x4,
+                offset: std::mem::size_of::<[f32; 10]>() as wgpu::BufferAddress,
+                shader_location: 3,
+            },
+        ],
+    }
+}
+
+fn textured_vertex_layout() -> wgpu::VertexBufferLayout<'static> {
+    wgpu::VertexBufferLayout {
+        array_stride: std::mem::size_of::<crate::vertex_type::TexturedVertex>() as u64,
+        step_mode: wgpu::VertexStepMode::Vertex,
+        attributes: &[
+            wgpu::VertexAttribute {
+                format: wgpu::VertexFormat::Float32x3,
+                offset: 0,
+                shader_location: 0,
+            },
+            wgpu::VertexAttribute {
+                format: wgpu::VertexFormat::Float32x3,
+                offset: std::mem::size_of::<[f32; 3]>() as wgpu::BufferAddress,
+                shader_location: 1,
+            },
+            wgpu::VertexAttribute {
+                format: wgpu::VertexFormat::Float32x2,
+                offset: std::mem::size_of::<[f32; 6]>() as wgpu::BufferAddress,
+                shader_location: 2,
+            },
+        ],
+    }
+}
+
+fn transform_layout() -> wgpu::VertexBufferLayout<'static> {
+    wgpu::VertexBufferLayout {
+        array_stride: std::mem::size_of::<crate::component::transform::TransformRaw>()
+            as wgpu::BufferAddress,
+        step_mode: wgpu::VertexStepMode::Instance,
+        attributes: &[
+            wgpu::VertexAttribute {
+                offset: 0,
+                shader_location: 5,
+                format: wgpu::VertexFormat::Float32x4,
+            },
+            wgpu::VertexAttribute {
+                offset: std::mem::size_of::<[f32; 4]>() as wgpu::BufferAddress,
+                shader_location: 6,
+                format: wgpu::VertexFormat::Float32x4,
+            },
+            wgpu::VertexAttribute {
+                offset: std::mem::size_of::<[f32; 8]>() as wgpu::BufferAddress,
+                shader_location: 7,
+                format: wgpu::VertexFormat::Float32x4,
+            },
+            wgpu::VertexAttribute {
+                offset: std::mem::size_of::<[f32; 12]>() as wgpu::BufferAddress,
+                shader_location: 8,
+                format: wgpu::VertexFormat::Float32x4,
+            },
+        ],
+    }
+}
+
+// Every shader sees the camera uniform declared in `shaders/camera.wgsl` at group 0
+fn shader_source(source: &str) -> String {
+    format!("{}\n{}", include_str!("shaders/camera.wgsl"), source)
+}
+
+fn blend_state(blend_mode: BlendMode) -> wgpu::BlendState {
+    match blend_mode {
+        BlendMode::Opaque => wgpu::BlendState::REPLACE,
+        BlendMode::AlphaBlend => wgpu::BlendState::ALPHA_BLENDING,
+    }
+}
+
+// Opaque draws write depth, blended draws only test against it so that
+// everything behind a transparent surface is still visible
+fn depth_stencil_state(blend_mode: BlendMode) -> wgpu::DepthStencilState {
+    wgpu::DepthStencilState {
+        format: crate::texture::Texture::DEPTH_FORMAT,
+        depth_write_enabled: blend_mode == BlendMode::Opaque,
+        depth_compare: wgpu::CompareFunction::Less,
+        stencil: wgpu::StencilState::default(),
+        bias: wgpu::DepthBiasState::default(),
+    }
+}
+
+// Triangle meshes with a transform per draw, the material is bound at group 1
+fn create_mesh_pipeline(
+    device: &wgpu::Device,
+    format: wgpu::TextureFormat,
+    bind_group_layouts: &[&wgpu::BindGroupLayout],
+    blend_mode: BlendMode,
+    label: &str,
+    source: &'static str,
+    vertex_type: crate::vertex_type::VertexType,
+) -> Pipeline {
+    let vertex_layout = match vertex_type {
+        crate::vertex_type::VertexType::Default => default_vertex_layout(),
+        crate::vertex_type::VertexType::Line => line_vertex_layout(),
+        crate::vertex_type::VertexType::Textured => textured_vertex_layout(),
+        crate::vertex_type::VertexType::Skinned => skinned_vertex_layout(),
+    };
+
+    let render_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: Some(&format!("{} Layout Descriptor", label)),
+        bind_group_layouts,
+        push_constant_ranges: &[],
+    });
+    let shader = wgpu::ShaderModuleDescriptor {
+        label: Some(&format!("{} Shader", label)),
+        source: wgpu::ShaderSource::Wgsl(shader_source(source).into()),
+    };
+
+    let shader = device.create_shader_module(shader);
+
+    let create = |polygon_mode| {
+        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some(label),
+            layout: Some(&render_pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "v_main",
+                buffers: &[vertex_layout.clone(), transform_layout()],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "f_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format,
+                    blend: Some(blend_state(blend_mode)),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                strip_index_format: None,
+                front_face: wgpu::FrontFace::Ccw,
+                cull_mode: None,
+                // cull_mode: Some(wgpu::Face::Back),
+                unclipped_depth: false,
+                polygon_mode,
+                conservative: false,
+            },
+            depth_stencil: Some(depth_stencil_state(blend_mode)),
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+        })
+    };
+
+    Pipeline {
+        render_pipeline: Rc::new(create(wgpu::PolygonMode::Fill)),
+        wireframe: Some(Rc::new(create(wgpu::PolygonMode::Line))),
+        blend_mode,
+        topology: wgpu::PrimitiveTopology::TriangleList,
+        vertex_type,
+        extra_bind_group: ExtraBindGroup::None,
+        pickable: false,
+    }
+}
+
+pub fn create_default_pipeline(
+    device: &wgpu::Device,
+    format: wgpu::TextureFormat,
+    bind_group_layouts: &[&wgpu::BindGroupLayout],
+    blend_mode: BlendMode,
+) -> Pipeline {
+    Pipeline {
+        pickable: true,
+        ..create_mesh_pipeline(
+            device,
+            format,
+            bind_group_layouts,
+            blend_mode,
+            "Render Pipeline",
+            include_str!("shaders/default.wgsl"),
+            crate::vertex_type::VertexType::Default,
+        )
+    }
+}
+
+// The joint matrices of the skin are bound at group 2
+pub fn create_skinned_pipeline(
+    device: &wgpu::Device,
+    format: wgpu::TextureFormat,
+    bind_group_layouts: &[&wgpu::BindGroupLayout],
+    blend_mode: BlendMode,
+) -> Pipeline {
+    Pipeline {
+        extra_bind_group: ExtraBindGroup::Skin,
+        ..create_mesh_pipeline(
+            device,
+            format,
+            bind_group_layouts,
+            blend_mode,
+            "Skinned Pipeline",
+            include_str!("shaders/skinned.wgsl"),
+            crate::vertex_type::VertexType::Skinned,
+        )
+    }
+}
+
+// The texture is bound at group 2 and multiplied with the material color
+pub fn create_textured_pipeline(
+    device: &wgpu::Device,
+    format: wgpu::TextureFormat,
+    bind_group_layouts: &[&wgpu::BindGroupLayout],
+    blend_mode: BlendMode,
+) -> Pipeline {
+    Pipeline {
+        extra_bind_group: ExtraBindGroup::Texture,
+        ..create_mesh_pipeline(
+            device,
+            format,
+            bind_group_layouts,
+            blend_mode,
+            "Textured Pipeline",
+            include_str!("shaders/textured.wgsl"),
+            crate::vertex_type::VertexType::Textured,
+        )
+    }
+}
+
+pub fn create_line_pipeline(
+    device: &wgpu::Device,
+    format: wgpu::TextureFormat,
+    bind_group_layouts: &[&wgpu::BindGroupLayout],
+    depth_test: bool,
+) -> Pipeline {
+    let render_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: Some("Render Pipeline Layout Descriptor"),
+        bind_group_layouts,
+        push_constant_ranges: &[],
+    });
+    let shader = wgpu::ShaderModuleDescriptor {
+        label: Some("Render Pipeline Shader"),
+        source: wgpu::ShaderSource::Wgsl(shader_source(include_str!("shaders/line.wgsl")).into()),
+    };
+
+    let shader = device.create_shader_module(shader);
+
+    let render_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some("Render Pipeline"),
+        layout: Some(&render_pipeline_layout),
+        vertex: wgpu::VertexState {
+            module: &shader,
+            entry_point: "v_main",
+            buffers: &[line_vertex_layout()],
+        },
+        fragment: Some(wgpu::FragmentState {
+            module: &shader,
+            entry_point: "f_main",
+            targets: &[Some(wgpu::ColorTargetState {
+                format,
+                blend: Some(wgpu::BlendState::REPLACE),
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+        }),
+        primitive: wgpu::PrimitiveState {
+            topology: wgpu::PrimitiveTopology::LineList,
+            strip_index_format: None,
+            front_face: wgpu::FrontFace::Ccw,
+            cull_mode: Some(wgpu::Face::Back),
+            unclipped_depth: false,
+            polygon_mode: wgpu::PolygonMode::Line,
+            conservative: false,
+        },
+        depth_stencil: Some(if depth_test {
+            depth_stencil_state(BlendMode::Opaque)
+        } else {
+            wgpu::DepthStencilState {
+                format: crate::texture::Texture::DEPTH_FORMAT,
+                depth_write_enabled: false,
+                depth_compare: wgpu::CompareFunction::Always,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }
+        }),
+        multisample: wgpu::MultisampleState::default(),
+        multiview: None,
+    });
+
+    Pipeline {
+        render_pipeline: Rc::new(render_pipeline),
+        wireframe: None,
+        blend_mode: BlendMode::Opaque,
+        topology: wgpu::PrimitiveTopology::LineList,
+        vertex_type: crate::vertex_type::VertexType::Line,
+        extra_bind_group: ExtraBindGroup::None,
+        pickable: false,
+    }
+}
+
+// Writes the draw id of every fragment into an `R32Uint` target for exact picking
+pub fn create_picking_pipeline(
+    device: &wgpu::Device,
+    bind_group_layouts: &[&wgpu::BindGroupLayout],
+) -> wgpu::RenderPipeline {
+    let render_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: Some("Picking Pipeline Layout Descriptor"),
+        bind_group_layouts,
+        push_constant_ranges: &[],
+    });
+    let shader = wgpu::ShaderModuleDescriptor {
+        label: Some("Picking Pipeline Shader"),
+        source: wgpu::ShaderSource::Wgsl(
+            shader_source(include_str!("shaders/picking.wgsl")).into(),
+        ),
+    };
+
+    let shader = device.create_shader_module(shader);
+
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some("Picking Pipeline"),
+        layout: Some(&render_pipeline_layout),
+        vertex: wgpu::VertexState {
+            module: &shader,
+            entry_point: "v_main",
+            buffers: &[default_vertex_layout(), transform_layout()],
+        },
+        fragment: Some(wgpu::FragmentState {
+            module: &shader,
+            entry_point: "f_main",
+            targets: &[Some(wgpu::ColorTargetState {
+                format: crate::picking::Picking::ID_FORMAT,
+                blend: None,
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+        }),
+        primitive: wgpu::PrimitiveState {
+            topology: wgpu::PrimitiveTopology::TriangleList,
+            strip_index_format: None,
+            front_face: wgpu::FrontFace::Ccw,
+            cull_mode: None,
+            unclipped_depth: false,
+            polygon_mode: wgpu::PolygonMode::Fill,
+            conservative: false,
+        },
+        depth_stencil: Some(depth_stencil_state(BlendMode::Opaque)),
+        multisample: wgpu::MultisampleState::default(),
+        multiview: None,
+    })
+}
@@ -0,0 +1,551 @@
+use std::collections::HashMap;
+
+const EPSILON: f32 = 1e-4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OverrideField {
+    Name,
+    Position,
+    Rotation,
+    Scale,
+    Pipeline,
+    Material,
+    Color,
+}
+
+impl OverrideField {
+    pub const ALL: [OverrideField; 7] = [
+        OverrideField::Name,
+        OverrideField::Position,
+        OverrideField::Rotation,
+        OverrideField::Scale,
+        OverrideField::Pipeline,
+        OverrideField::Material,
+        OverrideField::Color,
+    ];
+}
+
+// Fields left as `None` follow the prefab
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Overrides {
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub position: Option<[f32; 3]>,
+    #[serde(default)]
+    pub rotation: Option<[f32; 4]>,
+    #[serde(default)]
+    pub scale: Option<[f32; 3]>,
+    #[serde(default)]
+    pub pipeline: Option<String>,
+    #[serde(default)]
+    pub material: Option<String>,
+    #[serde(default)]
+    pub color: Option<[f32; 4]>,
+}
+
+impl Overrides {
+    pub fn is_set(&self, field: OverrideField) -> bool {
+        match field {
+            OverrideField::Name => self.name.is_some(),
+            OverrideField::Position => self.position.is_some(),
+            OverrideField::Rotation => self.rotation.is_some(),
+            OverrideField::Scale => self.scale.is_some(),
+            OverrideField::Pipeline => self.pipeline.is_some(),
+            OverrideField::Material => self.material.is_some(),
+            OverrideField::Color => self.color.is_some(),
+        }
+    }
+
+    pub fn clear(&mut self, field: OverrideField) {
+        match field {
+            OverrideField::Name => self.name = None,
+            OverrideField::Position => self.position = None,
+            OverrideField::Rotation => self.rotation = None,
+            OverrideField::Scale => self.scale = None,
+            OverrideField::Pipeline => self.pipeline = None,
+            OverrideField::Material => self.material = None,
+            OverrideField::Color => self.color = None,
+        }
+    }
+}
+
+// Links an entity to the entity at `index` of the prefab saved in `prefab`
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct PrefabInstance {
+    pub prefab: String,
+    pub index: usize,
+    #[serde(default)]
+    pub overrides: Overrides,
+}
+
+// A color override gets its own material, the engine creates it on demand. The generation
+// is part of the name, a new entity reusing the slot gets another one
+pub fn color_material(base: &str, entity: hecs::Entity) -> String {
+    format!("{}#{}", base, entity.to_bits())
+}
+
+pub fn is_color_material(name: &str) -> bool {
+    name.contains('#')
+}
+
+fn differs(a: &[f32], b: &[f32]) -> bool {
+    a.iter().zip(b.iter()).any(|(a, b)| (a - b).abs() > EPSILON)
+}
+
+// `q` and `-q` are the same rotation
+fn rotation_differs(a: &[f32; 4], b: &[f32; 4]) -> bool {
+    let dot = a.iter().zip(b.iter()).map(|(a, b)| a * b).sum::<f32>();
+    dot.abs() < 1.0 - EPSILON
+}
+
+#[derive(Default)]
+pub struct PrefabLibrary {
+    prefabs: HashMap<String, crate::scene::SceneFile>,
+}
+
+impl PrefabLibrary {
+    pub fn new() -> Self {
+        Self {
+            prefabs: HashMap::new(),
+        }
+    }
+
+    pub fn get(&self, path: &str) -> Option<&crate::scene::SceneFile> {
+        self.prefabs.get(path)
+    }
+
+    pub fn entity(&self, instance: &PrefabInstance) -> Option<&crate::scene::EntityData> {
+        self.prefabs
+            .get(&instance.prefab)
+            .and_then(|prefab| prefab.entities.get(instance.index))
+    }
+
+    // Prefabs are read from disk once, `reload` picks up later changes
+    pub fn load(
+        &mut self,
+        path: &str,
+    ) -> Result<&crate::scene::SceneFile, crate::scene::SceneError> {
+        if !self.prefabs.contains_key(path) {
+            let prefab = crate::scene::SceneFile::load(path)?;
+            self.prefabs.insert(path.to_owned(), prefab);
+        }
+
+        Ok(&self.prefabs[path])
+    }
+
+    // Saves `root` and its descendants as a prefab, they become its first instance
+    pub fn create(
+        &mut self,
+        world: &mut hecs::World,
+        root: hecs::Entity,
+        materials: &HashMap<String, crate::material::Material>,
+        path: &str,
+    ) -> Result<(), crate::scene::SceneError> {
+        let has_transform = |entity: hecs::Entity| {
+            world
+                .satisfies::<&crate::component::Transform>(entity)
+                .unwrap_or_default()
+        };
+
+        if !has_transform(root) {
+            return Err(crate::scene::SceneError::Prefab(
+                "the root of a prefab needs a transform".to_owned(),
+            ));
+        }
+
+        // Descendants come after their parents, an entity left out takes its subtree with it
+        let mut skipped = std::collections::HashSet::new();
+        let entities = std::iter::once(root)
+            .chain(
+                crate::hierarchy::descendants(world, root)
+                    .into_iter()
+                    .filter(|entity| {
+                        let keep = has_transform(*entity)
+                            && !crate::hierarchy::parent(world, *entity)
+                                .is_some_and(|parent| skipped.contains(&parent));
+                        if !keep {
+                            skipped.insert(*entity);
+                        }
+                        keep
+                    }),
+            )
+            .collect::<Vec<_>>();
+
+        let mut prefab = crate::scene::SceneFile::from_entities(world, &entities, materials);
+
+        // Nested prefabs are not supported, the saved entities are plain copies
+        prefab
+            .entities
+            .iter_mut()
+            .for_each(|entity| entity.prefab = None);
+        if let Some(transform) = prefab
+            .entities
+            .first_mut()
+            .and_then(|entity| entity.transform.as_mut())
+        {
+            transform.parent = None;
+        }
+
+        let used = prefab
+            .entities
+            .iter()
+            .filter_map(|entity| entity.render.as_ref().map(|render| render.material.clone()))
+            .collect::<Vec<_>>();
+        prefab.materials.retain(|name, _| used.contains(name));
+
+        prefab.save(path)?;
+        self.prefabs.insert(path.to_owned(), prefab);
+
+        entities.iter().enumerate().for_each(|(index, entity)| {
+            world
+                .insert_one(
+                    *entity,
+                    PrefabInstance {
+                        prefab: path.to_owned(),
+                        index,
+                        overrides: Overrides::default(),
+                    },
+                )
+                .unwrap_or_default();
+        });
+
+        Ok(())
+    }
+
+    // `overrides` are applied to the root of the new instance
+    pub fn instantiate(
+        &mut self,
+        path: &str,
+        world: &mut hecs::World,
+        device: &std::sync::Arc<wgpu::Device>,
+        window: &std::sync::Arc<winit::window::Window>,
+        skin_layout: &wgpu::BindGroupLayout,
+        overrides: Overrides,
+    ) -> Result<crate::scene::SpawnedScene, crate::scene::SceneError> {
+        let mut prefab = self.load(path)?.clone();
+
+        prefab
+            .entities
+            .iter_mut()
+            .enumerate()
+            .for_each(|(index, entity)| {
+                entity.prefab = Some(PrefabInstance {
+                    prefab: path.to_owned(),
+                    index,
+                    overrides: if index == 0 {
+                        overrides.clone()
+                    } else {
+                        Overrides::default()
+                    },
+                });
+            });
+
+        let spawned = prefab.instantiate(world, device, window, skin_layout)?;
+
+        spawned
+            .entities
+            .iter()
+            .try_for_each(|entity| self.apply(world, *entity, device.as_ref()))?;
+
+        Ok(spawned)
+    }
+
+    // Fields that were changed on an instance since the last check become overrides
+    pub fn detect_overrides(&self, world: &mut hecs::World) {
+        world
+            .query_mut::<(
+                &mut PrefabInstance,
+                Option<&crate::component::Name>,
+                Option<&crate::component::Transform>,
+                Option<&crate::component::Render>,
+            )>()
+            .into_iter()
+            .for_each(|(entity, (instance, name, transform, render))| {
+                let data = match self
+                    .prefabs
+                    .get(&instance.prefab)
+                    .and_then(|prefab| prefab.entities.get(instance.index))
+                {
+                    Some(data) => data,
+                    None => return,
+                };
+                let overrides = &mut instance.overrides;
+
+                if let (Some(name), Some(prefab_name)) = (name, data.name.as_ref()) {
+                    if overrides.name.is_none() && &name.0 != prefab_name {
+                        overrides.name = Some(name.0.clone());
+                    }
+                }
+
+                if let (Some(transform), Some(prefab_transform)) =
+                    (transform, data.transform.as_ref())
+                {
+                    let position: [f32; 3] = transform.get_position().into();
+                    if overrides.position.is_none()
+                        && differs(&position, &prefab_transform.position)
+                    {
+                        overrides.position = Some(position);
+                    }
+
+                    let rotation = transform.get_q_rotation();
+                    let rotation = [rotation.i, rotation.j, rotation.k, rotation.w];
+                    if overrides.rotation.is_none()
+                        && rotation_differs(&rotation, &prefab_transform.rotation)
+                    {
+                        overrides.rotation = Some(rotation);
+                    }
+
+                    let scale: [f32; 3] = transform.get_scale().into();
+                    if overrides.scale.is_none() && differs(&scale, &prefab_transform.scale) {
+                        overrides.scale = Some(scale);
+                    }
+                }
+
+                if let (Some(render), Some(prefab_render)) = (render, data.render.as_ref()) {
+                    if overrides.pipeline.is_none() && render.pipeline != prefab_render.pipeline {
+                        overrides.pipeline = Some(render.pipeline.clone());
+                    }
+
+                    let base = overrides
+                        .material
+                        .clone()
+                        .unwrap_or_else(|| prefab_render.material.clone());
+                    let expected = match overrides.color {
+                        Some(_) => color_material(&base, entity),
+                        None => base,
+                    };
+
+                    // Picking another material drops the color override
+                    if render.material != expected {
+                        overrides.material = Some(render.material.clone());
+                        overrides.color = None;
+                    }
+                }
+            });
+    }
+
+    // Copies every field that is not overridden from the prefab into the entity
+    pub fn apply(
+        &self,
+        world: &mut hecs::World,
+        entity: hecs::Entity,
+        device: &wgpu::Device,
+    ) -> Result<(), crate::scene::SceneError> {
+        let (instance, name, transform, render, light, camera) = match world.query_one_mut::<(
+            &PrefabInstance,
+            Option<&mut crate::component::Name>,
+            Option<&mut crate::component::Transform>,
+            Option<&mut crate::component::Render>,
+            Option<&mut crate::component::Light>,
+            Option<&mut crate::component::Camera>,
+        )>(entity)
+        {
+            Ok(components) => components,
+            Err(_) => return Ok(()),
+        };
+
+        let data = match self.entity(instance) {
+            Some(data) => data,
+            None => return Ok(()),
+        };
+        let overrides = &instance.overrides;
+
+        if let (Some(name), Some(prefab_name)) = (name, data.name.as_ref()) {
+            name.0 = overrides
+                .name
+                .clone()
+                .unwrap_or_else(|| prefab_name.clone());
+        }
+
+        if let (Some(transform), Some(prefab_transform)) = (transform, data.transform.as_ref()) {
+            let position = overrides.position.unwrap_or(prefab_transform.position);
+            let rotation = overrides.rotation.unwrap_or(prefab_transform.rotation);
+            let scale = overrides.scale.unwrap_or(prefab_transform.scale);
+
+            let current_rotation = transform.get_q_rotation();
+
+            if differs(&position, transform.get_position().as_slice())
+                || rotation_differs(
+                    &rotation,
+                    &[
+                        current_rotation.i,
+                        current_rotation.j,
+                        current_rotation.k,
+                        current_rotation.w,
+                    ],
+                )
+                || differs(&scale, transform.get_scale().as_slice())
+            {
+                transform.set_position(&position.into());
+                transform.set_rotation(&nalgebra_glm::quat(
+                    rotation[0],
+                    rotation[1],
+                    rotation[2],
+                    rotation[3],
+                ));
+                transform.set_scale(&scale.into());
+            }
+        }
+
+        if let (Some(render), Some(prefab_render)) = (render, data.render.as_ref()) {
+            let pipeline = overrides
+                .pipeline
+                .clone()
+                .unwrap_or_else(|| prefab_render.pipeline.clone());
+            let material = overrides
+                .material
+                .clone()
+                .unwrap_or_else(|| prefab_render.material.clone());
+            let material = match overrides.color {
+                Some(_) => color_material(&material, entity),
+                None => material,
+            };
+
+            // A skin stays bound to the new mesh
+            if render.source.as_ref() != Some(&prefab_render.mesh) {
+                let bind_group = render.bind_group.take();
+                *render = prefab_render
+                    .mesh
+                    .create_render(device, pipeline, render.transform_buffer.clone())
+                    .map_err(crate::scene::SceneError::Mesh)?;
+                render.bind_group = bind_group;
+            } else {
+                render.pipeline = pipeline;
+            }
+            render.material = material;
+            render.texture = prefab_render.texture.clone();
+        }
+
+        if let (Some(light), Some(prefab_light)) = (light, data.light) {
+            *light = prefab_light;
+        }
+
+        if let (Some(camera), Some(camera_type)) = (camera, data.camera) {
+            if camera.camera_type != camera_type {
+                camera.camera_type = camera_type;
+                camera.projection = camera.build_projection();
+            }
+            if let Some(output) = data.camera_output.as_ref() {
+                camera.output = output.clone();
+            }
+            if let Some(fov) = data.camera_fov {
+                camera.fov = fov;
+            }
+            if let Some([near, far]) = data.camera_clip {
+                camera.near = near;
+                camera.far = far;
+            }
+        }
+
+        Ok(())
+    }
+
+    // Replaces the prefab and updates its instances, overridden fields are kept
+    pub fn update(
+        &mut self,
+        path: &str,
+        prefab: crate::scene::SceneFile,
+        world: &mut hecs::World,
+        device: &wgpu::Device,
+    ) -> Result<(), crate::scene::SceneError> {
+        self.detect_overrides(world);
+        self.prefabs.insert(path.to_owned(), prefab);
+
+        let count = self.prefabs[path].entities.len();
+
+        let instances = world
+            .query_mut::<&PrefabInstance>()
+            .into_iter()
+            .filter(|(_, instance)| instance.prefab == path)
+            .map(|(entity, instance)| (entity, instance.index))
+            .collect::<Vec<_>>();
+
+        // Entities added to the prefab only show up in new instances
+        instances.into_iter().try_for_each(|(entity, index)| {
+            if index < count {
+                self.apply(world, entity, device)
+            } else {
+                log::warn!(
+                    "Entity {} is no longer part of the prefab {}, it is unlinked",
+                    entity.id(),
+                    path
+                );
+                world.remove_one::<PrefabInstance>(entity).ok();
+                Ok(())
+            }
+        })
+    }
+
+    pub fn reload(
+        &mut self,
+        path: &str,
+        world: &mut hecs::World,
+        device: &wgpu::Device,
+    ) -> Result<(), crate::scene::SceneError> {
+        let prefab = crate::scene::SceneFile::load(path)?;
+        self.update(path, prefab, world, device)
+    }
+
+    // Materials the color overrides expect to exist, by name
+    pub fn color_overrides(&self, world: &mut hecs::World) -> Vec<(String, [f32; 4])> {
+        world
+            .query_mut::<(&PrefabInstance, &crate::component::Render)>()
+            .into_iter()
+            .filter_map(|(_, (instance, render))| {
+                instance
+                    .overrides
+                    .color
+                    .map(|color| (render.material.clone(), color))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn color_materials_differ_between_generations() {
+        let mut world = hecs::World::new();
+        let old = world.spawn(());
+        world.despawn(old).unwrap();
+        let new = world.spawn(());
+
+        assert_eq!(old.id(), new.id());
+        assert_ne!(color_material("Red", old), color_material("Red", new));
+        assert!(is_color_material(&color_material("Red", new)));
+        assert!(!is_color_material("Red"));
+    }
+
+    #[test]
+    fn create_leaves_out_subtrees_without_transforms() {
+        let mut world = hecs::World::new();
+        let transform = || crate::component::TransformBuild::new().build();
+        let root = world.spawn((crate::component::Name::new("Root"), transform()));
+        let child = world.spawn((crate::component::Name::new("Child"), transform()));
+        let group = world.spawn((crate::component::Name::new("Group"),));
+        let nested = world.spawn((crate::component::Name::new("Nested"), transform()));
+        crate::hierarchy::set_parent(&mut world, child, Some(root)).unwrap();
+        crate::hierarchy::set_parent(&mut world, group, Some(root)).unwrap();
+        crate::hierarchy::set_parent(&mut world, nested, Some(group)).unwrap();
+
+        let path = std::env::temp_dir().join(format!("prefab_{}.ron", std::process::id()));
+        let path = path.to_str().unwrap();
+        let mut library = PrefabLibrary::new();
+        library
+            .create(&mut world, root, &HashMap::new(), path)
+            .unwrap();
+        std::fs::remove_file(path).unwrap();
+
+        let names = library.prefabs[path]
+            .entities
+            .iter()
+            .map(|entity| entity.name.clone().unwrap_or_default())
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["Root", "Child"]);
+        assert!(world.satisfies::<&PrefabInstance>(child).unwrap());
+        assert!(!world.satisfies::<&PrefabInstance>(nested).unwrap());
+    }
+}
@@ -0,0 +1,300 @@
+use std::collections::VecDeque;
+use std::time::Instant;
+
+// Frames shown by the graph
+const HISTORY: usize = 240;
+
+// Draws recorded into the render passes of a frame
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct RenderStats {
+    pub draw_calls: u32,
+    pub triangles: u64,
+    pub lines: u64,
+    pub vertices: u64,
+    // Runs of draws sharing a pipeline and a material, there is one instance per draw
+    pub batches: u32,
+}
+
+impl RenderStats {
+    // `vertices` is the size of the vertex buffer, `elements` the indices or vertices drawn
+    pub fn add_draw(&mut self, topology: wgpu::PrimitiveTopology, vertices: u64, elements: u64) {
+        self.draw_calls += 1;
+        self.vertices += vertices;
+
+        match topology {
+            wgpu::PrimitiveTopology::TriangleList => self.triangles += elements / 3,
+            wgpu::PrimitiveTopology::TriangleStrip => self.triangles += elements.saturating_sub(2),
+            wgpu::PrimitiveTopology::LineList => self.lines += elements / 2,
+            wgpu::PrimitiveTopology::LineStrip => self.lines += elements.saturating_sub(1),
+            wgpu::PrimitiveTopology::PointList => {}
+        }
+    }
+}
+
+// Bytes of the buffers and textures the engine created, egui keeps its own
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct GpuMemory {
+    pub buffers: u64,
+    pub textures: u64,
+}
+
+impl GpuMemory {
+    pub fn add_buffer(&mut self, buffer: &wgpu::Buffer) {
+        self.buffers += buffer.size();
+    }
+
+    pub fn add_texture(&mut self, texture: &wgpu::Texture) {
+        let format = texture.format();
+        let (block_width, block_height) = format.block_dimensions();
+        let block_size = format.block_size(None).unwrap_or_default() as u64;
+        let size = texture.size();
+
+        self.textures += (0..texture.mip_level_count())
+            .map(|level| {
+                let width = (size.width >> level).max(1).div_ceil(block_width) as u64;
+                let height = (size.height >> level).max(1).div_ceil(block_height) as u64;
+
+                width * height * size.depth_or_array_layers as u64 * block_size
+            })
+            .sum::<u64>()
+            * texture.sample_count() as u64;
+    }
+
+    pub fn add(&mut self, other: GpuMemory) {
+        self.buffers += other.buffers;
+        self.textures += other.textures;
+    }
+
+    pub fn total(&self) -> u64 {
+        self.buffers + self.textures
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct FrameProfile {
+    // CPU milliseconds of the update stages and of recording the render passes, in the
+    // order they ran
+    pub stages: Vec<(String, f32)>,
+    pub passes: Vec<(String, f32)>,
+    pub render: RenderStats,
+    pub entities: u32,
+    pub memory: GpuMemory,
+}
+
+// Timings are collected every frame, the window shows the last finished one
+pub struct Profiler {
+    pub enabled: bool,
+    // Milliseconds between the starts of two frames
+    frame_times: VecDeque<f32>,
+    frame_start: Option<Instant>,
+    current: FrameProfile,
+    last: FrameProfile,
+}
+
+impl Profiler {
+    pub fn new() -> Self {
+        Self {
+            enabled: false,
+            frame_times: VecDeque::with_capacity(HISTORY),
+            frame_start: None,
+            current: FrameProfile::default(),
+            last: FrameProfile::default(),
+        }
+    }
+
+    pub fn toggle(&mut self) {
+        self.enabled = !self.enabled;
+    }
+
+    pub fn begin_frame(&mut self) {
+        let now = Instant::now();
+
+        if let Some(start) = self.frame_start {
+            if self.frame_times.len() == HISTORY {
+                self.frame_times.pop_front();
+            }
+            self.frame_times
+                .push_back((now - start).as_secs_f32() * 1000.0);
+        }
+        self.frame_start = Some(now);
+
+        self.last = std::mem::take(&mut self.current);
+    }
+
+    // Returns the end of the stage, the start of the next one
+    pub fn stage(&mut self, name: &str, start: Instant) -> Instant {
+        let now = Instant::now();
+        self.current
+            .stages
+            .push((name.to_owned(), (now - start).as_secs_f32() * 1000.0));
+        now
+    }
+
+    pub fn pass(&mut self, name: &str, start: Instant) -> Instant {
+        let now = Instant::now();
+        self.current
+            .passes
+            .push((name.to_owned(), (now - start).as_secs_f32() * 1000.0));
+        now
+    }
+
+    pub fn render_stats(&mut self) -> &mut RenderStats {
+        &mut self.current.render
+    }
+
+    pub fn set_entities(&mut self, entities: u32) {
+        self.current.entities = entities;
+    }
+
+    pub fn set_memory(&mut self, memory: GpuMemory) {
+        self.current.memory = memory;
+    }
+
+    pub fn frame_times(&self) -> impl Iterator<Item = f32> + '_ {
+        self.frame_times.iter().copied()
+    }
+
+    // Averaged over the graph
+    pub fn frame_time(&self) -> f32 {
+        match self.frame_times.len() {
+            0 => 0.0,
+            count => self.frame_times.iter().sum::<f32>() / count as f32,
+        }
+    }
+
+    pub fn fps(&self) -> f32 {
+        match self.frame_time() {
+            time if time > 0.0 => 1000.0 / time,
+            _ => 0.0,
+        }
+    }
+
+    pub fn show(&mut self, ctx: &egui::Context) {
+        let mut open = self.enabled;
+        egui::Window::new("Performance")
+            .open(&mut open)
+            .default_pos(egui::pos2(8.0, 64.0))
+            .resizable(false)
+            .show(ctx, |ui| {
+                self.ui(ui);
+            });
+        self.enabled = open;
+    }
+
+    fn ui(&self, ui: &mut egui::Ui) {
+        let slowest = self.frame_times().fold(0.0, f32::max);
+        ui.label(format!(
+            "{:.0} fps, {:.2} ms, slowest {:.2} ms",
+            self.fps(),
+            self.frame_time(),
+            slowest
+        ));
+
+        self.graph(ui, slowest);
+
+        let last = &self.last;
+        ui.collapsing("Update", |ui| {
+            Self::timings(ui, "profiler_stages", &last.stages)
+        });
+        ui.collapsing("Render passes", |ui| {
+            Self::timings(ui, "profiler_passes", &last.passes)
+        });
+
+        let stats = &last.render;
+        egui::Grid::new("profiler_stats")
+            .num_columns(2)
+            .show(ui, |ui| {
+                [
+                    ("Draw calls", stats.draw_calls.to_string()),
+                    ("Batches", stats.batches.to_string()),
+                    ("Triangles", stats.triangles.to_string()),
+                    ("Lines", stats.lines.to_string()),
+                    ("Vertices", stats.vertices.to_string()),
+                    ("Entities", last.entities.to_string()),
+                    ("Buffers", format_bytes(last.memory.buffers)),
+                    ("Textures", format_bytes(last.memory.textures)),
+                    ("GPU memory", format_bytes(last.memory.total())),
+                ]
+                .into_iter()
+                .for_each(|(name, value)| {
+                    ui.label(name);
+                    ui.label(value);
+                    ui.end_row();
+                });
+            });
+    }
+
+    // Frame times from left to right, with lines at 60 and 30 fps
+    fn graph(&self, ui: &mut egui::Ui, slowest: f32) {
+        let (response, painter) =
+            ui.allocate_painter(egui::vec2(HISTORY as f32, 64.0), egui::Sense::hover());
+        let rect = response.rect;
+        let scale = slowest.max(1000.0 / 30.0) * 1.1;
+        let y = |time: f32| rect.bottom() - time / scale * rect.height();
+
+        painter.rect_filled(rect, 0.0, egui::Color32::from_black_alpha(160));
+
+        [
+            (1000.0 / 60.0, egui::Color32::DARK_GREEN),
+            (1000.0 / 30.0, egui::Color32::DARK_RED),
+        ]
+        .into_iter()
+        .for_each(|(time, color)| {
+            painter.line_segment(
+                [
+                    egui::pos2(rect.left(), y(time)),
+                    egui::pos2(rect.right(), y(time)),
+                ],
+                egui::Stroke::new(1.0, color),
+            );
+        });
+
+        let offset = HISTORY - self.frame_times.len();
+        let points = self
+            .frame_times()
+            .enumerate()
+            .map(|(i, time)| egui::pos2(rect.left() + (offset + i) as f32, y(time)))
+            .collect::<Vec<_>>();
+        painter.add(egui::Shape::line(
+            points,
+            egui::Stroke::new(1.0, egui::Color32::LIGHT_GREEN),
+        ));
+
+        if let Some(position) = response.hover_pos() {
+            let index = (position.x - rect.left()) as usize;
+            if let Some(time) = index
+                .checked_sub(offset)
+                .and_then(|i| self.frame_times.get(i))
+            {
+                response.on_hover_text(format!("{:.2} ms", time));
+            }
+        }
+    }
+
+    fn timings(ui: &mut egui::Ui, id: &str, timings: &[(String, f32)]) {
+        egui::Grid::new(id)
+            .num_columns(2)
+            .striped(true)
+            .show(ui, |ui| {
+                timings.iter().for_each(|(name, time)| {
+                    ui.label(name);
+                    ui.label(format!("{:.3} ms", time));
+                    ui.end_row();
+                });
+                ui.label("Total");
+                ui.label(format!(
+                    "{:.3} ms",
+                    timings.iter().map(|(_, time)| time).sum::<f32>()
+                ));
+                ui.end_row();
+            });
+    }
+}
+
+fn format_bytes(bytes: u64) -> String {
+    match bytes {
+        bytes if bytes >= 1 << 20 => format!("{:.2} MiB", bytes as f64 / (1 << 20) as f64),
+        bytes if bytes >= 1 << 10 => format!("{:.2} KiB", bytes as f64 / (1 << 10) as f64),
+        bytes => format!("{} B", bytes),
+    }
+}
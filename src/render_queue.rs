@@ -0,0 +1,104 @@
+use crate::pipeline::BlendMode;
+
+pub struct DrawItem<'a> {
+    pub render: &'a crate::component::Render,
+    pub depth: f32,
+}
+
+#[derive(Default)]
+pub struct RenderQueue<'a> {
+    opaque: Vec<DrawItem<'a>>,
+    transparent: Vec<DrawItem<'a>>,
+}
+
+impl<'a> RenderQueue<'a> {
+    pub fn new() -> Self {
+        Self {
+            opaque: vec![],
+            transparent: vec![],
+        }
+    }
+
+    // `depth` is the view space z of the draw, more negative is further away
+    pub fn push(
+        &mut self,
+        render: &'a crate::component::Render,
+        blend_mode: BlendMode,
+        depth: f32,
+    ) {
+        let item = DrawItem { render, depth };
+
+        match blend_mode {
+            BlendMode::Opaque => self.opaque.push(item),
+            BlendMode::AlphaBlend => self.transparent.push(item),
+        }
+    }
+
+    pub fn sort(&mut self) {
+        // Group opaque draws to minimize pipeline and bind group switches
+        self.opaque.sort_by(|a, b| {
+            a.render
+                .pipeline
+                .cmp(&b.render.pipeline)
+                .then_with(|| a.render.material.cmp(&b.render.material))
+        });
+
+        // Back to front
+        self.transparent.sort_by(|a, b| a.depth.total_cmp(&b.depth));
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &DrawItem<'a>> {
+        self.opaque.iter().chain(self.transparent.iter())
+    }
+
+    // Runs of draws with the same pipeline, material and texture
+    pub fn batches(&self) -> u32 {
+        let batched = self
+            .iter()
+            .zip(self.iter().skip(1))
+            .filter(|(a, b)| {
+                let (a, b) = (a.render, b.render);
+                a.pipeline == b.pipeline && a.material == b.material && a.texture == b.texture
+            })
+            .count();
+
+        (self.opaque.len() + self.transparent.len() - batched) as u32
+    }
+}
+
+// What is bound in a render pass, draws of the same batch skip setting it again
+#[derive(Default)]
+pub struct DrawState<'a> {
+    pipeline: Option<&'a wgpu::RenderPipeline>,
+    bind_groups: [Option<&'a wgpu::BindGroup>; 4],
+}
+
+impl<'a> DrawState<'a> {
+    pub fn set_pipeline(
+        &mut self,
+        render_pass: &mut wgpu::RenderPass<'a>,
+        pipeline: &'a wgpu::RenderPipeline,
+    ) {
+        if !self
+            .pipeline
+            .is_some_and(|bound| std::ptr::eq(bound, pipeline))
+        {
+            render_pass.set_pipeline(pipeline);
+            self.pipeline = Some(pipeline);
+        }
+    }
+
+    pub fn set_bind_group(
+        &mut self,
+        render_pass: &mut wgpu::RenderPass<'a>,
+        index: u32,
+        bind_group: &'a wgpu::BindGroup,
+    ) {
+        let bound = &mut self.bind_groups[index as usize];
+
+        if !bound.is_some_and(|bound| std::ptr::eq(bound, bind_group)) {
+            render_pass.set_bind_group(index, bind_group, &[]);
+            *bound = Some(bind_group);
+        }
+    }
+}
@@ -0,0 +1,585 @@
+use std::collections::{BTreeMap, HashSet};
+
+use crate::component::input::{Bindings, Input, InputEvent};
+
+// Recorded and replayed values may be rounded differently on other platforms
+const TOLERANCE: f32 = 1e-3;
+
+#[derive(Debug)]
+pub enum ReplayError {
+    Io(std::io::Error),
+    Parse(ron::error::SpannedError),
+    Serialize(ron::Error),
+    Scene(crate::scene::SceneError),
+    // The transform of `entity` is not the recorded one after `frame`
+    Diverged {
+        frame: u32,
+        entity: String,
+    },
+    // Scene files don't keep `component` of `entity` as the game logic would read it
+    Unsaved {
+        entity: String,
+        component: &'static str,
+    },
+}
+
+impl std::fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ReplayError::Io(e) => write!(f, "io error: {}", e),
+            ReplayError::Parse(e) => write!(f, "parse error: {}", e),
+            ReplayError::Serialize(e) => write!(f, "serialize error: {}", e),
+            ReplayError::Scene(e) => write!(f, "scene error: {}", e),
+            ReplayError::Diverged { frame, entity } => {
+                write!(f, "\"{}\" diverged at frame {}", entity, frame)
+            }
+            ReplayError::Unsaved { entity, component } => {
+                write!(
+                    f,
+                    "the scene can't keep the {} of \"{}\"",
+                    component, entity
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for ReplayError {}
+
+impl From<std::io::Error> for ReplayError {
+    fn from(e: std::io::Error) -> Self {
+        ReplayError::Io(e)
+    }
+}
+
+impl From<ron::error::SpannedError> for ReplayError {
+    fn from(e: ron::error::SpannedError) -> Self {
+        ReplayError::Parse(e)
+    }
+}
+
+impl From<ron::Error> for ReplayError {
+    fn from(e: ron::Error) -> Self {
+        ReplayError::Serialize(e)
+    }
+}
+
+impl From<crate::scene::SceneError> for ReplayError {
+    fn from(e: crate::scene::SceneError) -> Self {
+        ReplayError::Scene(e)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct TransformState {
+    pub position: [f32; 3],
+    pub rotation: [f32; 4],
+    pub scale: [f32; 3],
+}
+
+impl TransformState {
+    pub fn new(transform: &crate::component::Transform) -> Self {
+        let rotation = transform.get_q_rotation();
+
+        Self {
+            position: transform.get_position().into(),
+            rotation: [rotation.i, rotation.j, rotation.k, rotation.w],
+            scale: transform.get_scale().into(),
+        }
+    }
+
+    pub fn matches(&self, other: &TransformState) -> bool {
+        let values = |state: &TransformState| {
+            state
+                .position
+                .into_iter()
+                .chain(state.rotation)
+                .chain(state.scale)
+                .collect::<Vec<_>>()
+        };
+
+        values(self)
+            .into_iter()
+            .zip(values(other))
+            .all(|(a, b)| (a - b).abs() <= TOLERANCE)
+    }
+}
+
+// Local transforms of the named entities after a frame. Entities sharing a name can't be
+// told apart between runs and are left out, so are animated ones: scene files don't keep
+// their players and the replays leave them in the pose they were saved in
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Snapshot {
+    pub frame: u32,
+    pub transforms: BTreeMap<String, TransformState>,
+}
+
+impl Snapshot {
+    pub fn capture(world: &hecs::World, frame: u32) -> Self {
+        let mut transforms = BTreeMap::new();
+        let mut repeated = HashSet::new();
+
+        world
+            .query::<(&crate::component::Name, &crate::component::Transform)>()
+            .without::<&crate::component::AnimationPlayer>()
+            .iter()
+            .for_each(|(_, (name, transform))| {
+                if transforms
+                    .insert(name.0.clone(), TransformState::new(transform))
+                    .is_some()
+                {
+                    repeated.insert(name.0.clone());
+                }
+            });
+        transforms.retain(|name, _| !repeated.contains(name));
+
+        Self { frame, transforms }
+    }
+
+    // Entities spawned since the snapshot are not checked
+    pub fn verify(&self, world: &hecs::World) -> Result<(), ReplayError> {
+        let current = Snapshot::capture(world, self.frame);
+
+        self.transforms
+            .iter()
+            .try_for_each(|(name, state)| match current.transforms.get(name) {
+                Some(other) if state.matches(other) => Ok(()),
+                _ => Err(ReplayError::Diverged {
+                    frame: self.frame,
+                    entity: name.clone(),
+                }),
+            })
+    }
+}
+
+// Every frame advances by `timestep` seconds, so a frame number is also a timestamp
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Recording {
+    pub timestep: f32,
+    pub bindings: Bindings,
+    // Scene file the recording starts from
+    #[serde(default)]
+    pub scene: Option<String>,
+    pub length: u32,
+    // Events applied before each frame, frames without any are left out
+    pub frames: Vec<(u32, Vec<InputEvent>)>,
+    pub snapshots: Vec<Snapshot>,
+}
+
+impl Recording {
+    pub fn load(path: &str) -> Result<Self, ReplayError> {
+        let content = std::fs::read_to_string(path)?;
+        Ok(ron::from_str(&content)?)
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), ReplayError> {
+        let content = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())?;
+        std::fs::write(path, content)?;
+        Ok(())
+    }
+
+    // An input in the state the recorded one was when the recording started, once the
+    // events of the first frame are applied
+    pub fn create_input(&self) -> Input {
+        let mut input = Input::new();
+        input.set_bindings(self.bindings.clone());
+        input
+    }
+}
+
+pub struct Recorder {
+    path: String,
+    recording: Recording,
+    // Frames between two snapshots
+    snapshot_interval: u32,
+}
+
+impl Recorder {
+    pub fn new(path: &str, timestep: f32, bindings: Bindings, scene: Option<String>) -> Self {
+        Self {
+            path: path.to_owned(),
+            recording: Recording {
+                timestep,
+                bindings,
+                scene,
+                length: 0,
+                frames: vec![],
+                snapshots: vec![],
+            },
+            snapshot_interval: 60,
+        }
+    }
+
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    pub fn timestep(&self) -> f32 {
+        self.recording.timestep
+    }
+
+    // Called once the frame was simulated with `events` applied
+    pub fn record_frame(&mut self, events: Vec<InputEvent>, world: &hecs::World) {
+        let frame = self.recording.length;
+        self.recording.length += 1;
+
+        if !events.is_empty() {
+            self.recording.frames.push((frame, events));
+        }
+        if self.recording.length.is_multiple_of(self.snapshot_interval) {
+            self.recording
+                .snapshots
+                .push(Snapshot::capture(world, frame));
+        }
+    }
+
+    // The last frame always gets a snapshot
+    pub fn finish(mut self, world: &hecs::World) -> Recording {
+        let last = self.recording.length.checked_sub(1);
+        if last.is_some()
+            && self
+                .recording
+                .snapshots
+                .last()
+                .map(|snapshot| snapshot.frame)
+                != last
+        {
+            self.recording
+                .snapshots
+                .push(Snapshot::capture(world, last.unwrap_or_default()));
+        }
+
+        self.recording
+    }
+}
+
+pub struct Replay {
+    recording: Recording,
+    // Next frame to replay
+    frame: u32,
+    next_events: usize,
+    next_snapshot: usize,
+}
+
+impl Replay {
+    pub fn new(recording: Recording) -> Self {
+        Self {
+            recording,
+            frame: 0,
+            next_events: 0,
+            next_snapshot: 0,
+        }
+    }
+
+    pub fn recording(&self) -> &Recording {
+        &self.recording
+    }
+
+    pub fn timestep(&self) -> f32 {
+        self.recording.timestep
+    }
+
+    // Frames replayed so far
+    pub fn frame(&self) -> u32 {
+        self.frame
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.frame >= self.recording.length
+    }
+
+    // Applies the events of the next frame, returns false once the recording is over
+    pub fn next_frame(&mut self, input: &mut Input) -> bool {
+        if self.is_finished() {
+            return false;
+        }
+
+        if let Some((_, events)) = self
+            .recording
+            .frames
+            .get(self.next_events)
+            .filter(|(frame, _)| *frame == self.frame)
+        {
+            events.iter().cloned().for_each(|event| input.apply(event));
+            self.next_events += 1;
+        }
+        self.frame += 1;
+
+        true
+    }
+
+    // Compares with the snapshot of the frame just simulated, if there is one
+    pub fn verify(&mut self, world: &hecs::World) -> Result<(), ReplayError> {
+        let Some(snapshot) = self
+            .recording
+            .snapshots
+            .get(self.next_snapshot)
+            .filter(|snapshot| snapshot.frame + 1 == self.frame)
+        else {
+            return Ok(());
+        };
+        self.next_snapshot += 1;
+
+        snapshot.verify(world)
+    }
+}
+
+// Spawns a scene file without a GPU. The camera the engine would look through gets the
+// default controller if it has none, like `crate::engine::Engine::load_scene` does
+pub fn load_scene_headless(path: &str) -> Result<hecs::World, ReplayError> {
+    let file = crate::scene::SceneFile::load(path)?;
+
+    let mut world = hecs::World::new();
+    let spawned = file.instantiate_headless(&mut world)?;
+
+    let camera =
+        file.entities
+            .iter()
+            .zip(spawned.entities)
+            .find(|(data, _)| {
+                data.camera.is_some()
+                    && data.transform.is_some()
+                    && data.camera_output.as_ref().is_none_or(|output| {
+                        output.target == crate::component::CameraTarget::Window
+                    })
+            })
+            .map(|(_, entity)| entity)
+            .unwrap_or_else(|| {
+                world.spawn((
+                    crate::component::Name::new("Camera"),
+                    crate::component::TransformBuild::new().build(),
+                ))
+            });
+
+    if !world
+        .satisfies::<&crate::component::CameraController>(camera)
+        .unwrap_or_default()
+    {
+        world
+            .insert_one(
+                camera,
+                crate::component::CameraController::new(crate::component::ControllerMode::Fly),
+            )
+            .unwrap();
+    }
+
+    Ok(world)
+}
+
+// Recordings start from a scene file, what the game logic reads has to come back from it as
+// it is in `world`
+pub fn verify_scene(world: &hecs::World, scene: &str) -> Result<(), ReplayError> {
+    Snapshot::capture(world, 0)
+        .verify(&load_scene_headless(scene)?)
+        .map_err(|e| match e {
+            ReplayError::Diverged { entity, .. } => ReplayError::Unsaved {
+                entity,
+                component: "Transform",
+            },
+            e => e,
+        })
+}
+
+// Replays every frame on `world` and checks the snapshots, returns the number of frames
+pub fn replay_headless(recording: &Recording, world: &mut hecs::World) -> Result<u32, ReplayError> {
+    let mut input = recording.create_input();
+    let mut replay = Replay::new(recording.clone());
+
+    while replay.next_frame(&mut input) {
+        crate::simulation::step(world, &input, recording.timestep);
+        replay.verify(world)?;
+        input.end_frame();
+    }
+
+    Ok(replay.frame())
+}
+
+// Replays a recording file from its scene, for running recordings as regression tests
+pub fn verify_recording(path: &str) -> Result<u32, ReplayError> {
+    let recording = Recording::load(path)?;
+
+    let mut world = match &recording.scene {
+        Some(scene) => load_scene_headless(scene)?,
+        None => hecs::World::new(),
+    };
+
+    replay_headless(&recording, &mut world)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::component::input::{AxisBinding, Button};
+    use winit::event::VirtualKeyCode;
+
+    const TIMESTEP: f32 = 1.0 / 60.0;
+
+    // Without a `Camera` the scene files get another entity for the view, named "Camera"
+    fn world() -> hecs::World {
+        let mut world = hecs::World::new();
+        world.spawn((
+            crate::component::Name::new("Player"),
+            crate::component::TransformBuild::new()
+                .with_position(nalgebra_glm::vec3(0.0, 1.0, 5.0))
+                .build(),
+            crate::component::CameraController::new(crate::component::ControllerMode::Fly)
+                .with_smoothing(0.1),
+        ));
+        world.spawn((
+            crate::component::Name::new("Cube"),
+            crate::component::TransformBuild::new().build(),
+        ));
+        world
+    }
+
+    fn input() -> Input {
+        Input::new()
+            .with_axis(
+                "Move Z",
+                AxisBinding::Buttons {
+                    negative: Button::Key(VirtualKeyCode::S),
+                    positive: Button::Key(VirtualKeyCode::W),
+                },
+                1.0,
+            )
+            .with_axis("Look X", AxisBinding::MouseX, 1.0)
+    }
+
+    // Walks forward, looks around and stops, like a player would
+    fn record(world: &mut hecs::World, scene: Option<String>) -> Recording {
+        let mut input = input();
+        let mut recorder = Recorder::new("", TIMESTEP, input.bindings().clone(), scene);
+        input.start_recording();
+
+        (0..150).for_each(|frame| {
+            match frame {
+                0 => input.press(Button::Key(VirtualKeyCode::W)),
+                40..=59 => input.add_mouse_delta(&nalgebra_glm::vec2(3.0, 0.0)),
+                90 => input.release(Button::Key(VirtualKeyCode::W)),
+                _ => {}
+            }
+
+            crate::simulation::step(world, &input, TIMESTEP);
+            recorder.record_frame(input.take_recorded().unwrap_or_default(), world);
+            input.end_frame();
+        });
+
+        recorder.finish(world)
+    }
+
+    fn player_position(world: &hecs::World) -> nalgebra_glm::Vec3 {
+        world
+            .query::<(&crate::component::Name, &crate::component::Transform)>()
+            .iter()
+            .find(|(_, (name, _))| name.0 == "Player")
+            .map(|(_, (_, transform))| transform.get_position())
+            .unwrap()
+    }
+
+    #[test]
+    fn replays_end_where_the_recording_did() {
+        let mut recorded = world();
+        let recording = record(&mut recorded, None);
+
+        assert_eq!(recording.length, 150);
+        assert_eq!(recording.snapshots.last().unwrap().frame, 149);
+        assert!(
+            nalgebra_glm::distance(
+                &player_position(&recorded),
+                &nalgebra_glm::vec3(0.0, 1.0, 5.0)
+            ) > 1.0
+        );
+
+        let mut replayed = world();
+        assert_eq!(replay_headless(&recording, &mut replayed).unwrap(), 150);
+        assert!(
+            nalgebra_glm::distance(&player_position(&recorded), &player_position(&replayed))
+                < TOLERANCE
+        );
+    }
+
+    #[test]
+    fn replays_from_another_start_diverge() {
+        let recording = record(&mut world(), None);
+
+        let mut moved = world();
+        moved
+            .query_mut::<(&crate::component::Name, &mut crate::component::Transform)>()
+            .into_iter()
+            .filter(|(_, (name, _))| name.0 == "Cube")
+            .for_each(|(_, (_, transform))| {
+                transform.set_position(&nalgebra_glm::vec3(1.0, 0.0, 0.0))
+            });
+
+        assert!(matches!(
+            replay_headless(&recording, &mut moved),
+            Err(ReplayError::Diverged { frame: 59, entity }) if entity == "Cube"
+        ));
+    }
+
+    #[test]
+    fn recordings_replay_from_their_files() {
+        let directory = std::env::temp_dir();
+        let name = format!("replay_{}", std::process::id());
+        let scene = directory.join(format!("{}.scene.ron", name));
+        let scene = scene.to_str().unwrap().to_owned();
+        let path = directory.join(format!("{}.ron", name));
+        let path = path.to_str().unwrap().to_owned();
+
+        let mut world = world();
+        crate::scene::SceneFile::from_world(&world, &std::collections::HashMap::new())
+            .save(&scene)
+            .unwrap();
+        verify_scene(&world, &scene).unwrap();
+        record(&mut world, Some(scene.clone())).save(&path).unwrap();
+
+        let result = verify_recording(&path);
+        std::fs::remove_file(&scene).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(result.unwrap(), 150);
+    }
+
+    #[test]
+    fn animated_entities_are_left_out() {
+        let mut world = world();
+        world.spawn((
+            crate::component::Name::new("Door"),
+            crate::component::TransformBuild::new().build(),
+            crate::component::AnimationPlayer::new(),
+        ));
+
+        let recording = record(&mut world, None);
+        assert!(recording
+            .snapshots
+            .iter()
+            .all(|snapshot| !snapshot.transforms.contains_key("Door")
+                && snapshot.transforms.contains_key("Cube")));
+    }
+
+    #[test]
+    fn scenes_saved_before_a_change_are_refused() {
+        let scene = std::env::temp_dir().join(format!("replay_{}_moved.ron", std::process::id()));
+        let scene = scene.to_str().unwrap();
+
+        let mut world = world();
+        crate::scene::SceneFile::from_world(&world, &std::collections::HashMap::new())
+            .save(scene)
+            .unwrap();
+        world
+            .query_mut::<&mut crate::component::Transform>()
+            .into_iter()
+            .for_each(|(_, transform)| transform.add_position(&nalgebra_glm::vec3(0.0, 1.0, 0.0)));
+
+        let result = verify_scene(&world, scene);
+        std::fs::remove_file(scene).unwrap();
+
+        assert!(matches!(
+            result,
+            Err(ReplayError::Unsaved {
+                component: "Transform",
+                ..
+            })
+        ));
+    }
+}
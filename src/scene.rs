@@ -0,0 +1,788 @@
+use std::collections::{BTreeMap, HashMap};
+
+#[derive(Debug)]
+pub enum SceneError {
+    Io(std::io::Error),
+    Parse(ron::error::SpannedError),
+    Serialize(ron::Error),
+    Mesh(String),
+    InvalidParent { entity: usize, parent: usize },
+    InvalidJoint { entity: usize, joint: usize },
+    // The pipeline can't draw the vertices or lacks the bind groups of the mesh
+    IncompatiblePipeline { pipeline: String, entity: String },
+    UnknownPipeline(String),
+    UnknownMaterial(String),
+    Prefab(String),
+    Hierarchy(crate::hierarchy::HierarchyError),
+}
+
+impl std::fmt::Display for SceneError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SceneError::Io(e) => write!(f, "io error: {}", e),
+            SceneError::Parse(e) => write!(f, "parse error: {}", e),
+            SceneError::Serialize(e) => write!(f, "serialize error: {}", e),
+            SceneError::Mesh(e) => write!(f, "mesh error: {}", e),
+            SceneError::InvalidParent { entity, parent } => {
+                write!(f, "entity {} has an invalid parent {}", entity, parent)
+            }
+            SceneError::InvalidJoint { entity, joint } => {
+                write!(f, "entity {} has an invalid joint {}", entity, joint)
+            }
+            SceneError::IncompatiblePipeline { pipeline, entity } => {
+                write!(
+                    f,
+                    "pipeline \"{}\" can't draw the mesh of {}",
+                    pipeline, entity
+                )
+            }
+            SceneError::UnknownPipeline(name) => write!(f, "unknown pipeline \"{}\"", name),
+            SceneError::UnknownMaterial(name) => write!(f, "unknown material \"{}\"", name),
+            SceneError::Prefab(e) => write!(f, "prefab error: {}", e),
+            SceneError::Hierarchy(e) => write!(f, "hierarchy error: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for SceneError {}
+
+impl From<std::io::Error> for SceneError {
+    fn from(e: std::io::Error) -> Self {
+        SceneError::Io(e)
+    }
+}
+
+impl From<ron::error::SpannedError> for SceneError {
+    fn from(e: ron::error::SpannedError) -> Self {
+        SceneError::Parse(e)
+    }
+}
+
+impl From<crate::hierarchy::HierarchyError> for SceneError {
+    fn from(e: crate::hierarchy::HierarchyError) -> Self {
+        SceneError::Hierarchy(e)
+    }
+}
+
+impl From<ron::Error> for SceneError {
+    fn from(e: ron::Error) -> Self {
+        SceneError::Serialize(e)
+    }
+}
+
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct SceneFile {
+    #[serde(default)]
+    pub materials: BTreeMap<String, [f32; 4]>,
+    #[serde(default)]
+    pub entities: Vec<EntityData>,
+}
+
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct EntityData {
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub transform: Option<TransformData>,
+    #[serde(default)]
+    pub render: Option<RenderData>,
+    #[serde(default)]
+    pub camera: Option<crate::component::CameraType>,
+    #[serde(default)]
+    pub camera_output: Option<crate::component::CameraOutput>,
+    // Vertical field of view in degrees
+    #[serde(default)]
+    pub camera_fov: Option<f32>,
+    // Near and far clip planes
+    #[serde(default)]
+    pub camera_clip: Option<[f32; 2]>,
+    #[serde(default)]
+    pub controller: Option<ControllerData>,
+    #[serde(default)]
+    pub skin: Option<SkinData>,
+    #[serde(default)]
+    pub light: Option<crate::component::Light>,
+    #[serde(default)]
+    pub prefab: Option<crate::prefab::PrefabInstance>,
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct TransformData {
+    pub position: [f32; 3],
+    // Quaternion as (x, y, z, w)
+    #[serde(default = "TransformData::identity_rotation")]
+    pub rotation: [f32; 4],
+    #[serde(default = "TransformData::unit_scale")]
+    pub scale: [f32; 3],
+    // Index of the parent in `SceneFile::entities`
+    #[serde(default)]
+    pub parent: Option<usize>,
+}
+
+impl TransformData {
+    fn identity_rotation() -> [f32; 4] {
+        [0.0, 0.0, 0.0, 1.0]
+    }
+
+    fn unit_scale() -> [f32; 3] {
+        [1.0, 1.0, 1.0]
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct RenderData {
+    pub mesh: crate::shapes::MeshSource,
+    pub pipeline: String,
+    #[serde(default = "RenderData::default_material")]
+    pub material: String,
+    #[serde(default)]
+    pub texture: Option<String>,
+}
+
+impl RenderData {
+    fn default_material() -> String {
+        "Default".to_owned()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct SkinData {
+    // Indices of the joints in `SceneFile::entities`
+    pub joints: Vec<usize>,
+    // Columns of each matrix
+    pub inverse_bind_matrices: Vec<[[f32; 4]; 4]>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
+pub enum ControllerModeData {
+    Fly,
+    Fps {
+        min_pitch: f32,
+        max_pitch: f32,
+    },
+    Orbit {
+        target: [f32; 3],
+        distance: f32,
+        min_distance: f32,
+        max_distance: f32,
+    },
+    PanZoom {
+        height: f32,
+        min_height: f32,
+        max_height: f32,
+    },
+}
+
+// Settings of a `crate::component::CameraController`, its motion state starts over
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct ControllerData {
+    pub mode: ControllerModeData,
+    pub speed: f32,
+    pub sensitivity: f32,
+    pub smoothing: f32,
+}
+
+impl ControllerData {
+    pub fn new(controller: &crate::component::CameraController) -> Self {
+        use crate::component::ControllerMode;
+
+        let mode = match controller.mode {
+            ControllerMode::Fly => ControllerModeData::Fly,
+            ControllerMode::Fps {
+                min_pitch,
+                max_pitch,
+            } => ControllerModeData::Fps {
+                min_pitch,
+                max_pitch,
+            },
+            ControllerMode::Orbit {
+                target,
+                distance,
+                min_distance,
+                max_distance,
+            } => ControllerModeData::Orbit {
+                target: target.into(),
+                distance,
+                min_distance,
+                max_distance,
+            },
+            ControllerMode::PanZoom {
+                height,
+                min_height,
+                max_height,
+            } => ControllerModeData::PanZoom {
+                height,
+                min_height,
+                max_height,
+            },
+        };
+
+        Self {
+            mode,
+            speed: controller.speed,
+            sensitivity: controller.sensitivity,
+            smoothing: controller.smoothing,
+        }
+    }
+
+    pub fn build(&self) -> crate::component::CameraController {
+        use crate::component::ControllerMode;
+
+        let mode = match self.mode {
+            ControllerModeData::Fly => ControllerMode::Fly,
+            ControllerModeData::Fps {
+                min_pitch,
+                max_pitch,
+            } => ControllerMode::Fps {
+                min_pitch,
+                max_pitch,
+            },
+            ControllerModeData::Orbit {
+                target,
+                distance,
+                min_distance,
+                max_distance,
+            } => ControllerMode::Orbit {
+                target: target.into(),
+                distance,
+                min_distance,
+                max_distance,
+            },
+            ControllerModeData::PanZoom {
+                height,
+                min_height,
+                max_height,
+            } => ControllerMode::PanZoom {
+                height,
+                min_height,
+                max_height,
+            },
+        };
+
+        crate::component::CameraController::new(mode)
+            .with_speed(self.speed)
+            .with_sensitivity(self.sensitivity)
+            .with_smoothing(self.smoothing)
+    }
+}
+
+pub struct SpawnedScene {
+    pub entities: Vec<hecs::Entity>,
+    pub cameras: Vec<hecs::Entity>,
+}
+
+impl SceneFile {
+    pub fn load(path: &str) -> Result<Self, SceneError> {
+        let content = std::fs::read_to_string(path)?;
+        Ok(ron::from_str(&content)?)
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), SceneError> {
+        let content = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())?;
+        std::fs::write(path, content)?;
+        Ok(())
+    }
+
+    pub fn from_world(
+        world: &hecs::World,
+        materials: &HashMap<String, crate::material::Material>,
+    ) -> Self {
+        let mut entities = world
+            .iter()
+            .map(|entity| entity.entity())
+            .collect::<Vec<_>>();
+        entities.sort_by_key(|entity| entity.id());
+
+        Self::from_entities(world, &entities, materials)
+    }
+
+    // Parents that are not part of `entities` are dropped, those entities are saved as roots
+    pub fn from_entities(
+        world: &hecs::World,
+        entities: &[hecs::Entity],
+        materials: &HashMap<String, crate::material::Material>,
+    ) -> Self {
+        let entities = entities
+            .iter()
+            .filter_map(|entity| {
+                let mut query = world
+                    .query_one::<(
+                        Option<&crate::component::Name>,
+                        Option<&crate::component::Transform>,
+                        Option<&crate::component::Parent>,
+                        Option<&crate::component::Render>,
+                        Option<&crate::component::Skin>,
+                        Option<&crate::component::Camera>,
+                        Option<&crate::component::CameraController>,
+                        Option<&crate::component::Light>,
+                        Option<&crate::prefab::PrefabInstance>,
+                    )>(*entity)
+                    .ok()?;
+
+                let (name, transform, parent, render, skin, camera, controller, light, prefab) =
+                    query.get()?;
+
+                (name.is_some()
+                    || transform.is_some()
+                    || render.is_some()
+                    || camera.is_some()
+                    || light.is_some())
+                .then(|| {
+                    (
+                        *entity,
+                        name.map(|name| name.0.clone()),
+                        transform.map(|transform| {
+                            let rotation = transform.get_q_rotation();
+
+                            (
+                                TransformData {
+                                    position: transform.get_position().into(),
+                                    rotation: [rotation.i, rotation.j, rotation.k, rotation.w],
+                                    scale: transform.get_scale().into(),
+                                    parent: None,
+                                },
+                                parent.map(|parent| parent.0),
+                            )
+                        }),
+                        render.and_then(|render| match render.source.as_ref() {
+                            Some(mesh) => Some(RenderData {
+                                mesh: mesh.clone(),
+                                pipeline: render.pipeline.clone(),
+                                material: render.material.clone(),
+                                texture: render.texture.clone(),
+                            }),
+                            None => {
+                                log::warn!(
+                                    "Entity {} has a mesh without a source, it is not saved",
+                                    entity.id()
+                                );
+                                None
+                            }
+                        }),
+                        skin.map(|skin| {
+                            (
+                                skin.joints.clone(),
+                                skin.inverse_bind_matrices
+                                    .iter()
+                                    .map(|matrix| (*matrix).into())
+                                    .collect::<Vec<_>>(),
+                            )
+                        }),
+                        camera.map(|camera| {
+                            (
+                                camera.camera_type,
+                                camera.output.clone(),
+                                camera.fov,
+                                [camera.near, camera.far],
+                            )
+                        }),
+                        controller.map(ControllerData::new),
+                        light.copied(),
+                        prefab.cloned(),
+                    )
+                })
+            })
+            .collect::<Vec<_>>();
+
+        // Parents are stored as indices into the entity list
+        let entities = entities
+            .iter()
+            .map(
+                |(entity, name, transform, render, skin, camera, controller, light, prefab)| {
+                let transform = transform.as_ref().map(|(transform, parent)| {
+                    let parent = parent.and_then(|parent| {
+                        let index = entities.iter().position(|other| other.0 == parent);
+
+                        if index.is_none() {
+                            log::warn!(
+                                "Entity {} has a parent outside of the saved entities, it is saved as a root",
+                                entity.id()
+                            );
+                        }
+
+                        index
+                    });
+
+                    TransformData {
+                        parent,
+                        ..transform.clone()
+                    }
+                });
+
+                // A skin is only drawn with its joints, the mesh goes with it
+                let skin = skin.as_ref().and_then(|(joints, inverse_bind_matrices)| {
+                    let joints = joints
+                        .iter()
+                        .map(|joint| entities.iter().position(|other| other.0 == *joint))
+                        .collect::<Option<Vec<_>>>();
+
+                    if joints.is_none() {
+                        log::warn!(
+                            "Entity {} has joints outside of the saved entities, its skinned mesh is not saved",
+                            entity.id()
+                        );
+                    }
+
+                    joints.map(|joints| SkinData {
+                        joints,
+                        inverse_bind_matrices: inverse_bind_matrices.clone(),
+                    })
+                });
+                let render = match (skin.as_ref(), render.as_ref()) {
+                    (None, Some(render)) if render.mesh.vertex_type()
+                        == crate::vertex_type::VertexType::Skinned => None,
+                    _ => render.clone(),
+                };
+
+                EntityData {
+                    name: name.clone(),
+                    transform,
+                    render,
+                    skin,
+                    camera: camera.as_ref().map(|(camera_type, _, _, _)| *camera_type),
+                    camera_output: camera.as_ref().map(|(_, output, _, _)| output.clone()),
+                    camera_fov: camera.as_ref().map(|(_, _, fov, _)| *fov),
+                    camera_clip: camera.as_ref().map(|(_, _, _, clip)| *clip),
+                    controller: *controller,
+                    light: *light,
+                    prefab: prefab.clone(),
+                }
+            },
+            )
+            .collect();
+
+        Self {
+            materials: materials
+                .iter()
+                .map(|(name, material)| (name.clone(), material.uniform.color))
+                .collect(),
+            entities,
+        }
+    }
+
+    fn validate_parents(&self) -> Result<(), SceneError> {
+        let parent = |index: usize| {
+            self.entities[index]
+                .transform
+                .as_ref()
+                .and_then(|transform| transform.parent)
+        };
+
+        (0..self.entities.len()).try_for_each(|entity| {
+            // Walking up more steps than there are entities means there is a cycle
+            let mut current = entity;
+            for _ in 0..=self.entities.len() {
+                match parent(current) {
+                    Some(next) if next < self.entities.len() && next != current => {
+                        if self.entities[next].transform.is_none() {
+                            return Err(SceneError::InvalidParent {
+                                entity,
+                                parent: next,
+                            });
+                        }
+                        current = next;
+                    }
+                    Some(next) => {
+                        return Err(SceneError::InvalidParent {
+                            entity,
+                            parent: next,
+                        })
+                    }
+                    None => return Ok(()),
+                }
+            }
+
+            Err(SceneError::InvalidParent {
+                entity,
+                parent: parent(entity).unwrap_or_default(),
+            })
+        })
+    }
+
+    fn validate_joints(&self) -> Result<(), SceneError> {
+        self.entities
+            .iter()
+            .enumerate()
+            .try_for_each(|(entity, data)| {
+                let joints = data.skin.iter().flat_map(|skin| skin.joints.iter());
+
+                match joints.copied().find(|joint| *joint >= self.entities.len()) {
+                    Some(joint) => Err(SceneError::InvalidJoint { entity, joint }),
+                    None => Ok(()),
+                }
+            })
+    }
+
+    // Spawns the scene into `world`, GPU buffers are created again from the mesh sources
+    pub fn instantiate(
+        &self,
+        world: &mut hecs::World,
+        device: &std::sync::Arc<wgpu::Device>,
+        window: &std::sync::Arc<winit::window::Window>,
+        skin_layout: &wgpu::BindGroupLayout,
+    ) -> Result<SpawnedScene, SceneError> {
+        self.spawn(world, Some((device, window, skin_layout)))
+    }
+
+    // Spawns everything but the renders, the skins and the cameras, for running the game logic
+    // without a GPU. Camera entities are still listed in the spawned scene
+    pub fn instantiate_headless(
+        &self,
+        world: &mut hecs::World,
+    ) -> Result<SpawnedScene, SceneError> {
+        self.spawn(world, None)
+    }
+
+    fn spawn(
+        &self,
+        world: &mut hecs::World,
+        gpu: Option<(
+            &std::sync::Arc<wgpu::Device>,
+            &std::sync::Arc<winit::window::Window>,
+            &wgpu::BindGroupLayout,
+        )>,
+    ) -> Result<SpawnedScene, SceneError> {
+        self.validate_parents()?;
+        self.validate_joints()?;
+
+        let mut transforms = self
+            .entities
+            .iter()
+            .map(|entity| {
+                entity.transform.as_ref().map(|transform| {
+                    crate::component::TransformBuild::new()
+                        .with_position(transform.position.into())
+                        .with_q_rotation(nalgebra_glm::quat(
+                            transform.rotation[0],
+                            transform.rotation[1],
+                            transform.rotation[2],
+                            transform.rotation[3],
+                        ))
+                        .with_scale(transform.scale.into())
+                        .build()
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let renders = self
+            .entities
+            .iter()
+            .zip(transforms.iter_mut())
+            .map(|(entity, transform)| {
+                let Some((device, _, _)) = gpu else {
+                    return Ok(None);
+                };
+
+                entity
+                    .render
+                    .as_ref()
+                    .map(|render| {
+                        let transform_buffer = transform.as_mut().and_then(|transform| {
+                            transform.create_buffer(device.as_ref());
+                            transform.buffer.clone()
+                        });
+
+                        render
+                            .mesh
+                            .create_render(
+                                device.as_ref(),
+                                render.pipeline.clone(),
+                                transform_buffer,
+                            )
+                            .map(|mesh| {
+                                mesh.with_material(render.material.clone())
+                                    .with_texture(render.texture.clone())
+                            })
+                            .map_err(SceneError::Mesh)
+                    })
+                    .transpose()
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut spawned = SpawnedScene {
+            entities: vec![],
+            cameras: vec![],
+        };
+
+        self.entities.iter().zip(transforms).zip(renders).for_each(
+            |((data, transform), render)| {
+                let mut builder = hecs::EntityBuilder::new();
+
+                if let Some(name) = data.name.as_ref() {
+                    builder.add(crate::component::Name(name.clone()));
+                }
+                if let Some(transform) = transform {
+                    builder.add(transform);
+                }
+                if let Some(render) = render {
+                    builder.add(render);
+                }
+                if let Some(light) = data.light {
+                    builder.add(light);
+                }
+                if let Some(prefab) = data.prefab.clone() {
+                    builder.add(prefab);
+                }
+                if let Some(controller) = data.controller {
+                    builder.add(controller.build());
+                }
+
+                if let (Some(camera_type), Some((device, window, _))) = (data.camera, gpu) {
+                    let camera =
+                        crate::component::Camera::new(camera_type, window.clone(), device.clone())
+                            .with_output(data.camera_output.clone().unwrap_or_default());
+                    let camera = match data.camera_fov {
+                        Some(fov) => camera.with_fov(fov),
+                        None => camera,
+                    };
+                    builder.add(match data.camera_clip {
+                        Some([near, far]) => camera.with_clip_planes(near, far),
+                        None => camera,
+                    });
+                }
+
+                let entity = world.spawn(builder.build());
+
+                if data.camera.is_some() {
+                    spawned.cameras.push(entity);
+                }
+                spawned.entities.push(entity);
+            },
+        );
+
+        self.entities
+            .iter()
+            .zip(spawned.entities.iter())
+            .try_for_each(|(data, entity)| {
+                match data
+                    .transform
+                    .as_ref()
+                    .and_then(|transform| transform.parent)
+                {
+                    Some(parent) => {
+                        crate::hierarchy::set_parent(world, *entity, Some(spawned.entities[parent]))
+                            .map_err(SceneError::from)
+                    }
+                    None => Ok(()),
+                }
+            })?;
+
+        // Joints may come after their skin in the file
+        if let Some((device, _, skin_layout)) = gpu {
+            self.entities
+                .iter()
+                .zip(spawned.entities.iter())
+                .for_each(|(data, entity)| {
+                    let Some(skin) = data.skin.as_ref() else {
+                        return;
+                    };
+
+                    let skin = crate::component::Skin::new(
+                        device.as_ref(),
+                        skin_layout,
+                        skin.joints
+                            .iter()
+                            .map(|joint| spawned.entities[*joint])
+                            .collect(),
+                        skin.inverse_bind_matrices
+                            .iter()
+                            .map(|matrix| (*matrix).into())
+                            .collect(),
+                    );
+                    if let Ok(render) =
+                        world.query_one_mut::<&mut crate::component::Render>(*entity)
+                    {
+                        render.bind_group = Some(skin.bind_group.clone());
+                    }
+                    world.insert_one(*entity, skin).unwrap();
+                });
+        }
+
+        Ok(spawned)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn controllers_round_trip() {
+        let mut world = hecs::World::new();
+        let entity = world.spawn((
+            crate::component::Name::new("Camera"),
+            crate::component::TransformBuild::new()
+                .with_position(nalgebra_glm::vec3(1.0, 2.0, 3.0))
+                .build(),
+            crate::component::CameraController::new(crate::component::ControllerMode::orbit(
+                nalgebra_glm::vec3(0.0, 1.0, 0.0),
+                5.0,
+            ))
+            .with_speed(7.0)
+            .with_smoothing(0.2),
+        ));
+
+        let file = SceneFile::from_entities(&world, &[entity], &HashMap::new());
+        let content = ron::ser::to_string_pretty(&file, ron::ser::PrettyConfig::default()).unwrap();
+        let file: SceneFile = ron::from_str(&content).unwrap();
+
+        let mut loaded = hecs::World::new();
+        let spawned = file.instantiate_headless(&mut loaded).unwrap();
+        let controller = *loaded
+            .get::<&crate::component::CameraController>(spawned.entities[0])
+            .unwrap();
+
+        assert_eq!(
+            controller.mode,
+            crate::component::ControllerMode::orbit(nalgebra_glm::vec3(0.0, 1.0, 0.0), 5.0)
+        );
+        assert_eq!(controller.speed, 7.0);
+        assert_eq!(controller.smoothing, 0.2);
+    }
+
+    #[test]
+    fn invalid_parents_are_rejected() {
+        let file = SceneFile {
+            materials: BTreeMap::new(),
+            entities: vec![EntityData {
+                transform: Some(TransformData {
+                    position: [0.0; 3],
+                    rotation: [0.0, 0.0, 0.0, 1.0],
+                    scale: [1.0; 3],
+                    parent: Some(0),
+                }),
+                ..Default::default()
+            }],
+        };
+
+        assert!(matches!(
+            file.instantiate_headless(&mut hecs::World::new()),
+            Err(SceneError::InvalidParent {
+                entity: 0,
+                parent: 0
+            })
+        ));
+    }
+
+    #[test]
+    fn invalid_joints_are_rejected() {
+        let file = SceneFile {
+            materials: BTreeMap::new(),
+            entities: vec![EntityData {
+                skin: Some(SkinData {
+                    joints: vec![0, 3],
+                    inverse_bind_matrices: vec![nalgebra_glm::Mat4::identity().into(); 2],
+                }),
+                ..Default::default()
+            }],
+        };
+
+        assert!(matches!(
+            file.instantiate_headless(&mut hecs::World::new()),
+            Err(SceneError::InvalidJoint {
+                entity: 0,
+                joint: 3
+            })
+        ));
+    }
+}
@@ -0,0 +1,18 @@
+// Prepended to every shader by `crate::pipeline`, written once per camera and frame
+struct CameraUniform {
+    // Projection * view with the depth remapped to [0, 1]
+    view_proj: mat4x4<f32>,
+    view: mat4x4<f32>,
+    projection: mat4x4<f32>,
+    inverse_view: mat4x4<f32>,
+    inverse_projection: mat4x4<f32>,
+    position: vec3<f32>,
+    // Seconds since the engine started
+    time: f32,
+    // Pixels
+    viewport_size: vec2<f32>,
+    frame: u32,
+}
+
+@group(0) @binding(0)
+var<uniform> camera: CameraUniform;
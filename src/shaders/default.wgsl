@@ -1,17 +1,9 @@
-const OPENGL_TO_WGPU_MATRIX: mat4x4<f32> = mat4x4<f32>(
-    vec4<f32>(1.0, 0.0, 0.0, 0.0),
-    vec4<f32>(0.0, 1.0, 0.0, 0.0),
-    vec4<f32>(0.0, 0.0, 0.5, 0.0),
-    vec4<f32>(0.0, 0.0, 0.5, 1.0),
-);
-
-struct CameraUniform {
-    projection: mat4x4<f32>,
-    view: mat4x4<f32>,
+struct MaterialUniform {
+    color: vec4<f32>,
 }
 
-@group(0) @binding(0)
-var<uniform> camera: CameraUniform;
+@group(1) @binding(0)
+var<uniform> material: MaterialUniform;
 
 struct VertexInput {
     @location(0) position: vec3<f32>,
@@ -42,7 +34,7 @@ fn v_main(model: VertexInput, transform: TransformInput) -> VertexOutput {
 
     var out: VertexOutput;
 
-    out.clip_position = OPENGL_TO_WGPU_MATRIX * camera.projection * camera.view * transform_matrix * vec4<f32>(model.position, 1.0);
+    out.clip_position = camera.view_proj * transform_matrix * vec4<f32>(model.position, 1.0);
     out.color = model.color;
     return out;
 }
@@ -50,5 +42,5 @@ fn v_main(model: VertexInput, transform: TransformInput) -> VertexOutput {
 
 @fragment
 fn f_main(in: VertexOutput) -> @location(0) vec4<f32> {
-    return vec4<f32>(in.color, 1.0);
+    return vec4<f32>(in.color * material.color.rgb, material.color.a);
 }
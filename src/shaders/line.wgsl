@@ -1,11 +1,3 @@
-struct CameraUniform {
-    projection: mat4x4<f32>,
-    view: mat4x4<f32>,
-};
-
-@group(0) @binding(0)
-var<uniform> camera: CameraUniform;
-
 struct LineVertexInput {
     @location(0) position: vec3<f32>,
     @location(1) color: vec3<f32>,
@@ -16,19 +8,12 @@ struct LineVertexOutput {
     @location(0) color: vec3<f32>,
 };
 
-const OPENGL_TO_WGPU_MATRIX: mat4x4<f32> = mat4x4<f32>(
-    vec4<f32>(1.0, 0.0, 0.0, 0.0),
-    vec4<f32>(0.0, 1.0, 0.0, 0.0),
-    vec4<f32>(0.0, 0.0, 0.5, 0.0),
-    vec4<f32>(0.0, 0.0, 0.5, 1.0),
-);
-
 @vertex
 fn v_main(
     model: LineVertexInput,
 ) -> LineVertexOutput {
     var out: LineVertexOutput;
-    out.clip_position = OPENGL_TO_WGPU_MATRIX * camera.projection * camera.view * vec4<f32>(model.position, 1.0);
+    out.clip_position = camera.view_proj * vec4<f32>(model.position, 1.0);
     out.color = model.color;
     return out;
 }
@@ -0,0 +1,35 @@
+struct PickingUniform {
+    id: u32,
+}
+
+@group(1) @binding(0)
+var<uniform> picking: PickingUniform;
+
+struct VertexInput {
+    @location(0) position: vec3<f32>,
+    @location(1) color: vec3<f32>,
+};
+
+struct TransformInput {
+    @location(5) matrix_0: vec4<f32>,
+    @location(6) matrix_1: vec4<f32>,
+    @location(7) matrix_2: vec4<f32>,
+    @location(8) matrix_3: vec4<f32>,
+};
+
+@vertex
+fn v_main(model: VertexInput, transform: TransformInput) -> @builtin(position) vec4<f32> {
+    let transform_matrix = mat4x4<f32>(
+        transform.matrix_0,
+        transform.matrix_1,
+        transform.matrix_2,
+        transform.matrix_3,
+    );
+
+    return camera.view_proj * transform_matrix * vec4<f32>(model.position, 1.0);
+}
+
+@fragment
+fn f_main() -> @location(0) u32 {
+    return picking.id;
+}
@@ -0,0 +1,57 @@
+struct MaterialUniform {
+    color: vec4<f32>,
+}
+
+@group(1) @binding(0)
+var<uniform> material: MaterialUniform;
+
+// Joint matrices in the space of the mesh, already multiplied by the inverse bind matrices
+@group(2) @binding(0)
+var<storage, read> joints: array<mat4x4<f32>>;
+
+struct VertexInput {
+    @location(0) position: vec3<f32>,
+    @location(1) color: vec3<f32>,
+    @location(2) joints: vec4<u32>,
+    @location(3) weights: vec4<f32>,
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) color: vec3<f32>,
+};
+
+struct TransformInput {
+    @location(5) matrix_0: vec4<f32>,
+    @location(6) matrix_1: vec4<f32>,
+    @location(7) matrix_2: vec4<f32>,
+    @location(8) matrix_3: vec4<f32>,
+};
+
+
+@vertex
+fn v_main(model: VertexInput, transform: TransformInput) -> VertexOutput {
+    let transform_matrix = mat4x4<f32>(
+        transform.matrix_0,
+        transform.matrix_1,
+        transform.matrix_2,
+        transform.matrix_3,
+    );
+
+    let skin_matrix = joints[model.joints.x] * model.weights.x
+        + joints[model.joints.y] * model.weights.y
+        + joints[model.joints.z] * model.weights.z
+        + joints[model.joints.w] * model.weights.w;
+
+    var out: VertexOutput;
+
+    out.clip_position = camera.view_proj * transform_matrix * skin_matrix * vec4<f32>(model.position, 1.0);
+    out.color = model.color;
+    return out;
+}
+
+
+@fragment
+fn f_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    return vec4<f32>(in.color * material.color.rgb, material.color.a);
+}
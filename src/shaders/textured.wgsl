@@ -0,0 +1,56 @@
+struct MaterialUniform {
+    color: vec4<f32>,
+}
+
+@group(1) @binding(0)
+var<uniform> material: MaterialUniform;
+
+@group(2) @binding(0)
+var texture: texture_2d<f32>;
+@group(2) @binding(1)
+var texture_sampler: sampler;
+
+struct VertexInput {
+    @location(0) position: vec3<f32>,
+    @location(1) color: vec3<f32>,
+    @location(2) uv: vec2<f32>,
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) color: vec3<f32>,
+    @location(1) uv: vec2<f32>,
+};
+
+struct TransformInput {
+    @location(5) matrix_0: vec4<f32>,
+    @location(6) matrix_1: vec4<f32>,
+    @location(7) matrix_2: vec4<f32>,
+    @location(8) matrix_3: vec4<f32>,
+};
+
+
+@vertex
+fn v_main(model: VertexInput, transform: TransformInput) -> VertexOutput {
+    let transform_matrix = mat4x4<f32>(
+        transform.matrix_0,
+        transform.matrix_1,
+        transform.matrix_2,
+        transform.matrix_3,
+    );
+
+    var out: VertexOutput;
+
+    out.clip_position = camera.view_proj * transform_matrix * vec4<f32>(model.position, 1.0);
+    out.color = model.color;
+    out.uv = model.uv;
+    return out;
+}
+
+
+@fragment
+fn f_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let texel = textureSample(texture, texture_sampler, in.uv);
+
+    return vec4<f32>(in.color * texel.rgb * material.color.rgb, texel.a * material.color.a);
+}
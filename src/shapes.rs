@@ -1,5 +1,200 @@
 use crate::vertex_type::*;
 
+// How a mesh was built, kept next to the `Render` so scenes can be saved and rebuilt
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub enum MeshSource {
+    File {
+        path: String,
+    },
+    Inline {
+        positions: Vec<[f32; 3]>,
+        colors: Vec<[f32; 3]>,
+        indices: Vec<u16>,
+    },
+    Cube {
+        size: f32,
+        color: [f32; 3],
+    },
+    Plane {
+        width: f32,
+        depth: f32,
+        color: [f32; 3],
+    },
+    Grid {
+        width: f32,
+        depth: f32,
+        m: u32,
+        n: u32,
+    },
+    MarchingSquares {
+        width: u16,
+        height: u16,
+    },
+    TexturedQuad {
+        width: f32,
+        height: f32,
+    },
+    // Drawn with a `crate::component::Skin` holding `joints` joints
+    SkinnedColumn {
+        height: f32,
+        width: f32,
+        rings: u16,
+        joints: u32,
+        color: [f32; 3],
+    },
+}
+
+impl MeshSource {
+    // What `create_render` builds, without building it
+    pub fn vertex_type(&self) -> VertexType {
+        match self {
+            MeshSource::Grid { .. } => VertexType::Line,
+            MeshSource::TexturedQuad { .. } => VertexType::Textured,
+            MeshSource::SkinnedColumn { .. } => VertexType::Skinned,
+            _ => VertexType::Default,
+        }
+    }
+
+    pub fn create_render(
+        &self,
+        device: &wgpu::Device,
+        pipeline: String,
+        transform_buffer: Option<std::sync::Arc<wgpu::Buffer>>,
+    ) -> Result<crate::component::Render, String> {
+        let render = match self {
+            MeshSource::File { path } => {
+                crate::component::Render::new(device, load_obj(path)?, pipeline, transform_buffer)
+            }
+            MeshSource::Inline {
+                positions,
+                colors,
+                indices,
+            } => crate::component::Render::new(
+                device,
+                (
+                    positions
+                        .iter()
+                        .zip(colors.iter())
+                        .map(|(position, color)| DefaultVertex {
+                            position: *position,
+                            color: *color,
+                        })
+                        .collect(),
+                    indices.clone(),
+                ),
+                pipeline,
+                transform_buffer,
+            ),
+            MeshSource::Cube { size, color } => crate::component::Render::new(
+                device,
+                create_cube(*size, *color),
+                pipeline,
+                transform_buffer,
+            ),
+            MeshSource::Plane {
+                width,
+                depth,
+                color,
+            } => crate::component::Render::new(
+                device,
+                create_plane(*width, *depth, *color),
+                pipeline,
+                transform_buffer,
+            ),
+            MeshSource::Grid { width, depth, m, n } => crate::component::Render::new(
+                device,
+                create_grid(*width, *depth, *m, *n),
+                pipeline,
+                transform_buffer,
+            ),
+            MeshSource::MarchingSquares { width, height } => crate::component::Render::new(
+                device,
+                create_quad_marching_squares(*width, *height),
+                pipeline,
+                transform_buffer,
+            ),
+            MeshSource::TexturedQuad { width, height } => crate::component::Render::new(
+                device,
+                create_textured_quad(*width, *height),
+                pipeline,
+                transform_buffer,
+            ),
+            MeshSource::SkinnedColumn {
+                height,
+                width,
+                rings,
+                joints,
+                color,
+            } => crate::component::Render::new(
+                device,
+                create_skinned_column(*height, *width, *rings, *joints, *color),
+                pipeline,
+                transform_buffer,
+            ),
+        };
+
+        Ok(render.with_source(self.clone()))
+    }
+}
+
+// Minimal Wavefront OBJ support, only positions and faces are read
+pub fn load_obj(path: &str) -> Result<(Vec<DefaultVertex>, Vec<u16>), String> {
+    let content = std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+
+    let mut vertices = vec![];
+    let mut indices = vec![];
+
+    for (number, line) in content.lines().enumerate() {
+        let mut tokens = line.split_whitespace();
+
+        match tokens.next() {
+            Some("v") => {
+                let position = tokens
+                    .take(3)
+                    .map(|token| token.parse::<f32>())
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(|e| format!("{}:{}: {}", path, number + 1, e))?;
+
+                if position.len() != 3 {
+                    return Err(format!("{}:{}: expected 3 coordinates", path, number + 1));
+                }
+
+                vertices.push(DefaultVertex {
+                    position: [position[0], position[1], position[2]],
+                    color: [1.0, 1.0, 1.0],
+                });
+            }
+            Some("f") => {
+                // Faces are triangulated as a fan, texture and normal indices are ignored
+                let face = tokens
+                    .map(|token| {
+                        let index = token.split('/').next().unwrap_or_default();
+                        let index = index
+                            .parse::<i64>()
+                            .map_err(|e| format!("{}:{}: {}", path, number + 1, e))?;
+
+                        let index = if index < 0 {
+                            vertices.len() as i64 + index
+                        } else {
+                            index - 1
+                        };
+
+                        u16::try_from(index)
+                            .map_err(|_| format!("{}:{}: invalid index", path, number + 1))
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+
+                (1..face.len().saturating_sub(1)).for_each(|i| {
+                    indices.extend_from_slice(&[face[0], face[i], face[i + 1]]);
+                });
+            }
+            _ => {}
+        }
+    }
+
+    Ok((vertices, indices))
+}
+
 pub fn create_grid(width: f32, depth: f32, m: u32, n: u32) -> (Vec<LineVertex>, Vec<u16>) {
     let vertex_count: u32 = m * n;
     let face_count: u32 = (m - 1) * (n - 1) * 2;
@@ -48,6 +243,120 @@ pub fn create_grid(width: f32, depth: f32, m: u32, n: u32) -> (Vec<LineVertex>,
     (vertices, indices)
 }
 
+pub fn create_plane(width: f32, depth: f32, color: [f32; 3]) -> (Vec<DefaultVertex>, Vec<u16>) {
+    let half_width = width * 0.5;
+    let half_depth = depth * 0.5;
+
+    let vertices = vec![
+        DefaultVertex {
+            position: [-half_width, 0.0, -half_depth],
+            color,
+        },
+        DefaultVertex {
+            position: [half_width, 0.0, -half_depth],
+            color,
+        },
+        DefaultVertex {
+            position: [half_width, 0.0, half_depth],
+            color,
+        },
+        DefaultVertex {
+            position: [-half_width, 0.0, half_depth],
+            color,
+        },
+    ];
+
+    let indices = vec![0, 2, 1, 0, 3, 2];
+
+    (vertices, indices)
+}
+
+// Upright quad facing -Z, so the camera sees it the right way round when looking along +Z
+pub fn create_textured_quad(width: f32, height: f32) -> (Vec<TexturedVertex>, Vec<u16>) {
+    let half_width = width * 0.5;
+    let half_height = height * 0.5;
+
+    let vertices = [
+        ([half_width, -half_height, 0.0], [0.0, 1.0]),
+        ([-half_width, -half_height, 0.0], [1.0, 1.0]),
+        ([-half_width, half_height, 0.0], [1.0, 0.0]),
+        ([half_width, half_height, 0.0], [0.0, 0.0]),
+    ]
+    .into_iter()
+    .map(|(position, uv)| TexturedVertex {
+        position,
+        color: [1.0, 1.0, 1.0],
+        uv,
+    })
+    .collect();
+
+    let indices = vec![0, 1, 2, 0, 2, 3];
+
+    (vertices, indices)
+}
+
+pub fn create_cube(size: f32, color: [f32; 3]) -> (Vec<DefaultVertex>, Vec<u16>) {
+    let half = size * 0.5;
+
+    // Four vertices per face so every face can be shaded on its own later
+    let faces: [[[f32; 3]; 4]; 6] = [
+        [
+            [-half, -half, half],
+            [half, -half, half],
+            [half, half, half],
+            [-half, half, half],
+        ],
+        [
+            [half, -half, -half],
+            [-half, -half, -half],
+            [-half, half, -half],
+            [half, half, -half],
+        ],
+        [
+            [half, -half, half],
+            [half, -half, -half],
+            [half, half, -half],
+            [half, half, half],
+        ],
+        [
+            [-half, -half, -half],
+            [-half, -half, half],
+            [-half, half, half],
+            [-half, half, -half],
+        ],
+        [
+            [-half, half, half],
+            [half, half, half],
+            [half, half, -half],
+            [-half, half, -half],
+        ],
+        [
+            [-half, -half, -half],
+            [half, -half, -half],
+            [half, -half, half],
+            [-half, -half, half],
+        ],
+    ];
+
+    let mut vertices = vec![];
+    let mut indices = vec![];
+
+    faces.iter().for_each(|face| {
+        let start = vertices.len() as u16;
+
+        face.iter().for_each(|position| {
+            vertices.push(DefaultVertex {
+                position: *position,
+                color,
+            });
+        });
+
+        indices.extend_from_slice(&[start, start + 1, start + 2, start, start + 2, start + 3]);
+    });
+
+    (vertices, indices)
+}
+
 pub fn create_quad_marching_squares(
     width: u16,
     height: u16,
@@ -555,3 +864,51 @@ pub fn create_quad_marching_squares(
 
     (vertices, indices)
 }
+
+// Square column standing on the origin, bound to `joints` joints spaced evenly along
+// the y axis with the first one at the bottom
+pub fn create_skinned_column(
+    height: f32,
+    width: f32,
+    rings: u16,
+    joints: u32,
+    color: [f32; 3],
+) -> (Vec<SkinnedVertex>, Vec<u16>) {
+    let half = width * 0.5;
+    let corners = [[-half, -half], [half, -half], [half, half], [-half, half]];
+    let joint_spacing = height / joints.max(1) as f32;
+
+    let mut vertices = vec![];
+    let mut indices = vec![];
+
+    (0..=rings).for_each(|ring| {
+        let y = height * ring as f32 / rings.max(1) as f32;
+
+        let t = y / joint_spacing;
+        let joint = (t.floor() as u32).min(joints.max(1) - 1);
+        let next = (joint + 1).min(joints.max(1) - 1);
+        let weight = (t - joint as f32).clamp(0.0, 1.0);
+
+        corners.iter().for_each(|[x, z]| {
+            vertices.push(SkinnedVertex {
+                position: [*x, y, *z],
+                color,
+                joints: [joint, next, 0, 0],
+                weights: [1.0 - weight, weight, 0.0, 0.0],
+            });
+        });
+    });
+
+    (0..rings).for_each(|ring| {
+        let start = ring * 4;
+
+        (0..4).for_each(|side| {
+            let a = start + side;
+            let b = start + (side + 1) % 4;
+
+            indices.extend_from_slice(&[a, b, b + 4, a, b + 4, a + 4]);
+        });
+    });
+
+    (vertices, indices)
+}
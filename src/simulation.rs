@@ -0,0 +1,38 @@
+// Game logic of one frame. The engine and the headless replays both run it, so the same
+// input and the same step give the same transforms
+pub fn step(world: &mut hecs::World, input: &crate::component::input::Input, delta: f32) {
+    crate::animation::update(world, delta);
+
+    let camera_input = camera_input(input, delta);
+    world
+        .query_mut::<(
+            &mut crate::component::CameraController,
+            &mut crate::component::Transform,
+            Option<&mut crate::component::Camera>,
+        )>()
+        .into_iter()
+        .for_each(|(_, (controller, transform, camera))| {
+            controller.update(transform, camera, &camera_input, delta);
+        });
+
+    crate::hierarchy::propagate(world);
+}
+
+// The mouse only moves while the cursor is grabbed, sticks turn at a rate instead of by a
+// distance
+pub fn camera_input(
+    input: &crate::component::input::Input,
+    delta: f32,
+) -> crate::component::CameraInput {
+    crate::component::CameraInput {
+        movement: nalgebra_glm::vec3(
+            input.axis("Move X"),
+            input.axis("Move Y"),
+            input.axis("Move Z"),
+        ),
+        roll: input.axis("Roll"),
+        look: nalgebra_glm::vec2(input.axis("Look X"), input.axis("Look Y"))
+            + nalgebra_glm::vec2(input.axis("Turn X"), input.axis("Turn Y")) * delta,
+        zoom: input.axis("Zoom"),
+    }
+}
@@ -0,0 +1,110 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::animation::AnimationClip;
+use crate::component::{AnimationPlayer, GlobalTransform, Name, Skin};
+
+// Skins are built in code, like the column of `crate::shapes::create_skinned_column`.
+// Rigged meshes can't be imported yet, that needs a glTF loader filling `Skin` and the clips
+
+// One clip per joint, matched by the `Name` of the joint entities
+#[derive(Debug, Clone, Default)]
+pub struct SkeletalClip {
+    pub name: String,
+    pub joints: HashMap<String, Arc<AnimationClip>>,
+}
+
+impl SkeletalClip {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_owned(),
+            joints: HashMap::new(),
+        }
+    }
+
+    pub fn with_joint(mut self, joint: &str, clip: AnimationClip) -> Self {
+        self.joints.insert(joint.to_owned(), Arc::new(clip));
+        self
+    }
+
+    // Plays the clip on every joint of `skin`, joints without a track keep their pose
+    pub fn play(&self, world: &mut hecs::World, skin: hecs::Entity, crossfade: f32) {
+        let joints = match world.get::<&Skin>(skin) {
+            Ok(skin) => skin.joints.clone(),
+            Err(_) => return,
+        };
+
+        joints.into_iter().for_each(|joint| {
+            let track = world
+                .get::<&Name>(joint)
+                .ok()
+                .and_then(|name| self.joints.get(&name.0).cloned());
+
+            let Some(track) = track else {
+                return;
+            };
+
+            match world.query_one_mut::<&mut AnimationPlayer>(joint) {
+                Ok(player) => player.crossfade(track, crossfade),
+                Err(_) => world
+                    .insert_one(joint, AnimationPlayer::new().with_clip(track))
+                    .unwrap_or_default(),
+            }
+        });
+    }
+}
+
+// Inverse bind matrices of the current pose, `propagate` must have run since the
+// joints were placed
+pub fn inverse_bind_matrices(
+    world: &hecs::World,
+    mesh: hecs::Entity,
+    joints: &[hecs::Entity],
+) -> Vec<nalgebra_glm::Mat4> {
+    let mesh_matrix = world
+        .get::<&GlobalTransform>(mesh)
+        .map(|global| global.matrix)
+        .unwrap_or_else(|_| nalgebra_glm::Mat4::identity());
+
+    joints
+        .iter()
+        .map(|joint| {
+            let joint_matrix = world
+                .get::<&GlobalTransform>(*joint)
+                .map(|global| global.matrix)
+                .unwrap_or_else(|_| nalgebra_glm::Mat4::identity());
+
+            nalgebra_glm::inverse(&(nalgebra_glm::inverse(&mesh_matrix) * joint_matrix))
+        })
+        .collect()
+}
+
+// Writes the joint matrices of every skin, in the space of the skinned mesh
+pub fn update(world: &hecs::World, queue: &wgpu::Queue) {
+    world
+        .query::<(&Skin, &GlobalTransform)>()
+        .iter()
+        .for_each(|(_, (skin, global))| {
+            let inverse_mesh = nalgebra_glm::inverse(&global.matrix);
+
+            let matrices = skin
+                .joints
+                .iter()
+                .zip(skin.inverse_bind_matrices.iter())
+                .map(|(joint, inverse_bind)| {
+                    let joint_matrix = world
+                        .get::<&GlobalTransform>(*joint)
+                        .map(|joint| joint.matrix)
+                        .unwrap_or_else(|_| nalgebra_glm::Mat4::identity());
+
+                    crate::component::transform::TransformRaw {
+                        transform: (inverse_mesh * joint_matrix * inverse_bind).into(),
+                    }
+                })
+                .collect::<Vec<_>>();
+
+            if !matrices.is_empty() {
+                queue.write_buffer(&skin.buffer, 0, bytemuck::cast_slice(&matrices));
+            }
+        });
+}
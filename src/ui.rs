@@ -0,0 +1,71 @@
+// What user interface callbacks can read and change, built for every frame
+pub struct UiContext<'a> {
+    // Queried with the component types exported from the crate root
+    pub world: &'a mut hecs::World,
+    pub input: &'a crate::component::input::Input,
+    pub time: &'a crate::component::FrameTime,
+    // The camera the window is seen through
+    pub camera: hecs::Entity,
+    pub editor_enabled: bool,
+    pub debug_draw: &'a mut crate::debug_draw::DebugDraw,
+    // Hover and selection changes of the last update
+    pub picking_events: &'a [crate::picking::PickingEvent],
+}
+
+pub type UiCallback = Box<dyn FnMut(&egui::Context, &mut UiContext)>;
+
+// Callbacks run in the order they were added, after the editor
+#[derive(Default)]
+pub struct UiCallbacks {
+    callbacks: Vec<(String, UiCallback)>,
+}
+
+impl UiCallbacks {
+    // A callback with the same name is replaced
+    pub fn add(&mut self, name: &str, callback: UiCallback) {
+        match self.callbacks.iter_mut().find(|(other, _)| other == name) {
+            Some((_, existing)) => *existing = callback,
+            None => self.callbacks.push((name.to_owned(), callback)),
+        }
+    }
+
+    pub fn remove(&mut self, name: &str) -> bool {
+        let count = self.callbacks.len();
+        self.callbacks.retain(|(other, _)| other != name);
+
+        self.callbacks.len() != count
+    }
+
+    pub fn show(&mut self, ctx: &egui::Context, context: &mut UiContext) {
+        self.callbacks
+            .iter_mut()
+            .for_each(|(_, callback)| callback(ctx, context));
+    }
+}
+
+// Position and rotation of the camera in the top left corner
+pub fn debug_info(ctx: &egui::Context, context: &mut UiContext) {
+    let Ok(transform) = context
+        .world
+        .query_one_mut::<&crate::component::Transform>(context.camera)
+    else {
+        return;
+    };
+
+    egui::Area::new("debug_info")
+        .fixed_pos(egui::pos2(0.0, 0.0))
+        .show(ctx, |ui| {
+            ui.label(
+                egui::RichText::new(format!("position: {:.4?}", transform.get_position()))
+                    .background_color(egui::Color32::from_rgba_premultiplied(0, 0, 0, 160))
+                    .color(egui::Color32::WHITE)
+                    .size(20.0),
+            );
+            ui.label(
+                egui::RichText::new(format!("rotation: {:.4?}", transform.get_rotation()))
+                    .background_color(egui::Color32::from_rgba_premultiplied(0, 0, 0, 160))
+                    .color(egui::Color32::WHITE)
+                    .size(20.0),
+            );
+        });
+}
@@ -11,3 +11,68 @@ pub struct LineVertex {
     pub position: [f32; 3],
     pub color: [f32; 3],
 }
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct TexturedVertex {
+    pub position: [f32; 3],
+    pub color: [f32; 3],
+    pub uv: [f32; 2],
+}
+
+// Skinned by up to four joints, the weights should add up to one
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct SkinnedVertex {
+    pub position: [f32; 3],
+    pub color: [f32; 3],
+    pub joints: [u32; 4],
+    pub weights: [f32; 4],
+}
+
+// Layout of the vertices a pipeline reads, see `crate::pipeline::Pipeline::accepts`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VertexType {
+    Default,
+    Line,
+    Textured,
+    Skinned,
+}
+
+pub trait Vertex {
+    const TYPE: VertexType;
+
+    fn position(&self) -> [f32; 3];
+}
+
+impl Vertex for DefaultVertex {
+    const TYPE: VertexType = VertexType::Default;
+
+    fn position(&self) -> [f32; 3] {
+        self.position
+    }
+}
+
+impl Vertex for LineVertex {
+    const TYPE: VertexType = VertexType::Line;
+
+    fn position(&self) -> [f32; 3] {
+        self.position
+    }
+}
+
+impl Vertex for SkinnedVertex {
+    const TYPE: VertexType = VertexType::Skinned;
+
+    fn position(&self) -> [f32; 3] {
+        self.position
+    }
+}
+
+impl Vertex for TexturedVertex {
+    const TYPE: VertexType = VertexType::Textured;
+
+    fn position(&self) -> [f32; 3] {
+        self.position
+    }
+}